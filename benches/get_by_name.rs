@@ -0,0 +1,87 @@
+//! Throughput of the `get_by_name` lookup paths with many concurrent readers.
+//!
+//! The crate has no library target, so the cache modules are compiled into the benchmark directly,
+//! their unit tests are compiled too but not run, so their imports are unused.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use actix_web::Either;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/cache.rs"]
+mod cache;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/cache_backend.rs"]
+mod cache_backend;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/metrics.rs"]
+mod metrics;
+
+use cache::{CacheNamespace, Snapshot};
+
+const POKEMON_COUNT: usize = 1_000;
+const LOOKUPS_PER_TASK: usize = 1_000;
+
+static NAMES: LazyLock<Vec<String>> =
+    LazyLock::new(|| (0..POKEMON_COUNT).map(|i| format!("pokemon-{i}")).collect());
+static DATASET: LazyLock<Snapshot<HashMap<String, usize>>> = LazyLock::new(Snapshot::default);
+static POKEMONS: LazyLock<CacheNamespace<String, usize>> =
+    LazyLock::new(|| CacheNamespace::new("pokemon"));
+
+async fn dataset_lookup(task: usize) -> usize {
+    let mut found = 0;
+    for i in 0..LOOKUPS_PER_TASK {
+        let name = &NAMES[(task * 31 + i) % POKEMON_COUNT];
+        if let Some(dataset) = DATASET.get() {
+            found += dataset.get(name).copied().unwrap_or_default();
+        }
+    }
+    found
+}
+
+async fn namespace_lookup(task: usize) -> usize {
+    let mut found = 0;
+    for i in 0..LOOKUPS_PER_TASK {
+        let name = &NAMES[(task * 31 + i) % POKEMON_COUNT];
+        if let Either::Left(value) = POKEMONS.entry(name.clone()).get_or_write_lock().await {
+            found += *value;
+        }
+    }
+    found
+}
+
+fn bench_get_by_name(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    DATASET.set_with_ttl(NAMES.iter().cloned().zip(0..).collect(), None);
+    runtime.block_on(async {
+        for (i, name) in NAMES.iter().enumerate() {
+            POKEMONS
+                .entry(name.clone())
+                .write()
+                .await
+                .set_with_ttl(i, None);
+        }
+    });
+
+    let mut group = c.benchmark_group("get_by_name");
+    for tasks in [1, 8, 64] {
+        group.throughput(Throughput::Elements((tasks * LOOKUPS_PER_TASK) as u64));
+        group.bench_with_input(BenchmarkId::new("dataset", tasks), &tasks, |b, &tasks| {
+            b.to_async(&runtime).iter(|| async move {
+                let handles = (0..tasks).map(|task| tokio::spawn(dataset_lookup(task)));
+                futures::future::join_all(handles).await
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("namespace", tasks), &tasks, |b, &tasks| {
+            b.to_async(&runtime).iter(|| async move {
+                let handles = (0..tasks).map(|task| tokio::spawn(namespace_lookup(task)));
+                futures::future::join_all(handles).await
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_by_name);
+criterion_main!(benches);
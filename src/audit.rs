@@ -0,0 +1,387 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use actix_web::{http::StatusCode, HttpResponse};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{models::audit::AuditRecord, req_util::response_from_error};
+
+pub static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+const FILE_PREFIX: &str = "draws-";
+const FILE_EXTENSION: &str = ".ndjson";
+
+#[derive(Deserialize, IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+pub struct AuditFilter {
+    /// Only draws of this subject
+    pub sub: Option<String>,
+    /// Only draws at or after this unix timestamp
+    pub from: Option<u64>,
+    /// Only draws before this unix timestamp
+    pub to: Option<u64>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        let other_sub = matches!(&self.sub, Some(sub) if record.sub.as_ref() != Some(sub));
+        let too_early = matches!(self.from, Some(from) if record.timestamp < from);
+        let too_late = matches!(self.to, Some(to) if record.timestamp >= to);
+        !other_sub && !too_early && !too_late
+    }
+}
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+#[derive(Clone)]
+struct AuditFile {
+    path: PathBuf,
+    /// Id of the first entry in the file, files are never empty except the one being written to
+    first_id: u64,
+}
+
+struct AuditLogState {
+    file: File,
+    size: u64,
+    next_id: u64,
+    files: Vec<AuditFile>,
+}
+
+/// Append-only log of draws stored as newline delimited JSON files.
+///
+/// New file is started on every start and whenever the current one would exceed the maximal size,
+/// files are never modified after they are rotated nor removed.
+pub struct AuditLog {
+    dir: PathBuf,
+    max_file_size: u64,
+    state: Mutex<AuditLogState>,
+}
+
+fn file_index(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(FILE_PREFIX)?
+        .strip_suffix(FILE_EXTENSION)?
+        .parse()
+        .ok()
+}
+
+fn file_path(dir: &Path, index: u64) -> PathBuf {
+    dir.join(format!("{FILE_PREFIX}{index:08}{FILE_EXTENSION}"))
+}
+
+fn first_id(path: &Path) -> io::Result<Option<u64>> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+    Ok(serde_json::from_str::<AuditRecord>(&line)
+        .ok()
+        .map(|record| record.id))
+}
+
+fn last_id(path: &Path) -> io::Result<Option<u64>> {
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+    // last line may be incomplete after a crash
+    Ok(content
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str::<AuditRecord>(line).ok())
+        .map(|record| record.id))
+}
+
+impl AuditLog {
+    pub fn open(dir: impl Into<PathBuf>, max_file_size: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut indexed = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                Some((file_index(&path)?, path))
+            })
+            .collect::<Vec<_>>();
+        indexed.sort_unstable_by_key(|(index, _)| *index);
+
+        let mut files = Vec::with_capacity(indexed.len() + 1);
+        for (_, path) in &indexed {
+            if let Some(first_id) = first_id(path)? {
+                files.push(AuditFile {
+                    path: path.clone(),
+                    first_id,
+                });
+            }
+        }
+        let next_id = match files.last() {
+            Some(file) => last_id(&file.path)?.unwrap_or(file.first_id) + 1,
+            None => 0,
+        };
+        let index = indexed
+            .last()
+            .map(|(index, _)| index + 1)
+            .unwrap_or_default();
+
+        let path = file_path(&dir, index);
+        let file = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        files.push(AuditFile {
+            path,
+            first_id: next_id,
+        });
+
+        Ok(Self {
+            dir,
+            max_file_size,
+            state: Mutex::new(AuditLogState {
+                file,
+                size: 0,
+                next_id,
+                files,
+            }),
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn get() -> Result<&'static AuditLog, HttpResponse> {
+        AUDIT_LOG.get().ok_or_else(|| {
+            response_from_error(
+                "Audit log is not available",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        })
+    }
+
+    /// Assigns id to the record and durably appends it to the log.
+    pub fn append(&self, mut record: AuditRecord) -> io::Result<u64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        record.id = state.next_id;
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        if state.size > 0 && state.size + line.len() as u64 > self.max_file_size {
+            let index = state
+                .files
+                .last()
+                .and_then(|file| file_index(&file.path))
+                .unwrap_or_default()
+                + 1;
+            let path = file_path(&self.dir, index);
+            state.file = OpenOptions::new()
+                .append(true)
+                .create_new(true)
+                .open(&path)?;
+            state.size = 0;
+            state.files.push(AuditFile {
+                path,
+                first_id: record.id,
+            });
+        }
+
+        state.file.write_all(&line)?;
+        state.file.sync_data()?;
+        state.size += line.len() as u64;
+        state.next_id += 1;
+        Ok(record.id)
+    }
+
+    /// Returns records with id greater or equal to `from_id` in order.
+    pub fn records(&self, from_id: u64) -> AuditRecords {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let start = state
+            .files
+            .partition_point(|file| file.first_id <= from_id)
+            .saturating_sub(1);
+        AuditRecords {
+            files: state.files[start..]
+                .iter()
+                .rev()
+                .map(|file| file.path.clone())
+                .collect(),
+            lines: None,
+            from_id,
+        }
+    }
+}
+
+/// Iterator over audit records which reads files lazily.
+pub struct AuditRecords {
+    /// Remaining files in reverse order
+    files: Vec<PathBuf>,
+    lines: Option<io::Lines<BufReader<File>>>,
+    from_id: u64,
+}
+
+impl Iterator for AuditRecords {
+    type Item = io::Result<AuditRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(lines) = &mut self.lines else {
+                let path = self.files.pop()?;
+                match File::open(&path) {
+                    Ok(file) => self.lines = Some(BufReader::new(file).lines()),
+                    Err(e) => return Some(Err(e)),
+                }
+                continue;
+            };
+
+            match lines.next() {
+                Some(Ok(line)) => match serde_json::from_str::<AuditRecord>(&line) {
+                    Ok(record) if record.id >= self.from_id => return Some(Ok(record)),
+                    // skips records before the requested one and incomplete lines left by a crash
+                    _ => continue,
+                },
+                Some(Err(e)) => return Some(Err(e)),
+                None => self.lines = None,
+            }
+        }
+    }
+}
+
+pub const CSV_HEADER: &str = "id,timestamp,draw_id,banner,count,sub,grants,server_seed_hash,client_seed,nonce,dataset_version,cost,result,reverted\n";
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats record as a CSV row, grants are separated by spaces and result is a JSON array.
+pub fn csv_row(record: &AuditRecord) -> String {
+    let seed = record.seed.as_ref();
+    let fields = [
+        record.id.to_string(),
+        record.timestamp.to_string(),
+        record.draw_id.clone(),
+        record.banner.clone(),
+        record.count.to_string(),
+        record.sub.clone().unwrap_or_default(),
+        record.grants.join(" "),
+        seed.map(|seed| seed.server_seed_hash.clone())
+            .unwrap_or_default(),
+        seed.map(|seed| seed.client_seed.clone())
+            .unwrap_or_default(),
+        seed.map(|seed| seed.nonce.to_string()).unwrap_or_default(),
+        record.dataset_version.clone(),
+        record.cost.to_string(),
+        serde_json::to_string(&record.result).unwrap_or_default(),
+        record.reverted.to_string(),
+    ];
+    let mut row = fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push('\n');
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::audit::{AuditPokemon, AuditSeed},
+        test_util::TempDir,
+    };
+
+    fn record(sub: &str, timestamp: u64) -> AuditRecord {
+        AuditRecord {
+            id: 99,
+            timestamp,
+            draw_id: "d1".into(),
+            banner: "get_random".into(),
+            count: 1,
+            sub: Some(sub.into()),
+            grants: vec!["svc::pokemon_api::route::/pokemon/get_random".into()],
+            seed: None,
+            dataset_version: "v1".into(),
+            cost: 0,
+            result: vec![AuditPokemon {
+                name: "pikachu".into(),
+                shiny: false,
+            }],
+            reverted: false,
+        }
+    }
+
+    fn ids(records: AuditRecords) -> Vec<u64> {
+        records.map(|record| record.unwrap().id).collect()
+    }
+
+    #[test]
+    fn records_are_appended_and_read_across_rotations() {
+        let dir = TempDir::new();
+        let log = AuditLog::open(&*dir, 300).unwrap();
+        for id in 0..5 {
+            assert_eq!(log.append(record("ash", id)).unwrap(), id);
+        }
+        assert!(fs::read_dir(&dir).unwrap().count() > 2);
+        assert_eq!(ids(log.records(0)), [0, 1, 2, 3, 4]);
+        assert_eq!(ids(log.records(3)), [3, 4]);
+        assert_eq!(ids(log.records(5)), [] as [u64; 0]);
+    }
+
+    #[test]
+    fn ids_continue_after_restart_and_incomplete_lines_are_skipped() {
+        let dir = TempDir::new();
+        {
+            let log = AuditLog::open(&*dir, DEFAULT_MAX_FILE_SIZE).unwrap();
+            log.append(record("ash", 1)).unwrap();
+            log.append(record("ash", 2)).unwrap();
+            let mut file = OpenOptions::new()
+                .append(true)
+                .open(file_path(&dir, 0))
+                .unwrap();
+            file.write_all(br#"{"id":2,"timesta"#).unwrap();
+        }
+
+        let log = AuditLog::open(&*dir, DEFAULT_MAX_FILE_SIZE).unwrap();
+        assert_eq!(log.append(record("gary", 3)).unwrap(), 2);
+        assert_eq!(ids(log.records(0)), [0, 1, 2]);
+    }
+
+    #[test]
+    fn filter_matches_sub_and_time_range() {
+        let filter = AuditFilter {
+            sub: Some("ash".into()),
+            from: Some(10),
+            to: Some(20),
+        };
+        assert!(filter.matches(&record("ash", 10)));
+        assert!(!filter.matches(&record("ash", 9)));
+        assert!(!filter.matches(&record("ash", 20)));
+        assert!(!filter.matches(&record("gary", 15)));
+        assert!(AuditFilter::default().matches(&record("gary", 0)));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        let mut record = record("ash, ketchum", 10);
+        record.grants.push("admin".into());
+        record.seed = Some(AuditSeed {
+            server_seed_hash: "hash".into(),
+            client_seed: "say \"hi\"".into(),
+            nonce: 7,
+        });
+        assert_eq!(
+            csv_row(&record),
+            "99,10,d1,get_random,1,\"ash, ketchum\",svc::pokemon_api::route::/pokemon/get_random admin,hash,\"say \"\"hi\"\"\",7,v1,0,\"[{\"\"name\"\":\"\"pikachu\"\",\"\"shiny\"\":false}]\",false\n"
+        );
+    }
+}
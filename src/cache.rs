@@ -1,129 +1,1069 @@
 use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
-    marker::PhantomData,
+    borrow::Borrow,
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
+    fmt::Display,
+    future::Future,
+    hash::{BuildHasher, Hash},
+    io,
     ops::Deref,
-    sync::{Arc, LazyLock},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex, MutexGuard, OnceLock,
+    },
+    time::{Duration, Instant},
 };
 
 use actix_web::Either;
-use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+use arc_swap::ArcSwapOption;
+use futures::{future::BoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{OwnedMutexGuard, OwnedRwLockWriteGuard, RwLock};
 
+use crate::{cache_backend::backend, metrics};
+
+/// Registry of all cache namespaces, namespaces have to be registered at startup.
 pub static CACHE: LazyLock<Cache> = LazyLock::new(Cache::default);
 
-#[derive(Default)]
-pub struct Cache {
-    #[allow(clippy::type_complexity)]
-    inner: Arc<
-        std::sync::RwLock<
-            HashMap<(String, TypeId), Arc<RwLock<Option<Box<dyn Any + Send + Sync>>>>>,
-        >,
-    >,
+/// TTL of entries fetched from the remote api, entries never expire when not set.
+pub static DEFAULT_TTL: OnceLock<Duration> = OnceLock::new();
+
+/// Age after which entries looked up with revalidation are refreshed in the background.
+pub static SOFT_TTL: OnceLock<Duration> = OnceLock::new();
+
+pub const DEFAULT_SOFT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long stale entry is served before fetching it again when the remote api failed.
+pub const STALE_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Maximal number of entries in a namespace unless configured otherwise.
+pub const DEFAULT_NAMESPACE_LIMIT: usize = 10_000;
+
+pub fn default_ttl() -> Option<Duration> {
+    DEFAULT_TTL.get().copied()
 }
 
-pub struct CacheEntry<T> {
-    inner: Arc<RwLock<Option<Box<dyn Any + Send + Sync>>>>,
-    any_type: PhantomData<T>,
+pub fn soft_ttl() -> Duration {
+    SOFT_TTL.get().copied().unwrap_or(DEFAULT_SOFT_TTL)
 }
 
-pub struct ReadCacheEntryValue<T> {
-    inner: OwnedRwLockReadGuard<Option<Box<dyn Any + Send + Sync>>>,
-    any_type: PhantomData<T>,
+/// Parses comma separated `namespace=limit` entries.
+pub fn parse_limits(value: &str) -> Result<Vec<(String, usize)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let Some((namespace, limit)) = entry.split_once('=') else {
+                return Err(format!("Entry '{entry}' is missing '='"));
+            };
+            match limit.trim().parse::<usize>() {
+                Ok(limit) if limit > 0 => Ok((namespace.trim().to_string(), limit)),
+                Ok(_) => Err(format!("Limit of '{entry}' must be positive")),
+                Err(e) => Err(format!("Limit of '{entry}' is invalid: {e}")),
+            }
+        })
+        .collect()
 }
 
-impl<T: 'static> ReadCacheEntryValue<T> {
-    pub fn get(&self) -> Option<&T> {
-        let data = (*self.inner).as_ref()?;
-        let data = data.downcast_ref::<T>().unwrap();
-        Some(data)
+/// Shared reference to a cached value, it stays valid even when the entry is replaced or evicted.
+pub struct RefVal<V>(Arc<V>);
+
+impl<V> Clone for RefVal<V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
     }
 }
 
-#[allow(dead_code)]
-impl<T: Send + Sync + 'static> WriteCacheEntryValue<T> {
-    pub fn get(&self) -> Option<&T> {
-        let data = (*self.inner).as_ref()?;
-        let data = data.downcast_ref::<T>().unwrap();
-        Some(data)
+impl<V> Deref for RefVal<V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+struct CachedValue<V> {
+    value: RefVal<V>,
+    expires_at: Option<Instant>,
+    /// When the value should be refreshed in the background, see [`CacheEntry::get_or_write_lock_revalidating`]
+    refresh_at: Instant,
+    /// Held while the value is being refreshed in the background
+    refresh: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<V> CachedValue<V> {
+    fn new(value: RefVal<V>, ttl: Option<Duration>) -> Self {
+        let now = Instant::now();
+        Self {
+            value,
+            expires_at: ttl.map(|ttl| now + ttl),
+            refresh_at: now + soft_ttl(),
+            refresh: Arc::default(),
+        }
     }
 
-    pub fn set(&mut self, val: T) {
-        *self.inner = Some(Box::new(val));
+    fn is_fresh(&self) -> bool {
+        !matches!(self.expires_at, Some(expires_at) if expires_at <= Instant::now())
+    }
+
+    fn needs_refresh(&self) -> bool {
+        self.refresh_at <= Instant::now()
     }
 }
 
-pub struct WriteCacheEntryValue<T> {
-    inner: OwnedRwLockWriteGuard<Option<Box<dyn Any + Send + Sync>>>,
-    any_type: PhantomData<T>,
+/// Single value which is read without any locking and replaced atomically.
+///
+/// Readers keep using the value they loaded while a new one is swapped in, refreshes are serialized.
+pub struct Snapshot<V> {
+    current: ArcSwapOption<CachedValue<V>>,
+    refresh: tokio::sync::Mutex<()>,
+}
+
+impl<V> Default for Snapshot<V> {
+    fn default() -> Self {
+        Self {
+            current: ArcSwapOption::empty(),
+            refresh: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+impl<V> Snapshot<V> {
+    /// Returns the value even when it's expired.
+    pub fn get(&self) -> Option<RefVal<V>> {
+        let current = self.current.load();
+        current.as_ref().map(|data| data.value.clone())
+    }
+
+    /// Returns the value only when it's not expired.
+    pub fn get_fresh(&self) -> Option<RefVal<V>> {
+        let current = self.current.load();
+        current
+            .as_ref()
+            .filter(|data| data.is_fresh())
+            .map(|data| data.value.clone())
+    }
+
+    /// Returns remaining TTL of the value, zero when it's expired and `None` when it never expires.
+    pub fn ttl(&self) -> Option<Duration> {
+        let current = self.current.load();
+        let expires_at = current.as_ref()?.expires_at?;
+        Some(expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Waits for refreshes in progress, hold the guard while refreshing so the value is refreshed only once.
+    pub async fn refresh_lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.refresh.lock().await
+    }
+
+    pub fn set_with_ttl(&self, val: V, ttl: Option<Duration>) -> RefVal<V> {
+        let value = RefVal(Arc::new(val));
+        self.current
+            .store(Some(Arc::new(CachedValue::new(value.clone(), ttl))));
+        value
+    }
+
+    /// Changes TTL of the current value and returns it, does nothing when there is no value.
+    pub fn set_ttl(&self, ttl: Option<Duration>) -> Option<RefVal<V>> {
+        let value = self.get()?;
+        self.current
+            .store(Some(Arc::new(CachedValue::new(value.clone(), ttl))));
+        Some(value)
+    }
+}
+
+type Slot<V> = Arc<RwLock<Option<CachedValue<V>>>>;
+
+fn decode<V: DeserializeOwned>(bytes: &[u8]) -> serde_json::Result<V> {
+    serde_json::from_slice(bytes)
+}
+
+/// Key and serialization of an entry stored in the cache backend.
+struct SharedKey<V> {
+    key: String,
+    encode: fn(&V) -> serde_json::Result<Vec<u8>>,
+    decode: fn(&[u8]) -> serde_json::Result<V>,
+}
+
+impl<V> SharedKey<V> {
+    /// Reads the value and its remaining TTL from the cache backend, failures are logged and treated as misses.
+    async fn load(&self) -> Option<(V, Option<Duration>)> {
+        match backend().get(&self.key).await {
+            Ok(Some((bytes, ttl))) => match (self.decode)(&bytes) {
+                Ok(value) => Some((value, ttl)),
+                Err(e) => {
+                    tracing::warn!("Deserialization of {} failed: {}", self.key, e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Reading of {} from cache backend failed: {}", self.key, e);
+                None
+            }
+        }
+    }
+}
+
+impl<V> Clone for SharedKey<V> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            encode: self.encode,
+            decode: self.decode,
+        }
+    }
+}
+
+pub struct CacheEntry<V> {
+    namespace: &'static str,
+    inner: Slot<V>,
+    shared: Option<SharedKey<V>>,
+}
+
+pub struct WriteCacheEntryValue<V> {
+    inner: OwnedRwLockWriteGuard<Option<CachedValue<V>>>,
+    shared: Option<SharedKey<V>>,
 }
 
 #[allow(dead_code)]
-impl<T: Send + Sync + 'static> CacheEntry<T> {
-    pub async fn read(&self) -> ReadCacheEntryValue<T> {
-        let data = self.inner.clone().read_owned().await;
-        ReadCacheEntryValue {
-            inner: data,
-            any_type: PhantomData,
+impl<V> WriteCacheEntryValue<V> {
+    /// Returns current value, it may be expired.
+    pub fn get(&self) -> Option<&V> {
+        self.inner.as_ref().map(|data| &*data.value)
+    }
+
+    /// Sets value which never expires.
+    pub fn set(&mut self, val: V) -> RefVal<V> {
+        self.set_with_ttl(val, None)
+    }
+
+    /// Sets the value, value of a shared namespace is also stored in the cache backend.
+    pub fn set_with_ttl(&mut self, val: V, ttl: Option<Duration>) -> RefVal<V> {
+        if let Some(shared) = self.shared.as_ref().filter(|_| backend().is_shared()) {
+            match (shared.encode)(&val) {
+                Ok(bytes) => {
+                    let key = shared.key.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = backend().set(&key, bytes, ttl).await {
+                            tracing::warn!("Storing of {} in cache backend failed: {}", key, e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Serialization of {} failed: {}", shared.key, e),
+            }
+        }
+        self.set_local(val, ttl)
+    }
+
+    /// Removes the value, value of a shared namespace is also removed from the cache backend.
+    pub fn remove(&mut self) {
+        if let Some(shared) = self.shared.as_ref().filter(|_| backend().is_shared()) {
+            let key = shared.key.clone();
+            tokio::spawn(async move {
+                if let Err(e) = backend().delete(std::slice::from_ref(&key)).await {
+                    tracing::warn!("Removing of {} from cache backend failed: {}", key, e);
+                }
+            });
         }
+        *self.inner = None;
+    }
+
+    fn set_local(&mut self, val: V, ttl: Option<Duration>) -> RefVal<V> {
+        let value = RefVal(Arc::new(val));
+        *self.inner = Some(CachedValue::new(value.clone(), ttl));
+        value
     }
 
-    pub async fn write(&self) -> WriteCacheEntryValue<T> {
-        let data = self.inner.clone().write_owned().await;
+    /// Changes TTL of the current value and returns it, does nothing when there is no value.
+    pub fn set_ttl(&mut self, ttl: Option<Duration>) -> Option<RefVal<V>> {
+        let data = self.inner.as_mut()?;
+        data.expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        Some(data.value.clone())
+    }
+
+    /// Delays the background refresh of the current value, does nothing when there is no value.
+    pub fn postpone_refresh(&mut self, after: Duration) {
+        if let Some(data) = self.inner.as_mut() {
+            data.refresh_at = Instant::now() + after;
+        }
+    }
+}
+
+impl<V> Clone for CacheEntry<V> {
+    fn clone(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            inner: self.inner.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<V> CacheEntry<V> {
+    /// Returns the value even when it's expired.
+    pub async fn get(&self) -> Option<RefVal<V>> {
+        let data = self.inner.read().await;
+        data.as_ref().map(|data| data.value.clone())
+    }
+
+    /// Returns the value only when it's not expired.
+    pub async fn get_fresh(&self) -> Option<RefVal<V>> {
+        let data = self.inner.read().await;
+        data.as_ref()
+            .filter(|data| data.is_fresh())
+            .map(|data| data.value.clone())
+    }
+
+    pub async fn write(&self) -> WriteCacheEntryValue<V> {
         WriteCacheEntryValue {
-            inner: data,
-            any_type: PhantomData,
+            inner: self.inner.clone().write_owned().await,
+            shared: self.shared.clone(),
         }
     }
 
-    pub async fn get_or_write_lock(&self) -> Either<RefVal<T>, WriteCacheEntryValue<T>> {
+    /// Returns the value when it's present and not expired, otherwise write lock to set it.
+    ///
+    /// Values of shared namespaces missing locally are looked up in the cache backend first.
+    /// Expired value is still available through the write lock so it can be served when refreshing fails.
+    pub async fn get_or_write_lock(&self) -> Either<RefVal<V>, WriteCacheEntryValue<V>> {
+        let mut waited = false;
         loop {
-            let read_guard = self.inner.read().await;
-            if read_guard.is_some() {
-                return Either::Left(RefVal(self.read().await));
+            if let Some(value) = self.get_fresh().await {
+                let result = if waited { "coalesced" } else { "hit" };
+                metrics::CACHE_LOOKUPS.inc([self.namespace, result]);
+                return Either::Left(value);
             }
-            drop(read_guard);
+            let started = Instant::now();
             let write_guard = self.inner.clone().write_owned().await;
-            if write_guard.is_some() {
+            metrics::CACHE_WRITE_LOCK_WAIT.observe([self.namespace], started.elapsed());
+            if write_guard.as_ref().is_some_and(CachedValue::is_fresh) {
+                waited = true;
                 continue;
             }
-            return Either::Right(WriteCacheEntryValue {
+            let mut lock = WriteCacheEntryValue {
                 inner: write_guard,
-                any_type: PhantomData,
-            });
+                shared: self.shared.clone(),
+            };
+            return match self.load_shared(&mut lock).await {
+                Some(value) => {
+                    metrics::CACHE_LOOKUPS.inc([self.namespace, "backend_hit"]);
+                    Either::Left(value)
+                }
+                None => {
+                    metrics::CACHE_LOOKUPS.inc([self.namespace, "miss"]);
+                    Either::Right(lock)
+                }
+            };
         }
     }
-}
 
-impl Cache {
-    fn get_value_guard<T: 'static>(
+    /// Like [`Self::get_or_write_lock`], but value older than the soft TTL is returned right away
+    /// and refreshed with `refetch` in the background.
+    ///
+    /// Only one refresh of a value runs at a time and lookups of an expired value wait for it,
+    /// so concurrent lookups never fetch the same value twice.
+    /// Value is removed when `refetch` returns `Ok(None)` because it doesn't exist anymore,
+    /// it's kept when `refetch` fails and its refresh is retried after [`STALE_RETRY_AFTER`].
+    pub async fn get_or_write_lock_revalidating<F, Fut, E>(
         &self,
-        key: String,
-    ) -> Arc<RwLock<Option<Box<dyn Any + Send + Sync>>>> {
-        let key = (key, TypeId::of::<T>());
-        let hash_map = self.inner.read().unwrap();
-        if let Some(data) = hash_map.get(&key) {
-            return data.clone();
+        refetch: F,
+    ) -> Either<RefVal<V>, WriteCacheEntryValue<V>>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<V>, E>> + Send + 'static,
+        E: Display + Send + 'static,
+        V: Send + Sync + 'static,
+    {
+        let refresh = {
+            let data = self.inner.read().await;
+            match data.as_ref() {
+                Some(data) if data.is_fresh() => {
+                    if !data.needs_refresh() {
+                        metrics::CACHE_LOOKUPS.inc([self.namespace, "hit"]);
+                    } else if let Ok(refresh_guard) = data.refresh.clone().try_lock_owned() {
+                        metrics::CACHE_LOOKUPS.inc([self.namespace, "revalidate"]);
+                        self.spawn_refresh(refresh_guard, refetch);
+                    } else {
+                        metrics::CACHE_LOOKUPS.inc([self.namespace, "stale"]);
+                    }
+                    return Either::Left(data.value.clone());
+                }
+                Some(data) => Some(data.refresh.clone()),
+                None => None,
+            }
+        };
+        // expired value can still be refreshed in the background, it's fetched only once
+        if let Some(refresh) = refresh {
+            let _ = refresh.lock().await;
+        }
+        self.get_or_write_lock().await
+    }
+
+    fn spawn_refresh<F, Fut, E>(&self, refresh_guard: OwnedMutexGuard<()>, refetch: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<V>, E>> + Send + 'static,
+        E: Display + Send + 'static,
+        V: Send + Sync + 'static,
+    {
+        let entry = self.clone();
+        tokio::spawn(async move {
+            let value = refetch().await;
+            let mut lock = entry.write().await;
+            match value {
+                Ok(Some(value)) => {
+                    lock.set_with_ttl(value, default_ttl());
+                }
+                // next lookup fetches the value again and finds out it's missing
+                Ok(None) => lock.remove(),
+                Err(e) => {
+                    tracing::warn!("Revalidation of {} entry failed: {}", entry.namespace, e);
+                    metrics::CACHE_STALE_SERVED.inc([entry.namespace]);
+                    lock.postpone_refresh(STALE_RETRY_AFTER);
+                }
+            }
+            // waiters continue after the new value is set
+            drop(refresh_guard);
+        });
+    }
+
+    async fn load_shared(&self, lock: &mut WriteCacheEntryValue<V>) -> Option<RefVal<V>> {
+        let shared = self.shared.as_ref().filter(|_| backend().is_shared())?;
+        let (value, ttl) = shared.load().await?;
+        Some(lock.set_local(value, ttl))
+    }
+}
+
+/// Key which has a textual form, it's used to address entries in the cache backend and by administrators.
+pub trait CacheKey: Hash + Eq + Clone {
+    /// Returns the textual form, distinct keys must have distinct forms.
+    fn to_key_string(&self) -> String;
+}
+
+impl CacheKey for String {
+    fn to_key_string(&self) -> String {
+        self.clone()
+    }
+}
+
+impl CacheKey for () {
+    fn to_key_string(&self) -> String {
+        String::new()
+    }
+}
+
+impl CacheKey for (&'static str, String) {
+    fn to_key_string(&self) -> String {
+        format!("{}/{}", self.0, self.1)
+    }
+}
+
+/// Number of independently locked shards of a namespace.
+const SHARD_COUNT: usize = 16;
+
+struct SlotState<V> {
+    slot: Slot<V>,
+    /// Tick of the last access
+    last_used: u64,
+}
+
+struct Shard<K, V> {
+    slots: HashMap<K, SlotState<V>>,
+    /// Entries by tick of their last access, ticks are shared by all shards of a namespace
+    lru: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Clone, V> Shard<K, V> {
+    fn touch(&mut self, key: &K, tick: u64) -> Option<Slot<V>> {
+        let state = self.slots.get_mut(key)?;
+        self.lru.remove(&state.last_used);
+        self.lru.insert(tick, key.clone());
+        state.last_used = tick;
+        Some(state.slot.clone())
+    }
+
+    /// Returns slot of the key and whether it was inserted.
+    fn slot(&mut self, key: K, tick: u64) -> (Slot<V>, bool) {
+        if let Some(slot) = self.touch(&key, tick) {
+            return (slot, false);
         }
-        drop(hash_map);
-        let mut hash_map = self.inner.write().unwrap();
-        hash_map.entry(key).or_default().clone()
+
+        let slot = Slot::default();
+        self.lru.insert(tick, key.clone());
+        self.slots.insert(
+            key,
+            SlotState {
+                slot: slot.clone(),
+                last_used: tick,
+            },
+        );
+        (slot, true)
     }
 
-    pub async fn entry<T: Send + 'static>(&self, key: String) -> CacheEntry<T> {
-        let data_guard = self.get_value_guard::<T>(key);
+    /// Removes the least recently used entry, returns whether there was any.
+    fn evict_oldest(&mut self) -> bool {
+        let Some((_, key)) = self.lru.pop_first() else {
+            return false;
+        };
+        self.slots.remove(&key);
+        true
+    }
+}
+
+/// Typed cache of values by key.
+///
+/// Namespace holds at most its limit of entries, least recently used entries are evicted first.
+/// Entries are split into shards by key hash so lookups of different keys rarely wait for each other,
+/// the limit and recency are tracked across all shards.
+pub struct CacheNamespace<K, V> {
+    name: &'static str,
+    hasher: RandomState,
+    shards: Vec<Mutex<Shard<K, V>>>,
+    limit: AtomicUsize,
+    /// Number of entries in all shards
+    len: AtomicUsize,
+    /// Counter ordering accesses of all shards
+    tick: AtomicU64,
+    shared: Option<SharedCodec<K, V>>,
+}
+
+struct SharedCodec<K, V> {
+    key: fn(&K) -> String,
+    encode: fn(&V) -> serde_json::Result<Vec<u8>>,
+    decode: fn(&[u8]) -> serde_json::Result<V>,
+}
+
+impl<K: Hash + Eq + Clone, V> CacheNamespace<K, V> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            hasher: RandomState::new(),
+            shards: (0..SHARD_COUNT)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        slots: HashMap::new(),
+                        lru: BTreeMap::new(),
+                    })
+                })
+                .collect(),
+            limit: AtomicUsize::new(DEFAULT_NAMESPACE_LIMIT),
+            len: AtomicUsize::new(0),
+            tick: AtomicU64::new(0),
+            shared: None,
+        }
+    }
+
+    /// Creates namespace whose values are shared with other replicas through the cache backend.
+    pub fn shared(name: &'static str) -> Self
+    where
+        K: CacheKey,
+        V: Serialize + DeserializeOwned,
+    {
+        Self {
+            shared: Some(SharedCodec {
+                key: K::to_key_string,
+                encode: serde_json::to_vec::<V>,
+                decode: decode::<V>,
+            }),
+            ..Self::new(name)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> MutexGuard<'_, Shard<K, V>> {
+        let index = self.hasher.hash_one(key) as usize % SHARD_COUNT;
+        self.shards[index].lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn shared_key(&self, key: &K) -> Option<SharedKey<V>> {
+        let codec = self.shared.as_ref()?;
+        Some(SharedKey {
+            key: format!("{}:{}", self.name, (codec.key)(key)),
+            encode: codec.encode,
+            decode: codec.decode,
+        })
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn entry(&self, key: K) -> CacheEntry<V> {
+        let shared = self.shared_key(&key);
+        let tick = self.next_tick();
+        let (inner, inserted) = self.shard(&key).slot(key, tick);
+        if inserted {
+            self.len.fetch_add(1, Ordering::Relaxed);
+            self.evict();
+        }
         CacheEntry {
-            inner: data_guard,
-            any_type: PhantomData,
+            namespace: self.name,
+            inner,
+            shared,
+        }
+    }
+
+    /// Returns the entry only when it exists, so looking up missing keys doesn't create empty entries.
+    pub fn existing_entry<Q>(&self, key: &Q) -> Option<CacheEntry<V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = self.shard(key);
+        let (key, _) = shard.slots.get_key_value(key)?;
+        let key = key.clone();
+        Some(CacheEntry {
+            namespace: self.name,
+            inner: shard.touch(&key, self.next_tick())?,
+            shared: self.shared_key(&key),
+        })
+    }
+
+    /// Evicts least recently used entries of all shards until the namespace is within its limit.
+    fn evict(&self) {
+        while self.len.load(Ordering::Relaxed) > self.limit.load(Ordering::Relaxed) {
+            let oldest = self
+                .shards
+                .iter()
+                .enumerate()
+                .filter_map(|(index, shard)| {
+                    let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+                    let (tick, _) = shard.lru.first_key_value()?;
+                    Some((*tick, index))
+                })
+                .min();
+            let Some((_, index)) = oldest else {
+                break;
+            };
+            let mut shard = self.shards[index].lock().unwrap_or_else(|e| e.into_inner());
+            if shard.evict_oldest() {
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                metrics::CACHE_EVICTIONS.inc([self.name]);
+            }
+        }
+    }
+
+    /// Returns the fresh value without locking its entry, so lookups of missing keys don't wait for each other.
+    ///
+    /// Values of shared namespaces missing locally are read from the cache backend,
+    /// the entry is created only when the value is found there.
+    pub async fn get_fresh(&self, key: K) -> Option<RefVal<V>> {
+        if let Some(entry) = self.existing_entry(&key) {
+            if let Some(value) = entry.get_fresh().await {
+                metrics::CACHE_LOOKUPS.inc([self.name, "hit"]);
+                return Some(value);
+            }
         }
+        let loaded = match self.shared_key(&key).filter(|_| backend().is_shared()) {
+            Some(shared) => shared.load().await,
+            None => None,
+        };
+        let Some((value, ttl)) = loaded else {
+            metrics::CACHE_LOOKUPS.inc([self.name, "miss"]);
+            return None;
+        };
+        metrics::CACHE_LOOKUPS.inc([self.name, "backend_hit"]);
+        Some(self.entry(key).write().await.set_local(value, ttl))
     }
 }
 
-pub struct RefVal<T>(pub ReadCacheEntryValue<T>);
+/// Cached value of an entry as seen by administrators.
+pub struct EntryInfo {
+    /// `None` while the value is being fetched or when fetching failed
+    pub value: Option<serde_json::Value>,
+    /// Remaining TTL, zero when the value is expired and `None` when it never expires
+    pub ttl: Option<Duration>,
+}
 
-impl<T: Send + 'static> Deref for RefVal<T> {
-    type Target = T;
+/// Operations on a namespace which don't depend on its key and value types.
+pub trait Namespace: Send + Sync {
+    fn name(&self) -> &'static str;
 
-    fn deref(&self) -> &Self::Target {
-        self.0.get().unwrap()
+    /// Whether the values are shared with other replicas through the cache backend.
+    fn is_shared(&self) -> bool;
+
+    fn limit(&self) -> usize;
+
+    /// Sets maximal number of entries, takes effect on the next insert.
+    fn set_limit(&self, limit: usize);
+
+    /// Number of entries cached by this replica.
+    fn len(&self) -> usize;
+
+    /// Returns entry by the textual form of its key.
+    fn inspect(&self, key: &str) -> Option<EntryInfo>;
+
+    /// Removes entry by the textual form of its key, returns whether it was cached by this replica.
+    ///
+    /// Entry of a shared namespace is removed from the cache backend too.
+    fn invalidate<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<bool>>;
+
+    /// Removes all entries, returns how many were cached by this replica.
+    ///
+    /// Only entries cached by this replica are removed from the cache backend,
+    /// entries cached only by other replicas stay there until they expire.
+    fn clear(&self) -> BoxFuture<'_, io::Result<usize>>;
+}
+
+impl<K: CacheKey, V> CacheNamespace<K, V> {
+    /// Removes entries matching the predicate from all shards, returns their keys.
+    fn remove_where(&self, predicate: impl Fn(&K) -> bool) -> Vec<K> {
+        let mut removed = Vec::new();
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            let keys = shard
+                .slots
+                .keys()
+                .filter(|key| predicate(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in keys {
+                if let Some(state) = shard.slots.remove(&key) {
+                    shard.lru.remove(&state.last_used);
+                    self.len.fetch_sub(1, Ordering::Relaxed);
+                }
+                removed.push(key);
+            }
+        }
+        removed
+    }
+
+    /// Removes the keys from the cache backend when the namespace is shared.
+    async fn delete_shared(&self, keys: &[String]) -> io::Result<()> {
+        if self.shared.is_none() || !backend().is_shared() {
+            return Ok(());
+        }
+        let keys = keys
+            .iter()
+            .map(|key| format!("{}:{key}", self.name))
+            .collect::<Vec<_>>();
+        backend().delete(&keys).await
+    }
+}
+
+impl<K, V> Namespace for CacheNamespace<K, V>
+where
+    K: CacheKey + Send + Sync,
+    V: Serialize + Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn is_shared(&self) -> bool {
+        self.shared.is_some()
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.limit.store(limit.max(1), Ordering::Relaxed);
+        self.evict();
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    fn inspect(&self, key: &str) -> Option<EntryInfo> {
+        let slot = self.shards.iter().find_map(|shard| {
+            let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            shard
+                .slots
+                .iter()
+                .find(|(k, _)| k.to_key_string() == key)
+                .map(|(_, state)| state.slot.clone())
+        })?;
+        // entry is write locked while its value is being fetched
+        let Ok(data) = slot.try_read() else {
+            return Some(EntryInfo {
+                value: None,
+                ttl: None,
+            });
+        };
+        Some(match data.as_ref() {
+            Some(data) => EntryInfo {
+                value: serde_json::to_value(&*data.value).ok(),
+                ttl: data
+                    .expires_at
+                    .map(|expires_at| expires_at.saturating_duration_since(Instant::now())),
+            },
+            None => EntryInfo {
+                value: None,
+                ttl: None,
+            },
+        })
+    }
+
+    fn invalidate<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<bool>> {
+        async move {
+            let removed = !self.remove_where(|k| k.to_key_string() == key).is_empty();
+            self.delete_shared(&[key.to_string()]).await?;
+            Ok(removed)
+        }
+        .boxed()
+    }
+
+    fn clear(&self) -> BoxFuture<'_, io::Result<usize>> {
+        async move {
+            let removed = self
+                .remove_where(|_| true)
+                .iter()
+                .map(CacheKey::to_key_string)
+                .collect::<Vec<_>>();
+            self.delete_shared(&removed).await?;
+            Ok(removed.len())
+        }
+        .boxed()
+    }
+}
+
+#[derive(Default)]
+pub struct Cache {
+    namespaces: Mutex<Vec<&'static dyn Namespace>>,
+}
+
+impl Cache {
+    pub fn register(&self, namespace: &'static dyn Namespace) {
+        self.namespaces.lock().unwrap().push(namespace);
+    }
+
+    pub fn namespace(&self, name: &str) -> Option<&'static dyn Namespace> {
+        self.namespaces
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|namespace| namespace.name() == name)
+            .copied()
+    }
+
+    pub fn namespaces(&self) -> Vec<&'static dyn Namespace> {
+        self.namespaces.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_snapshot_is_kept_until_replaced() {
+        let snapshot = Snapshot::default();
+        assert!(snapshot.get().is_none());
+        assert!(snapshot.set_ttl(None).is_none());
+
+        snapshot.set_with_ttl(1, Some(Duration::ZERO));
+        assert!(snapshot.get_fresh().is_none());
+        assert_eq!(snapshot.get().as_deref(), Some(&1));
+        assert_eq!(snapshot.ttl(), Some(Duration::ZERO));
+
+        assert_eq!(
+            snapshot.set_ttl(Some(Duration::from_secs(60))).as_deref(),
+            Some(&1)
+        );
+        assert_eq!(snapshot.get_fresh().as_deref(), Some(&1));
+        assert!(snapshot.ttl().unwrap() > Duration::from_secs(59));
+
+        snapshot.set_with_ttl(2, None);
+        assert_eq!(snapshot.get_fresh().as_deref(), Some(&2));
+        assert_eq!(snapshot.ttl(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_entry_is_available_through_write_lock() {
+        let namespace = CacheNamespace::<String, u32>::new("test_ttl");
+        let entry = namespace.entry("key".into());
+        let Either::Right(mut lock) = entry.get_or_write_lock().await else {
+            panic!("empty entry is returned");
+        };
+        assert!(lock.get().is_none());
+        lock.set_with_ttl(1, Some(Duration::ZERO));
+        drop(lock);
+
+        assert!(entry.get_fresh().await.is_none());
+        assert_eq!(entry.get().await.as_deref(), Some(&1));
+        let Either::Right(mut lock) = entry.get_or_write_lock().await else {
+            panic!("expired entry is returned");
+        };
+        assert_eq!(lock.get(), Some(&1));
+        lock.set_ttl(Some(Duration::from_secs(60)));
+        drop(lock);
+
+        let Either::Left(value) = entry.get_or_write_lock().await else {
+            panic!("fresh entry isn't returned");
+        };
+        assert_eq!(*value, 1);
+    }
+
+    #[test]
+    fn limits_are_parsed() {
+        assert_eq!(
+            parse_limits(" pokemon=2000, not_found = 5 ,").unwrap(),
+            [("pokemon".to_string(), 2000), ("not_found".to_string(), 5)]
+        );
+        assert!(parse_limits("").unwrap().is_empty());
+        assert!(parse_limits("pokemon").is_err());
+        assert!(parse_limits("pokemon=0").is_err());
+        assert!(parse_limits("pokemon=-1").is_err());
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let namespace = CacheNamespace::<String, u32>::new("test_eviction");
+        namespace.set_limit(2);
+        namespace.entry("a".into());
+        namespace.entry("b".into());
+        assert!(namespace.existing_entry("a").is_some());
+        namespace.entry("c".into());
+        assert_eq!(namespace.len(), 2);
+        assert!(namespace.existing_entry("a").is_some());
+        assert!(namespace.existing_entry("b").is_none());
+
+        // lowering the limit evicts right away
+        namespace.set_limit(1);
+        assert_eq!(namespace.len(), 1);
+        assert!(namespace.existing_entry("a").is_some());
+    }
+
+    #[tokio::test]
+    async fn namespace_stays_within_its_limit() {
+        let namespace = CacheNamespace::<String, u32>::new("test_lru");
+        namespace.set_limit(32);
+        for i in 0..200 {
+            namespace.entry(i.to_string()).write().await.set(i);
+        }
+        assert_eq!(namespace.len(), 32);
+        assert!(namespace.existing_entry("199").is_some());
+        // looking up missing keys doesn't create entries
+        let len = namespace.len();
+        assert!(namespace.existing_entry("200").is_none());
+        assert_eq!(namespace.len(), len);
+    }
+
+    #[tokio::test]
+    async fn concurrent_lookups_wait_for_the_first_writer() {
+        let namespace = CacheNamespace::<String, u32>::new("test_coalescing");
+        let entry = namespace.entry("key".into());
+        let Either::Right(mut lock) = entry.get_or_write_lock().await else {
+            panic!("empty entry is returned");
+        };
+
+        let waiter = namespace.entry("key".into());
+        let waiter = tokio::spawn(async move {
+            match waiter.get_or_write_lock().await {
+                Either::Left(value) => Some(*value),
+                Either::Right(_) => None,
+            }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        lock.set(7);
+        drop(lock);
+        assert_eq!(waiter.await.unwrap(), Some(7));
+    }
+
+    #[tokio::test]
+    async fn values_outlive_their_entries() {
+        let namespace = CacheNamespace::<String, String>::new("test_refval");
+        let value = namespace
+            .entry("key".into())
+            .write()
+            .await
+            .set("value".into());
+        assert_eq!(namespace.clear().await.unwrap(), 1);
+        assert!(namespace.existing_entry("key").is_none());
+        assert_eq!(&*value, "value");
+
+        let entry = namespace.entry("key".into());
+        assert!(entry.get().await.is_none());
+        let replaced = entry.write().await.set("other".into());
+        assert_eq!(&*value, "value");
+        assert_eq!(&*replaced, "other");
+    }
+
+    /// Returns entry with a value due for a background refresh.
+    async fn stale_entry(namespace: &CacheNamespace<String, u32>) -> CacheEntry<u32> {
+        let entry = namespace.entry("key".into());
+        let mut lock = entry.write().await;
+        lock.set(1);
+        lock.postpone_refresh(Duration::ZERO);
+        drop(lock);
+        entry
+    }
+
+    /// Waits until the background refresh of the entry finishes.
+    async fn refreshed(entry: &CacheEntry<u32>) {
+        let refresh = entry.inner.read().await.as_ref().unwrap().refresh.clone();
+        let _ = refresh.lock().await;
+    }
+
+    #[tokio::test]
+    async fn stale_value_is_refreshed_once_in_background() {
+        let namespace = CacheNamespace::<String, u32>::new("test_revalidation");
+        let entry = stale_entry(&namespace).await;
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let lookups = (0..10).map(|_| {
+            let fetches = fetches.clone();
+            entry.get_or_write_lock_revalidating(move || async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<_, String>(Some(2))
+            })
+        });
+        for lookup in futures::future::join_all(lookups).await {
+            let Either::Left(value) = lookup else {
+                panic!("stale value is served while it's refreshed");
+            };
+            assert_eq!(*value, 1);
+        }
+        refreshed(&entry).await;
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(entry.get_fresh().await.as_deref(), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn missing_value_is_removed_on_revalidation() {
+        let namespace = CacheNamespace::<String, u32>::new("test_revalidation_missing");
+        let entry = stale_entry(&namespace).await;
+        let lookup = entry
+            .get_or_write_lock_revalidating(|| async { Ok::<_, String>(None) })
+            .await;
+        assert!(matches!(lookup, Either::Left(value) if *value == 1));
+        refreshed(&entry).await;
+        assert!(entry.get().await.is_none());
+        assert!(matches!(entry.get_or_write_lock().await, Either::Right(_)));
+    }
+
+    #[tokio::test]
+    async fn failed_revalidation_keeps_stale_value() {
+        let namespace = CacheNamespace::<String, u32>::new("test_revalidation_failed");
+        let entry = stale_entry(&namespace).await;
+        entry
+            .get_or_write_lock_revalidating(|| async { Err("remote api is down") })
+            .await;
+        refreshed(&entry).await;
+        let data = entry.inner.read().await;
+        let data = data.as_ref().unwrap();
+        assert_eq!(*data.value, 1);
+        // refresh is retried later
+        assert!(!data.needs_refresh());
+    }
+
+    #[test]
+    fn keys_have_distinct_textual_forms() {
+        assert_eq!("pikachu".to_string().to_key_string(), "pikachu");
+        assert_eq!(().to_key_string(), "");
+        assert_eq!(
+            ("pokemon", "pikachu".to_string()).to_key_string(),
+            "pokemon/pikachu"
+        );
     }
 }
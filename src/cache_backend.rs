@@ -0,0 +1,484 @@
+use std::{
+    io,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Semaphore,
+};
+
+pub static CACHE_BACKEND: OnceLock<Box<dyn CacheBackend>> = OnceLock::new();
+
+/// Timeout of a single round trip to the backend, cache falls back to the remote api when it's exceeded.
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximal number of connections to the backend, commands beyond it wait for a free connection.
+const POOL_SIZE: usize = 8;
+
+/// Returns the configured backend, [`LocalBackend`] when none is configured.
+pub fn backend() -> &'static dyn CacheBackend {
+    match CACHE_BACKEND.get() {
+        Some(backend) => backend.as_ref(),
+        None => &LocalBackend,
+    }
+}
+
+/// Storage of serialized cache entries shared by replicas.
+///
+/// Values are kept in the in-memory namespaces of every replica, the backend is consulted only
+/// when the value is missing locally and it's updated whenever a shared namespace fetches a new value.
+pub trait CacheBackend: Send + Sync {
+    /// Whether the values are shared with other replicas, cache skips backends which are not.
+    fn is_shared(&self) -> bool {
+        true
+    }
+
+    /// Returns the value and its remaining TTL, `None` when the key doesn't exist.
+    #[allow(clippy::type_complexity)]
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, io::Result<Option<(Vec<u8>, Option<Duration>)>>>;
+
+    fn set<'a>(
+        &'a self,
+        key: &'a str,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Removes the keys, missing keys are ignored.
+    fn delete<'a>(&'a self, keys: &'a [String]) -> BoxFuture<'a, io::Result<()>>;
+}
+
+/// Default backend, nothing is shared and values live only in the in-memory namespaces of this replica.
+pub struct LocalBackend;
+
+impl CacheBackend for LocalBackend {
+    fn is_shared(&self) -> bool {
+        false
+    }
+
+    fn get<'a>(
+        &'a self,
+        _key: &'a str,
+    ) -> BoxFuture<'a, io::Result<Option<(Vec<u8>, Option<Duration>)>>> {
+        futures::future::ready(Ok(None)).boxed()
+    }
+
+    fn set<'a>(
+        &'a self,
+        _key: &'a str,
+        _value: Vec<u8>,
+        _ttl: Option<Duration>,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    fn delete<'a>(&'a self, _keys: &'a [String]) -> BoxFuture<'a, io::Result<()>> {
+        futures::future::ready(Ok(())).boxed()
+    }
+}
+
+enum Reply {
+    Status,
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+}
+
+fn encode_command(args: &[&[u8]]) -> Vec<u8> {
+    let mut command = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        command.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        command.extend_from_slice(arg);
+        command.extend_from_slice(b"\r\n");
+    }
+    command
+}
+
+async fn read_reply(reader: &mut BufReader<TcpStream>) -> io::Result<Reply> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let line = line.trim_end_matches("\r\n");
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid reply '{line}'"),
+        )
+    };
+    let (kind, value) = line.split_at_checked(1).ok_or_else(invalid)?;
+    match kind {
+        "+" => Ok(Reply::Status),
+        "-" => Err(io::Error::other(value.to_string())),
+        ":" => value.parse().map(Reply::Integer).map_err(|_| invalid()),
+        "$" => {
+            let len = value.parse::<i64>().map_err(|_| invalid())?;
+            if len < 0 {
+                return Ok(Reply::Bulk(None));
+            }
+            let mut data = vec![0; len as usize + 2];
+            reader.read_exact(&mut data).await?;
+            data.truncate(len as usize);
+            Ok(Reply::Bulk(Some(data)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Backend storing values in Redis or any server speaking its protocol.
+///
+/// Commands are sent over a small pool of connections, connection is dropped after any error
+/// and a new one is opened by the next command.
+pub struct RedisBackend {
+    address: String,
+    password: Option<String>,
+    key_prefix: String,
+    timeout: Duration,
+    /// Limits the number of open connections to [`POOL_SIZE`]
+    permits: Semaphore,
+    idle: Mutex<Vec<BufReader<TcpStream>>>,
+}
+
+impl RedisBackend {
+    pub fn new(address: String, password: Option<String>) -> Self {
+        Self {
+            address,
+            password,
+            key_prefix: "pokemon-api:".to_string(),
+            timeout: BACKEND_TIMEOUT,
+            permits: Semaphore::new(POOL_SIZE),
+            idle: Mutex::new(Vec::with_capacity(POOL_SIZE)),
+        }
+    }
+
+    async fn connect(&self) -> io::Result<BufReader<TcpStream>> {
+        let mut connection = BufReader::new(TcpStream::connect(&self.address).await?);
+        if let Some(password) = &self.password {
+            let command = encode_command(&[b"AUTH", password.as_bytes()]);
+            connection.get_mut().write_all(&command).await?;
+            read_reply(&mut connection).await?;
+        }
+        Ok(connection)
+    }
+
+    /// Sends the commands at once and returns their replies in order.
+    async fn query(&self, commands: &[&[&[u8]]]) -> io::Result<Vec<Reply>> {
+        let exchange = async {
+            let _permit = self.permits.acquire().await.map_err(io::Error::other)?;
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let mut connection = match idle {
+                Some(connection) => connection,
+                None => self.connect().await?,
+            };
+            let pipeline = commands
+                .iter()
+                .flat_map(|args| encode_command(args))
+                .collect::<Vec<_>>();
+            connection.get_mut().write_all(&pipeline).await?;
+            let mut replies = Vec::with_capacity(commands.len());
+            for _ in commands {
+                replies.push(read_reply(&mut connection).await?);
+            }
+            // the connection is returned to the pool only after a complete exchange
+            self.idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(connection);
+            io::Result::Ok(replies)
+        };
+        tokio::time::timeout(self.timeout, exchange)
+            .await
+            .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+    }
+}
+
+impl CacheBackend for RedisBackend {
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, io::Result<Option<(Vec<u8>, Option<Duration>)>>> {
+        async move {
+            let key = format!("{}{key}", self.key_prefix);
+            let replies = self
+                .query(&[&[b"GET", key.as_bytes()], &[b"PTTL", key.as_bytes()]])
+                .await?;
+            match replies.as_slice() {
+                [Reply::Bulk(Some(value)), Reply::Integer(ttl)] => match *ttl {
+                    // key expired between the commands
+                    -2 => Ok(None),
+                    ttl if ttl < 0 => Ok(Some((value.clone(), None))),
+                    ttl => Ok(Some((
+                        value.clone(),
+                        Some(Duration::from_millis(ttl as u64)),
+                    ))),
+                },
+                [Reply::Bulk(None), _] => Ok(None),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Unexpected replies to GET",
+                )),
+            }
+        }
+        .boxed()
+    }
+
+    fn set<'a>(
+        &'a self,
+        key: &'a str,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            let key = format!("{}{key}", self.key_prefix);
+            let ttl = ttl.map(|ttl| ttl.as_millis().max(1).to_string());
+            let mut command = vec![b"SET".as_slice(), key.as_bytes(), &value];
+            if let Some(ttl) = &ttl {
+                command.extend([b"PX".as_slice(), ttl.as_bytes()]);
+            }
+            match self.query(&[&command]).await?.as_slice() {
+                [Reply::Status] => Ok(()),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Unexpected reply to SET",
+                )),
+            }
+        }
+        .boxed()
+    }
+    fn delete<'a>(&'a self, keys: &'a [String]) -> BoxFuture<'a, io::Result<()>> {
+        async move {
+            if keys.is_empty() {
+                return Ok(());
+            }
+            let keys = keys
+                .iter()
+                .map(|key| format!("{}{key}", self.key_prefix))
+                .collect::<Vec<_>>();
+            let mut command = vec![b"DEL".as_slice()];
+            command.extend(keys.iter().map(String::as_bytes));
+            match self.query(&[&command]).await?.as_slice() {
+                [Reply::Integer(_)] => Ok(()),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Unexpected reply to DEL",
+                )),
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Server answering the commands used by [`RedisBackend`] from memory.
+    struct StubServer {
+        address: String,
+        /// Number of accepted connections
+        connections: Arc<AtomicUsize>,
+    }
+
+    /// Starts a stub server, the first `stalled` connections never get any reply.
+    async fn stub_server(password: Option<&'static str>, stalled: usize) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let values = Arc::new(Mutex::new(HashMap::<Vec<u8>, (Vec<u8>, i64)>::new()));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let stall = accepted.fetch_add(1, Ordering::SeqCst) < stalled;
+                let values = values.clone();
+                tokio::spawn(async move {
+                    let mut connection = BufReader::new(socket);
+                    let mut authenticated = password.is_none();
+                    while let Some(args) = read_command(&mut connection).await {
+                        if stall {
+                            continue;
+                        }
+                        let reply = match (args[0].as_slice(), authenticated) {
+                            (b"AUTH", _)
+                                if Some(args[1].as_slice()) == password.map(str::as_bytes) =>
+                            {
+                                authenticated = true;
+                                b"+OK\r\n".to_vec()
+                            }
+                            (b"AUTH", _) => b"-WRONGPASS invalid password\r\n".to_vec(),
+                            (_, false) => b"-NOAUTH Authentication required\r\n".to_vec(),
+                            (b"SET", _) => {
+                                let ttl = match args.get(4) {
+                                    Some(ttl) => std::str::from_utf8(ttl).unwrap().parse().unwrap(),
+                                    None => -1,
+                                };
+                                let mut values = values.lock().unwrap();
+                                values.insert(args[1].clone(), (args[2].clone(), ttl));
+                                b"+OK\r\n".to_vec()
+                            }
+                            (b"GET", _) => match values.lock().unwrap().get(&args[1]) {
+                                Some((value, _)) => {
+                                    let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                                    reply.extend_from_slice(value);
+                                    reply.extend_from_slice(b"\r\n");
+                                    reply
+                                }
+                                None => b"$-1\r\n".to_vec(),
+                            },
+                            (b"PTTL", _) => match values.lock().unwrap().get(&args[1]) {
+                                Some((_, ttl)) => format!(":{ttl}\r\n").into_bytes(),
+                                None => b":-2\r\n".to_vec(),
+                            },
+                            (b"DEL", _) => {
+                                let mut values = values.lock().unwrap();
+                                let deleted =
+                                    args[1..].iter().filter(|key| values.remove(*key).is_some());
+                                format!(":{}\r\n", deleted.count()).into_bytes()
+                            }
+                            _ => b"-ERR unknown command\r\n".to_vec(),
+                        };
+                        if connection.get_mut().write_all(&reply).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        StubServer {
+            address,
+            connections,
+        }
+    }
+
+    async fn read_command(connection: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+        let mut line = String::new();
+        connection.read_line(&mut line).await.ok()?;
+        let count = line.trim_end().strip_prefix('*')?.parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            connection.read_line(&mut line).await.ok()?;
+            let len = line.trim_end().strip_prefix('$')?.parse::<usize>().ok()?;
+            let mut arg = vec![0; len + 2];
+            connection.read_exact(&mut arg).await.ok()?;
+            arg.truncate(len);
+            args.push(arg);
+        }
+        Some(args)
+    }
+
+    #[tokio::test]
+    async fn values_round_trip() {
+        let server = stub_server(None, 0).await;
+        let backend = RedisBackend::new(server.address, None);
+
+        // missing key is a null bulk reply
+        assert_eq!(backend.get("bulbasaur").await.unwrap(), None);
+
+        let ttl = Duration::from_secs(5);
+        backend
+            .set("bulbasaur", b"grass".to_vec(), Some(ttl))
+            .await
+            .unwrap();
+        backend
+            .set("ivysaur", b"poison".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.get("bulbasaur").await.unwrap(),
+            Some((b"grass".to_vec(), Some(ttl)))
+        );
+        assert_eq!(
+            backend.get("ivysaur").await.unwrap(),
+            Some((b"poison".to_vec(), None))
+        );
+
+        let keys = [
+            "bulbasaur".to_string(),
+            "ivysaur".to_string(),
+            "venusaur".to_string(),
+        ];
+        backend.delete(&keys).await.unwrap();
+        assert_eq!(backend.get("bulbasaur").await.unwrap(), None);
+        assert_eq!(backend.get("ivysaur").await.unwrap(), None);
+        // all commands went through one connection
+        assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn connection_is_authenticated() {
+        let server = stub_server(Some("secret"), 0).await;
+        let backend = RedisBackend::new(server.address.clone(), Some("secret".to_string()));
+        backend
+            .set("pikachu", b"electric".to_vec(), None)
+            .await
+            .unwrap();
+        assert!(backend.get("pikachu").await.unwrap().is_some());
+
+        // error replies are errors
+        let backend = RedisBackend::new(server.address.clone(), Some("wrong".to_string()));
+        let e = backend.get("pikachu").await.unwrap_err();
+        assert!(e.to_string().starts_with("WRONGPASS"), "{e}");
+        let backend = RedisBackend::new(server.address, None);
+        let e = backend.get("pikachu").await.unwrap_err();
+        assert!(e.to_string().starts_with("NOAUTH"), "{e}");
+    }
+
+    #[tokio::test]
+    async fn connection_is_reopened_after_timeout() {
+        let server = stub_server(None, 1).await;
+        let backend = RedisBackend {
+            timeout: Duration::from_millis(100),
+            ..RedisBackend::new(server.address, None)
+        };
+        let e = backend.get("snorlax").await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+
+        backend
+            .set("snorlax", b"normal".to_vec(), None)
+            .await
+            .unwrap();
+        assert!(backend.get("snorlax").await.unwrap().is_some());
+        assert_eq!(server.connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_commands_share_the_pool() {
+        let server = stub_server(None, 0).await;
+        let backend = Arc::new(RedisBackend::new(server.address, None));
+        let tasks = (0..4 * POOL_SIZE)
+            .map(|i| {
+                let backend = backend.clone();
+                tokio::spawn(async move {
+                    let key = format!("pokemon-{i}");
+                    backend
+                        .set(&key, i.to_string().into_bytes(), None)
+                        .await
+                        .unwrap();
+                    backend.get(&key).await.unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        for (i, task) in tasks.into_iter().enumerate() {
+            let (value, _) = task.await.unwrap().unwrap();
+            assert_eq!(value, i.to_string().into_bytes());
+        }
+        let connections = server.connections.load(Ordering::SeqCst);
+        assert!((1..=POOL_SIZE).contains(&connections), "{connections}");
+    }
+}
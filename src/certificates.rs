@@ -0,0 +1,228 @@
+use std::sync::OnceLock;
+
+use actix_web::{http::StatusCode, HttpResponse};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::{
+    pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey},
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    draws::{Draw, DrawnPokemon},
+    models::jwks::Jwk,
+    req_util::response_from_error,
+};
+
+pub const CERTIFICATE_ISSUER: &str = "pokemon-api";
+
+pub static CERTIFICATE_SIGNER: OnceLock<CertificateSigner> = OnceLock::new();
+
+#[derive(Serialize, Deserialize)]
+pub struct CertificateClaims<T> {
+    pub iss: String,
+    pub sub: String,
+    pub iat: u64,
+    pub draw_id: String,
+    pub index: usize,
+    pub pokemon: T,
+}
+
+pub struct CertificateSigner {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+    jwk: Jwk,
+}
+
+impl CertificateSigner {
+    pub fn from_rsa_pem(pem: &str) -> Result<Self, String> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|e| e.to_string())?;
+        let n = URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be());
+
+        // RFC 7638 thumbprint
+        let thumbprint = Sha256::digest(format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#));
+        let kid = URL_SAFE_NO_PAD.encode(thumbprint);
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_required_spec_claims(&["iss", "sub"]);
+        validation.set_issuer(&[CERTIFICATE_ISSUER]);
+        validation.validate_exp = false;
+
+        Ok(Self {
+            encoding_key: EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| e.to_string())?,
+            decoding_key: DecodingKey::from_rsa_components(&n, &e).map_err(|e| e.to_string())?,
+            validation,
+            jwk: Jwk {
+                kty: "RSA".into(),
+                r#use: "sig".into(),
+                alg: "RS256".into(),
+                kid,
+                n,
+                e,
+            },
+        })
+    }
+
+    /// Returns whether the RSA public key is the public key of this signer.
+    pub fn has_public_key(&self, pem: &str) -> Result<bool, String> {
+        let public_key = RsaPublicKey::from_public_key_pem(pem)
+            .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+            .map_err(|e| e.to_string())?;
+        Ok(
+            URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()) == self.jwk.n
+                && URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()) == self.jwk.e,
+        )
+    }
+
+    pub fn jwk(&self) -> &Jwk {
+        &self.jwk
+    }
+
+    pub fn sign<T: Serialize>(
+        &self,
+        claims: &CertificateClaims<T>,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(self.jwk.kid.clone());
+        jsonwebtoken::encode(&header, claims, &self.encoding_key)
+    }
+
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        certificate: &str,
+    ) -> Result<CertificateClaims<T>, jsonwebtoken::errors::Error> {
+        jsonwebtoken::decode(certificate, &self.decoding_key, &self.validation)
+            .map(|data| data.claims)
+    }
+}
+
+/// Signs every drawn pokemon for the given subject.
+#[allow(clippy::result_large_err)]
+pub fn certify_draw<T: DrawnPokemon>(
+    pokemons: &mut [T],
+    sub: &str,
+    draw: &Draw,
+) -> Result<(), HttpResponse> {
+    let Some(signer) = CERTIFICATE_SIGNER.get() else {
+        return Err(response_from_error(
+            "Certificates are not configured",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ));
+    };
+
+    for (index, pokemon) in pokemons.iter_mut().enumerate() {
+        let claims = CertificateClaims {
+            iss: CERTIFICATE_ISSUER.to_string(),
+            sub: sub.to_string(),
+            iat: draw.timestamp,
+            draw_id: draw.id.clone(),
+            index,
+            pokemon: &*pokemon,
+        };
+        let certificate = signer.sign(&claims).map_err(|e| {
+            response_from_error(
+                format!("Failed to sign certificate: {e}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        })?;
+        pokemon.set_certificate(certificate);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const KEY: &str = include_str!("../test_data/certificate_key.pem");
+    const KEY_PKCS1: &str = include_str!("../test_data/certificate_key_pkcs1.pem");
+    const PUBLIC_KEY: &str = include_str!("../test_data/certificate_key.pub.pem");
+    const OTHER_PUBLIC_KEY: &str = include_str!("../test_data/decoding_key.pub.pem");
+
+    fn claims() -> CertificateClaims<serde_json::Value> {
+        CertificateClaims {
+            iss: CERTIFICATE_ISSUER.to_string(),
+            sub: "ash".to_string(),
+            iat: 1_700_000_000,
+            draw_id: "draw".to_string(),
+            index: 1,
+            pokemon: json!({ "name": "pikachu", "level": 5 }),
+        }
+    }
+
+    #[test]
+    fn signed_certificate_is_verified() {
+        let signer = CertificateSigner::from_rsa_pem(KEY).unwrap();
+        let certificate = signer.sign(&claims()).unwrap();
+
+        let header = jsonwebtoken::decode_header(&certificate).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some(signer.jwk().kid.as_str()));
+
+        let verified = signer.verify::<serde_json::Value>(&certificate).unwrap();
+        assert_eq!(verified.sub, "ash");
+        assert_eq!(verified.draw_id, "draw");
+        assert_eq!(verified.index, 1);
+        assert_eq!(verified.pokemon, claims().pokemon);
+    }
+
+    #[test]
+    fn pkcs1_and_pkcs8_keys_are_the_same_signer() {
+        let pkcs8 = CertificateSigner::from_rsa_pem(KEY).unwrap();
+        let pkcs1 = CertificateSigner::from_rsa_pem(KEY_PKCS1).unwrap();
+        assert_eq!(pkcs8.jwk().kid, pkcs1.jwk().kid);
+
+        let certificate = pkcs1.sign(&claims()).unwrap();
+        assert!(pkcs8.verify::<serde_json::Value>(&certificate).is_ok());
+    }
+
+    #[test]
+    fn kid_is_jwk_thumbprint() {
+        let signer = CertificateSigner::from_rsa_pem(KEY).unwrap();
+        let jwk = signer.jwk();
+        let thumbprint = Sha256::digest(format!(
+            r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+            jwk.e, jwk.n
+        ));
+        assert_eq!(jwk.kid, URL_SAFE_NO_PAD.encode(thumbprint));
+        assert_eq!(jwk.e, "AQAB");
+    }
+
+    #[test]
+    fn tampered_or_foreign_certificates_are_rejected() {
+        let signer = CertificateSigner::from_rsa_pem(KEY).unwrap();
+        let certificate = signer.sign(&claims()).unwrap();
+
+        let (signed, signature) = certificate.rsplit_once('.').unwrap();
+        let (header, _) = signed.split_once('.').unwrap();
+        let mut forged = claims();
+        forged.sub = "gary".to_string();
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        let forged = format!("{header}.{forged_payload}.{signature}");
+        assert!(signer.verify::<serde_json::Value>(&forged).is_err());
+
+        let mut other_issuer = claims();
+        other_issuer.iss = "someone-else".to_string();
+        let certificate = signer.sign(&other_issuer).unwrap();
+        assert!(signer.verify::<serde_json::Value>(&certificate).is_err());
+    }
+
+    #[test]
+    fn public_key_of_signer_is_recognized() {
+        let signer = CertificateSigner::from_rsa_pem(KEY).unwrap();
+        assert_eq!(signer.has_public_key(PUBLIC_KEY), Ok(true));
+        assert_eq!(signer.has_public_key(OTHER_PUBLIC_KEY), Ok(false));
+        assert!(signer.has_public_key("not a key").is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+pub static PULL_COSTS: OnceLock<PullCosts> = OnceLock::new();
+
+/// Draw endpoint a pull is made on.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Banner {
+    Random,
+    Fair,
+    Generate,
+}
+
+impl Banner {
+    pub const ALL: [Banner; 3] = [Banner::Random, Banner::Fair, Banner::Generate];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Banner::Random => "get_random",
+            Banner::Fair => "get_random_fair",
+            Banner::Generate => "generate",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PullCosts {
+    per_pull: HashMap<Banner, u64>,
+    per_count: HashMap<(Banner, u8), u64>,
+}
+
+impl PullCosts {
+    /// Parses comma separated `banner=cost` and `banner:count=cost` entries.
+    ///
+    /// `banner=cost` sets cost of a single pull, `banner:count=cost` overrides cost of the whole draw of exactly `count` pokemons.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut costs = Self::default();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((key, cost)) = entry.split_once('=') else {
+                return Err(format!("Entry '{entry}' is missing '='"));
+            };
+            let cost = cost
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| format!("Cost of '{entry}' is invalid: {e}"))?;
+            if cost > i64::MAX as u64 {
+                return Err(format!("Cost of '{entry}' is too large"));
+            }
+            let (banner, count) = match key.trim().split_once(':') {
+                Some((banner, count)) => (
+                    banner,
+                    Some(
+                        count
+                            .parse::<u8>()
+                            .map_err(|e| format!("Count of '{entry}' is invalid: {e}"))?,
+                    ),
+                ),
+                None => (key.trim(), None),
+            };
+            let Some(banner) = Banner::ALL.into_iter().find(|b| b.as_str() == banner) else {
+                return Err(format!("Unknown banner '{banner}'"));
+            };
+            match count {
+                Some(count) => costs.per_count.insert((banner, count), cost),
+                None => costs.per_pull.insert(banner, cost),
+            };
+        }
+        Ok(costs)
+    }
+
+    /// Returns cost of drawing `count` pokemons on the banner, pulls are free unless configured.
+    pub fn cost(&self, banner: Banner, count: u8) -> u64 {
+        self.per_count
+            .get(&(banner, count))
+            .copied()
+            .unwrap_or_else(|| {
+                self.per_pull
+                    .get(&banner)
+                    .copied()
+                    .unwrap_or_default()
+                    .saturating_mul(count as u64)
+            })
+    }
+}
+
+pub fn pull_cost(banner: Banner, count: u8) -> u64 {
+    PULL_COSTS
+        .get()
+        .map(|costs| costs.cost(banner, count))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn costs_are_per_pull_unless_overridden_per_count() {
+        let costs = PullCosts::parse(" get_random=10, generate=25 ,generate:10=200,").unwrap();
+        assert_eq!(costs.cost(Banner::Random, 1), 10);
+        assert_eq!(costs.cost(Banner::Random, 3), 30);
+        assert_eq!(costs.cost(Banner::Generate, 9), 225);
+        assert_eq!(costs.cost(Banner::Generate, 10), 200);
+        assert_eq!(costs.cost(Banner::Fair, 5), 0);
+        assert_eq!(PullCosts::parse("").unwrap().cost(Banner::Random, 1), 0);
+    }
+
+    #[test]
+    fn invalid_costs_are_rejected() {
+        for value in [
+            "get_random",
+            "get_random=-1",
+            "get_random=9223372036854775808",
+            "get_random:256=1",
+            "get_random:x=1",
+            "gacha=1",
+        ] {
+            assert!(PullCosts::parse(value).is_err(), "{value}");
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub static DAILY_CONFIG: OnceLock<DailyConfig> = OnceLock::new();
+
+pub struct DailyConfig {
+    pub time_zone: Tz,
+    pub seed: String,
+}
+
+impl Default for DailyConfig {
+    fn default() -> Self {
+        Self {
+            time_zone: Tz::UTC,
+            seed: String::new(),
+        }
+    }
+}
+
+impl DailyConfig {
+    pub fn get() -> &'static DailyConfig {
+        DAILY_CONFIG.get_or_init(DailyConfig::default)
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.time_zone).date_naive()
+    }
+
+    /// Picks index of the pokemon of the day from pokemons with the `names`, `None` when there are none.
+    ///
+    /// Pokemon with the lowest `HMAC-SHA256(key = seed, message = "{date}:{name}")` is picked,
+    /// so the pick doesn't depend on the order of the dataset nor on other pokemons in it.
+    /// It changes only when the picked pokemon is removed or when a pokemon scoring lower is added.
+    pub fn pick_index<'a>(
+        &self,
+        date: NaiveDate,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Option<usize> {
+        names
+            .into_iter()
+            .enumerate()
+            .min_by_key(|(_, name)| {
+                let mut mac = Hmac::<Sha256>::new_from_slice(self.seed.as_bytes())
+                    .expect("HMAC can take key of any size");
+                mac.update(format!("{date}:{name}").as_bytes());
+                mac.finalize().into_bytes()
+            })
+            .map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> DailyConfig {
+        DailyConfig {
+            time_zone: Tz::UTC,
+            seed: "seed".into(),
+        }
+    }
+
+    #[test]
+    fn same_date_picks_same_pokemon() {
+        let config = config();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let names = ["gible", "gabite", "garchomp", "trapinch"];
+        let picked = config.pick_index(date, names).unwrap();
+        assert_eq!(config.pick_index(date, names), Some(picked));
+
+        // order of the dataset doesn't matter
+        let mut reversed = names;
+        reversed.reverse();
+        let i = config.pick_index(date, reversed).unwrap();
+        assert_eq!(reversed[i], names[picked]);
+
+        let picks = (1..=28)
+            .map(|day| {
+                let date = NaiveDate::from_ymd_opt(2024, 2, day).unwrap();
+                names[config.pick_index(date, names).unwrap()]
+            })
+            .collect::<std::collections::HashSet<_>>();
+        assert!(picks.len() > 1);
+        assert_eq!(config.pick_index(date, []), None);
+    }
+
+    #[test]
+    fn pick_is_kept_when_pokemon_is_added() {
+        let config = config();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let names = vec!["gible", "gabite", "garchomp", "trapinch"];
+        let picked = names[config.pick_index(date, names.clone()).unwrap()];
+
+        let mut kept = 0;
+        for added in ["vibrava", "flygon", "sandile", "krokorok", "krookodile"] {
+            let mut names = names.clone();
+            names.insert(0, added);
+            let now_picked = names[config.pick_index(date, names.clone()).unwrap()];
+            assert!(now_picked == picked || now_picked == added);
+            if now_picked == picked {
+                kept += 1;
+            }
+        }
+        assert!(kept > 0);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+};
+
+use crate::models::remote_api::ApiPokemonList;
+
+/// Number of consecutive changes kept, clients syncing from older versions have to fetch the whole list.
+pub const MAX_CHANGES: usize = 100;
+
+/// Changes seen by this replica, oldest first.
+///
+/// They are not persisted nor shared, versions from before a restart or from other replicas are unknown.
+static CHANGES: Mutex<VecDeque<DatasetChange>> = Mutex::new(VecDeque::new());
+
+/// How a pokemon differs from its state in an older dataset.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Difference between two consecutive datasets.
+pub struct DatasetChange {
+    pub from: String,
+    pub to: String,
+    pub pokemons: BTreeMap<String, ChangeKind>,
+}
+
+impl DatasetChange {
+    pub fn between(previous: &ApiPokemonList, current: &ApiPokemonList) -> Self {
+        let previous_hashes = previous.content_hashes();
+        let current_hashes = current.content_hashes();
+
+        let mut pokemons = BTreeMap::new();
+        for (name, hash) in current_hashes {
+            match previous_hashes.get(name) {
+                None => {
+                    pokemons.insert(name.clone(), ChangeKind::Added);
+                }
+                Some(previous_hash) if previous_hash != hash => {
+                    pokemons.insert(name.clone(), ChangeKind::Changed);
+                }
+                Some(_) => {}
+            }
+        }
+        for name in previous_hashes.keys() {
+            if !current_hashes.contains_key(name) {
+                pokemons.insert(name.clone(), ChangeKind::Removed);
+            }
+        }
+
+        Self {
+            from: previous.content_version().to_string(),
+            to: current.content_version().to_string(),
+            pokemons,
+        }
+    }
+}
+
+/// Records difference between the replaced and the new dataset, nothing is recorded when their content is the same.
+pub fn record(previous: &ApiPokemonList, current: &ApiPokemonList) {
+    if previous.content_version() == current.content_version() {
+        return;
+    }
+    let change = DatasetChange::between(previous, current);
+    tracing::info!(
+        "Pokemon data changed from version {} to {}, {} pokemons differ",
+        change.from,
+        change.to,
+        change.pokemons.len()
+    );
+
+    let mut changes = CHANGES.lock().unwrap_or_else(|e| e.into_inner());
+    if changes.len() == MAX_CHANGES {
+        changes.pop_front();
+    }
+    changes.push_back(change);
+}
+
+/// Returns how pokemons differ between the `since` and `current` versions.
+///
+/// `None` is returned when the `since` version is unknown or too old.
+pub fn since(since: &str, current: &str) -> Option<BTreeMap<String, ChangeKind>> {
+    let mut pokemons = BTreeMap::new();
+    if since == current {
+        return Some(pokemons);
+    }
+
+    let changes = CHANGES.lock().unwrap_or_else(|e| e.into_inner());
+    // the same version can be in the history multiple times when the dataset changed back
+    let start = changes.iter().rposition(|change| change.from == since)?;
+    let mut version = since;
+    for change in changes.range(start..) {
+        if change.from != version {
+            return None;
+        }
+        for (name, kind) in &change.pokemons {
+            let combined = match (pokemons.get(name).copied(), kind) {
+                (None, kind) => Some(*kind),
+                (Some(ChangeKind::Added), ChangeKind::Removed) => None,
+                (Some(ChangeKind::Added), _) => Some(ChangeKind::Added),
+                (Some(ChangeKind::Removed), ChangeKind::Added) => Some(ChangeKind::Changed),
+                (Some(_), kind) => Some(*kind),
+            };
+            match combined {
+                Some(kind) => pokemons.insert(name.clone(), kind),
+                None => pokemons.remove(name),
+            };
+        }
+        version = &change.to;
+        if version == current {
+            return Some(pokemons);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::remote_api::{
+        test_data::{self, PokemonBuilder},
+        ApiPokemon,
+    };
+
+    fn pokemon(name: &str, picture: &str) -> ApiPokemon {
+        PokemonBuilder::new(name).picture(picture).build()
+    }
+
+    #[test]
+    fn changes_are_combined_since_version() {
+        use ChangeKind::*;
+
+        let first = test_data::pokemon_list(vec![pokemon("sandshrew", "a.png")]);
+        let second = test_data::pokemon_list(vec![
+            pokemon("sandshrew", "a.png"),
+            pokemon("sandslash", "a.png"),
+        ]);
+        let third = test_data::pokemon_list(vec![pokemon("sandslash", "b.png")]);
+        record(&first, &second);
+        // same content isn't recorded
+        record(&second, &test_data::pokemon_list(second.results.clone()));
+        record(&second, &third);
+
+        let versions = [&first, &second, &third].map(|list| list.content_version().to_string());
+        let versions = versions.each_ref().map(String::as_str);
+        let expect = |changes: &[(&str, ChangeKind)]| {
+            Some(
+                changes
+                    .iter()
+                    .map(|(name, kind)| (name.to_string(), *kind))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        };
+        assert_eq!(since(versions[2], versions[2]), expect(&[]));
+        assert_eq!(
+            since(versions[1], versions[2]),
+            expect(&[("sandshrew", Removed), ("sandslash", Changed)])
+        );
+        // pokemon added and then changed is still new to the client
+        assert_eq!(
+            since(versions[0], versions[2]),
+            expect(&[("sandshrew", Removed), ("sandslash", Added)])
+        );
+        assert_eq!(since("unknown", versions[2]), None);
+        // history doesn't lead to the version
+        assert_eq!(since(versions[0], "unknown"), None);
+
+        // dataset changed back, removed and added again pokemon may differ
+        record(&third, &first);
+        assert_eq!(
+            since(versions[1], versions[0]),
+            expect(&[("sandshrew", Changed), ("sandslash", Removed)])
+        );
+        assert_eq!(
+            since(versions[2], versions[0]),
+            expect(&[("sandshrew", Added), ("sandslash", Removed)])
+        );
+
+        // oldest changes are dropped
+        let mut previous = first;
+        for i in 0..MAX_CHANGES {
+            let current = test_data::pokemon_list(vec![pokemon("sandshrew", &format!("{i}.png"))]);
+            record(&previous, &current);
+            previous = current;
+        }
+        assert_eq!(since(versions[2], previous.content_version()), None);
+    }
+}
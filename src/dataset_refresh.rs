@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::{paths::pokemon::get_all::refresh_all_pokemons, peers::PEERS};
+
+/// Refreshes the pokemon dataset once in the background, current dataset is kept when it fails.
+///
+/// Peer replicas are asked first, remote api is used only when none of them has snapshot created after `created_at`.
+pub fn spawn_refresh(req_client: reqwest::Client, created_at: u64) {
+    actix_web::rt::spawn(async move {
+        if let Some(peers) = PEERS.get() {
+            match peers.load_snapshot(&req_client, created_at).await {
+                Ok((peer, created_at)) => {
+                    tracing::info!(
+                        "Pokemon data refreshed from snapshot of peer {} created at {}",
+                        peer,
+                        created_at
+                    );
+                    return;
+                }
+                Err(e) => tracing::info!(
+                    "Pokemon data couldn't be refreshed from peers, using remote api: {}",
+                    e
+                ),
+            }
+        }
+        match refresh_all_pokemons(&req_client).await {
+            Ok(()) => tracing::info!("Pokemon data refreshed"),
+            Err(e) => tracing::warn!(
+                "Refreshing of pokemon data failed, keeping the current data: {}",
+                e
+            ),
+        }
+    });
+}
+
+/// Periodically refreshes the pokemon dataset in the background.
+///
+/// Failed refreshes keep the current dataset, so stale data are served while the remote api is down.
+pub fn spawn_refresher(req_client: reqwest::Client, period: Duration) {
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // first tick completes immediately and the dataset was just prefetched
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match refresh_all_pokemons(&req_client).await {
+                Ok(()) => tracing::info!("Pokemon data refreshed"),
+                Err(e) => tracing::warn!(
+                    "Refreshing of pokemon data failed, keeping the current data: {}",
+                    e
+                ),
+            }
+        }
+    });
+}
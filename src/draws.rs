@@ -0,0 +1,335 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{
+    http::{header::ContentType, StatusCode},
+    HttpResponse,
+};
+use actix_web_grants::authorities::AuthDetails;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use utoipa::IntoParams;
+
+use crate::{
+    audit::AuditLog,
+    certificates::certify_draw,
+    credits::{pull_cost, Banner},
+    jwt_stuff::JwtSubject,
+    leaderboards::LEADERBOARDS,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{
+        audit::{AuditPokemon, AuditRecord, AuditSeed},
+        remote_api::{ApiPokemon, ApiPokemonList},
+    },
+    paths::pokemon::DATASET_VERSION_HEADER,
+    req_util::response_from_error,
+    store::{
+        collection::RecordedPokemon,
+        ledger::{DrawCommit, DrawOutcome, LedgerError},
+        Store,
+    },
+};
+
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DrawQuery {
+    /// Attach signed certificate to every drawn pokemon, requires `sub` claim in JWT token
+    #[serde(default)]
+    pub certify: bool,
+    /// Record drawn pokemons into collection of the requester, requires `sub` claim in JWT token
+    #[serde(default)]
+    pub record: bool,
+    /// Retrying a draw with the same key returns the original draw without charging again, requires `sub` claim in JWT token
+    #[param(min_length = 1, max_length = 255)]
+    pub idempotency_key: Option<String>,
+}
+
+/// Pokemon returned from a draw endpoint.
+pub trait DrawnPokemon: Serialize {
+    fn name(&self) -> &str;
+    /// Data the pokemon was created from.
+    fn api_pokemon(&self) -> &ApiPokemon;
+    fn shiny(&self) -> bool;
+    fn set_certificate(&mut self, certificate: String);
+}
+
+#[derive(Clone)]
+pub struct Draw {
+    pub id: String,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+impl Draw {
+    pub fn generate() -> Self {
+        Self {
+            id: hex::encode(rand::thread_rng().gen::<[u8; 16]>()),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        }
+    }
+}
+
+/// Describes where a draw comes from, used for charging and in the audit log.
+pub struct DrawContext<'a> {
+    pub banner: Banner,
+    pub count: u8,
+    pub subject: Option<&'a JwtSubject>,
+    pub auth_details: &'a AuthDetails,
+    /// Present only for provably fair draws
+    pub seed: Option<AuditSeed>,
+    /// Dataset the pokemons were drawn from
+    pub dataset: &'a ApiPokemonList,
+    /// Parameters of the route besides the count and [`DrawQuery`], e.g. client seed and nonce
+    pub parameters: Vec<(&'static str, String)>,
+}
+
+/// SHA-256 of all parameters the draw was requested with, idempotency key is excluded.
+///
+/// Retry reusing the idempotency key for a draw which differs in any parameter is rejected.
+fn request_fingerprint(
+    banner: Banner,
+    count: u8,
+    query: &DrawQuery,
+    parameters: &[(&'static str, String)],
+) -> String {
+    let mut parameters = parameters.to_vec();
+    parameters.sort_unstable();
+    let request = json!({
+        "banner": banner.as_str(),
+        "count": count,
+        "certify": query.certify,
+        "record": query.record,
+        "parameters": parameters,
+    });
+    hex::encode(Sha256::digest(request.to_string()))
+}
+
+fn audit_error(draw_id: &str, e: impl std::fmt::Display) -> HttpResponse {
+    tracing::error!("Failed to audit draw {}: {}", draw_id, e);
+    response_from_error(
+        format!("Failed to audit draw: {e}"),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+/// Appends the draw to the audit log, draws which couldn't be audited fail.
+async fn audit_draw(audit_log: &'static AuditLog, record: AuditRecord) -> Result<(), HttpResponse> {
+    let draw_id = record.draw_id.clone();
+    let res = tokio::task::spawn_blocking(move || audit_log.append(record)).await;
+    match res {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(audit_error(&draw_id, e)),
+        Err(e) => Err(audit_error(&draw_id, e)),
+    }
+}
+
+/// Charges, certifies and records drawn pokemons as requested by the query and builds the response.
+///
+/// Charging the pull, recording the pokemons and storing the idempotency key happen in a single transaction.
+/// Every new draw is appended to the audit log before it's committed and fails when it can't be audited,
+/// draw which fails to commit afterwards is audited again as reverted. Replayed draws are not audited again.
+pub async fn finish_draw<T: DrawnPokemon, B: Serialize>(
+    mut pokemons: Vec<T>,
+    query: &DrawQuery,
+    context: DrawContext<'_>,
+    body: impl FnOnce(Vec<T>) -> B,
+) -> HttpResponse {
+    let DrawContext {
+        banner,
+        count,
+        subject,
+        auth_details,
+        seed,
+        dataset,
+        parameters,
+    } = context;
+    let audit_log = yeet_error!(AuditLog::get());
+    let draw = Draw::generate();
+    let cost = pull_cost(banner, count);
+
+    let mut grants = auth_details.authorities.iter().cloned().collect::<Vec<_>>();
+    grants.sort_unstable();
+    let audit_record = AuditRecord {
+        id: 0,
+        timestamp: draw.timestamp,
+        draw_id: draw.id.clone(),
+        banner: banner.as_str().to_string(),
+        count,
+        sub: subject.map(|subject| subject.0.clone()),
+        grants,
+        seed,
+        dataset_version: dataset.version().to_string(),
+        cost,
+        result: pokemons
+            .iter()
+            .map(|pokemon| AuditPokemon {
+                name: pokemon.name().to_string(),
+                shiny: pokemon.shiny(),
+            })
+            .collect(),
+        reverted: false,
+    };
+
+    if !query.certify && !query.record && cost == 0 && query.idempotency_key.is_none() {
+        yeet_error!(audit_draw(audit_log, audit_record).await);
+        return resp_200_Ok_json!(body(pokemons));
+    }
+
+    let Some(JwtSubject(sub)) = subject else {
+        return response_from_error(
+            "JWT token doesn't contain sub claim",
+            StatusCode::BAD_REQUEST,
+        );
+    };
+    if matches!(&query.idempotency_key, Some(key) if key.is_empty() || key.len() > 255) {
+        return response_from_error(
+            "Idempotency key must be 1 to 255 characters long",
+            StatusCode::BAD_REQUEST,
+        );
+    }
+
+    if query.certify {
+        yeet_error!(certify_draw(&mut pokemons, sub, &draw));
+    }
+
+    let recorded = if query.record {
+        let res = RecordedPokemon::from_drawn(&pokemons);
+        Some(yeet_error!(
+            res.map_err(|e| e.into_response("Failed to record draw"))
+        ))
+    } else {
+        None
+    };
+    let res = serde_json::to_string(&body(pokemons));
+    let response = yeet_error!(res.map_err(|e| {
+        response_from_error(
+            format!("Failed to serialize draw: {e}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }));
+
+    let store = yeet_error!(Store::get());
+    let draw_id = draw.id.clone();
+    let revert_record = AuditRecord {
+        reverted: true,
+        ..audit_record.clone()
+    };
+    let res = store
+        .commit_draw(
+            DrawCommit {
+                sub: sub.clone(),
+                draw,
+                banner,
+                cost,
+                idempotency_key: query.idempotency_key.clone(),
+                request: request_fingerprint(banner, count, query, &parameters),
+                recorded,
+                response: response.clone(),
+                dataset_version: dataset.content_version().to_string(),
+            },
+            move || audit_log.append(audit_record),
+            move || audit_log.append(revert_record),
+        )
+        .await;
+    match res {
+        Ok(Ok(DrawOutcome::Committed)) => {
+            if query.record {
+                LEADERBOARDS.invalidate();
+            }
+            HttpResponse::Ok()
+                .insert_header(ContentType::json())
+                .body(response)
+        }
+        Ok(Ok(DrawOutcome::Replayed {
+            response,
+            dataset_version,
+        })) => {
+            let mut res = HttpResponse::Ok();
+            res.insert_header(ContentType::json())
+                .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"));
+            // the original draw may come from an older dataset than the current one
+            if let Some(version) = dataset_version {
+                res.insert_header((DATASET_VERSION_HEADER, version));
+            }
+            res.body(response)
+        }
+        Ok(Err(LedgerError::InsufficientCredits { balance, cost })) => response_from_error(
+            format!("Draw costs {cost} credits but balance is only {balance}"),
+            StatusCode::PAYMENT_REQUIRED,
+        ),
+        Ok(Err(LedgerError::IdempotencyKeyReused)) => response_from_error(
+            "Idempotency key was already used for a different draw",
+            StatusCode::CONFLICT,
+        ),
+        Ok(Err(LedgerError::InvalidAmount)) => {
+            response_from_error("Invalid credit amount", StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Ok(Err(LedgerError::Audit(e))) => audit_error(&draw_id, e),
+        Err(e) => e.into_response("Failed to record draw"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(certify: bool, record: bool, key: &str) -> DrawQuery {
+        DrawQuery {
+            certify,
+            record,
+            idempotency_key: Some(key.into()),
+        }
+    }
+
+    #[test]
+    fn fingerprint_covers_all_parameters() {
+        let seed = |client_seed: &str, nonce: u64| {
+            vec![
+                ("client_seed", client_seed.to_string()),
+                ("nonce", nonce.to_string()),
+            ]
+        };
+        let fingerprint =
+            request_fingerprint(Banner::Fair, 3, &query(false, true, "a"), &seed("lucky", 1));
+        // idempotency key and order of the parameters don't matter
+        let mut reordered = seed("lucky", 1);
+        reordered.reverse();
+        assert_eq!(
+            request_fingerprint(Banner::Fair, 3, &query(false, true, "b"), &reordered),
+            fingerprint
+        );
+
+        let differing = [
+            request_fingerprint(
+                Banner::Random,
+                3,
+                &query(false, true, "a"),
+                &seed("lucky", 1),
+            ),
+            request_fingerprint(Banner::Fair, 4, &query(false, true, "a"), &seed("lucky", 1)),
+            request_fingerprint(Banner::Fair, 3, &query(true, true, "a"), &seed("lucky", 1)),
+            request_fingerprint(
+                Banner::Fair,
+                3,
+                &query(false, false, "a"),
+                &seed("lucky", 1),
+            ),
+            request_fingerprint(Banner::Fair, 3, &query(false, true, "a"), &seed("lucky", 2)),
+            request_fingerprint(
+                Banner::Fair,
+                3,
+                &query(false, true, "a"),
+                &seed("unlucky", 1),
+            ),
+        ];
+        for other in differing {
+            assert_ne!(other, fingerprint);
+        }
+    }
+}
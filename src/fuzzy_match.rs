@@ -0,0 +1,21 @@
+/// Lowercases the name and drops everything but letters and digits so "Mr. Mime" equals "mr-mime".
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Checks whether the guess is close enough to the name.
+///
+/// Names are normalized first and one typo (Levenshtein edit) is allowed for every 5 characters of the name,
+/// names shorter than 5 characters have to match exactly.
+pub fn name_matches(guess: &str, name: &str) -> bool {
+    let guess = normalize_name(guess);
+    let name = normalize_name(name);
+    if guess.is_empty() || name.is_empty() {
+        return false;
+    }
+    let allowed_edits = name.chars().count() / 5;
+    strsim::levenshtein(&guess, &name) <= allowed_edits
+}
@@ -1,7 +1,7 @@
 use actix_web_grants::authorities::AuthDetails;
 use futures::future::LocalBoxFuture;
-use jsonwebtoken::{DecodingKey, Validation};
-use serde::{de::DeserializeOwned, Deserialize};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
 use std::{
     collections::HashSet,
     future::{ready, Ready},
@@ -10,20 +10,64 @@ use std::{
 
 use actix_web::{
     body::EitherBody,
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
     error::ErrorBadRequest,
-    http::header::{self, HeaderValue},
-    Error, HttpMessage,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    Error, FromRequest, HttpMessage, HttpRequest,
+};
+
+use crate::{
+    empty_error::EmptyError,
+    json_error::JsonError,
+    peers::{peer_token_validation, PEER_GRANT_PREFIX},
+    IS_DEBUG_ON,
 };
 
 #[derive(Deserialize)]
 struct TokenData {
     pub grants: HashSet<String>,
+    pub sub: Option<String>,
+}
+
+/// Subject (`sub` claim) of the JWT token the request was authorized with.
+#[derive(Clone)]
+pub struct JwtSubject(pub String);
+
+impl FromRequest for JwtSubject {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<JwtSubject>()
+                .cloned()
+                .ok_or_else(|| {
+                    let code = StatusCode::BAD_REQUEST;
+                    let msg = "JWT token doesn't contain sub claim";
+                    if unsafe { IS_DEBUG_ON } {
+                        JsonError::new(msg, code).into()
+                    } else {
+                        EmptyError::new(code).into()
+                    }
+                }),
+        )
+    }
+}
+
+/// Key of the secret shared by peer replicas, tokens signed with it can grant only the peer routes.
+pub struct PeerKey {
+    decoding_key: DecodingKey,
+    validation: Validation,
 }
 
 pub struct JwtGrantsMiddleware {
     decoding_key: Arc<DecodingKey>,
     validation: Arc<Validation>,
+    peer_key: Option<Arc<PeerKey>>,
     #[allow(clippy::type_complexity)]
     err_handler: Option<Arc<dyn Fn(JwtDecodeErrors) -> Error + Send + Sync>>,
 }
@@ -33,10 +77,20 @@ impl JwtGrantsMiddleware {
         Self {
             decoding_key: Arc::new(decoding_key),
             validation: Arc::new(validation),
+            peer_key: None,
             err_handler: None,
         }
     }
 
+    /// Accepts HS256 tokens minted by peer replicas with the shared secret too.
+    pub fn peer_secret(mut self, secret: &[u8]) -> Self {
+        self.peer_key = Some(Arc::new(PeerKey {
+            decoding_key: DecodingKey::from_secret(secret),
+            validation: peer_token_validation(),
+        }));
+        self
+    }
+
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
         F: Fn(JwtDecodeErrors) -> Error + Send + Sync + 'static,
@@ -63,6 +117,7 @@ where
             service,
             decoding_key: self.decoding_key.clone(),
             validation: self.validation.clone(),
+            peer_key: self.peer_key.clone(),
             err_handler: self.err_handler.clone(),
         }))
     }
@@ -72,6 +127,7 @@ pub struct JwtGrantsService<S> {
     service: S,
     decoding_key: Arc<DecodingKey>,
     validation: Arc<Validation>,
+    peer_key: Option<Arc<PeerKey>>,
     #[allow(clippy::type_complexity)]
     err_handler: Option<Arc<dyn Fn(JwtDecodeErrors) -> Error + Send + Sync>>,
 }
@@ -95,18 +151,40 @@ impl JwtDecodeErrors {
     }
 }
 
-fn decode_jwt<T: DeserializeOwned>(
+fn decode_jwt(
     header_value: &HeaderValue,
     decoding_key: &DecodingKey,
     validation: &Validation,
-) -> Result<T, JwtDecodeErrors> {
+    peer_key: Option<&PeerKey>,
+) -> Result<TokenData, JwtDecodeErrors> {
     let Ok(header_value) = header_value.to_str() else {
         return Err(JwtDecodeErrors::InvalidAuthHeader);
     };
     if !header_value.starts_with("Bearer ") {
         return Err(JwtDecodeErrors::InvalidJWTHeader);
     }
-    match jsonwebtoken::decode::<T>(&header_value[7..], decoding_key, validation) {
+    let token = &header_value[7..];
+
+    if let Some(peer_key) = peer_key {
+        if matches!(jsonwebtoken::decode_header(token), Ok(header) if header.alg == Algorithm::HS256)
+        {
+            return match jsonwebtoken::decode::<TokenData>(
+                token,
+                &peer_key.decoding_key,
+                &peer_key.validation,
+            ) {
+                Ok(mut data) => {
+                    data.claims
+                        .grants
+                        .retain(|grant| grant.starts_with(PEER_GRANT_PREFIX));
+                    Ok(data.claims)
+                }
+                Err(e) => Err(JwtDecodeErrors::InvalidJWTToken(e)),
+            };
+        }
+    }
+
+    match jsonwebtoken::decode::<TokenData>(token, decoding_key, validation) {
         Ok(data) => Ok(data.claims),
         Err(e) => Err(JwtDecodeErrors::InvalidJWTToken(e)),
     }
@@ -128,12 +206,21 @@ where
         let auth_header_value = req.headers().get(header::AUTHORIZATION).cloned();
 
         if let Some(auth_header_value) = auth_header_value {
-            let claims = decode_jwt(&auth_header_value, &self.decoding_key, &self.validation);
+            let claims = decode_jwt(
+                &auth_header_value,
+                &self.decoding_key,
+                &self.validation,
+                self.peer_key.as_deref(),
+            );
             match claims {
-                Ok(TokenData { grants }) => {
-                    req.extensions_mut().insert(AuthDetails {
+                Ok(TokenData { grants, sub }) => {
+                    let mut extensions = req.extensions_mut();
+                    extensions.insert(AuthDetails {
                         authorities: Arc::new(grants),
                     });
+                    if let Some(sub) = sub {
+                        extensions.insert(JwtSubject(sub));
+                    }
                 }
                 Err(e) => {
                     return Box::pin(ready(Ok(req
@@ -153,3 +240,29 @@ where
         Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
     }
 }
+
+#[cfg(test)]
+const TEST_SECRET: &[u8] = b"pokemon-api-test";
+
+#[cfg(test)]
+impl JwtGrantsMiddleware {
+    /// Middleware accepting tokens created by [`test_authorization`].
+    pub fn test() -> Self {
+        let mut validation = Validation::new(jsonwebtoken::Algorithm::HS256);
+        validation.set_required_spec_claims::<&str>(&[]);
+        validation.validate_exp = false;
+        Self::new(DecodingKey::from_secret(TEST_SECRET), validation)
+    }
+}
+
+/// Returns authorization header with token containing the grants and the subject.
+#[cfg(test)]
+pub fn test_authorization(sub: Option<&str>, grants: &[&str]) -> (header::HeaderName, String) {
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &serde_json::json!({ "grants": grants, "sub": sub }),
+        &jsonwebtoken::EncodingKey::from_secret(TEST_SECRET),
+    )
+    .expect("test token is signed");
+    (header::AUTHORIZATION, format!("Bearer {token}"))
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    daily::DailyConfig,
+    models::remote_api::ApiPokemon,
+    store::{
+        leaderboards::{LeaderboardRow, Score},
+        Store, StoreError,
+    },
+};
+
+pub const SHINY_RARITY_MULTIPLIER: u32 = 16;
+pub const MAX_LEADERBOARD_SIZE: u32 = 100;
+
+pub static LEADERBOARDS: LazyLock<LeaderboardCache> = LazyLock::new(LeaderboardCache::default);
+
+/// Rarity score of a drawn pokemon, `256 - capture_rate` multiplied by [`SHINY_RARITY_MULTIPLIER`] for shinies.
+///
+/// Pokemons with unknown capture rate score as the easiest to catch.
+pub fn rarity_score(api_pokemon: &ApiPokemon, shiny: bool) -> u32 {
+    let capture_rate = api_pokemon
+        .species
+        .as_ref()
+        .and_then(|species| species.capture_rate)
+        .unwrap_or(u8::MAX);
+    let score = 256 - capture_rate as u32;
+    if shiny {
+        score * SHINY_RARITY_MULTIPLIER
+    } else {
+        score
+    }
+}
+
+/// Day of the timestamp in the time zone the pokemon of the day changes in.
+pub fn day_of(timestamp: u64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&DailyConfig::get().time_zone)
+        .date_naive()
+}
+
+/// Timestamp of the start of the day in the time zone the pokemon of the day changes in.
+pub fn start_of(day: NaiveDate) -> u64 {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    midnight
+        .and_local_timezone(DailyConfig::get().time_zone)
+        .earliest()
+        .map(|start| start.timestamp())
+        .unwrap_or_else(|| midnight.and_utc().timestamp())
+        .max(0) as u64
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardWindow {
+    Daily,
+    Weekly,
+    #[default]
+    AllTime,
+}
+
+impl LeaderboardWindow {
+    /// First day of the window, `None` for all-time.
+    ///
+    /// Days start at midnight in the time zone the pokemon of the day changes in and weeks start on Monday.
+    pub fn since(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            LeaderboardWindow::Daily => Some(today),
+            LeaderboardWindow::Weekly => {
+                Some(today - Days::new(today.weekday().num_days_from_monday() as u64))
+            }
+            LeaderboardWindow::AllTime => None,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    /// Time window of the leaderboard, defaults to all_time
+    #[param(inline)]
+    pub window: Option<LeaderboardWindow>,
+    /// Maximal number of returned entries, defaults to 10
+    #[param(minimum = 1, maximum = 100)]
+    pub limit: Option<u32>,
+}
+
+impl LeaderboardQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(10).clamp(1, MAX_LEADERBOARD_SIZE) as usize
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Board {
+    Score(Score),
+    /// Completion of the generation with `size` drawable pokemons
+    Generation {
+        generation: u8,
+        size: usize,
+    },
+}
+
+struct CachedLeaderboard {
+    version: u64,
+    since: Option<NaiveDate>,
+    rows: Arc<Vec<LeaderboardRow>>,
+}
+
+/// Computed leaderboards, recomputed only after new draws were recorded or the window moved.
+///
+/// Leaderboards themselves are computed from per-day aggregates which are updated with every recorded draw,
+/// so no recomputation ever scans the draw history.
+#[derive(Default)]
+pub struct LeaderboardCache {
+    version: AtomicU64,
+    entries: Mutex<HashMap<(Board, LeaderboardWindow), CachedLeaderboard>>,
+}
+
+impl LeaderboardCache {
+    /// Marks all cached leaderboards as outdated, has to be called after draws are recorded.
+    pub fn invalidate(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Returns first day of the window and top [`MAX_LEADERBOARD_SIZE`] entries of the leaderboard.
+    pub async fn get(
+        &self,
+        store: &Store,
+        board: Board,
+        window: LeaderboardWindow,
+    ) -> Result<(Option<NaiveDate>, Arc<Vec<LeaderboardRow>>), StoreError> {
+        let since = window.since(DailyConfig::get().today());
+        let version = self.version.load(Ordering::Acquire);
+        if let Some(cached) = self.entries.lock().unwrap().get(&(board, window)) {
+            if cached.version == version && cached.since == since {
+                return Ok((since, cached.rows.clone()));
+            }
+        }
+
+        let rows = Arc::new(match board {
+            Board::Score(score) => {
+                store
+                    .leaderboard(score, since, MAX_LEADERBOARD_SIZE)
+                    .await?
+            }
+            Board::Generation { generation, size } => {
+                store
+                    .generation_leaderboard(
+                        generation,
+                        size,
+                        since.map(start_of).unwrap_or_default(),
+                        MAX_LEADERBOARD_SIZE,
+                    )
+                    .await?
+            }
+        });
+        self.entries.lock().unwrap().insert(
+            (board, window),
+            CachedLeaderboard {
+                version,
+                since,
+                rows: rows.clone(),
+            },
+        );
+        Ok((since, rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        credits::Banner,
+        draws::Draw,
+        models::remote_api::test_data,
+        store::{self, collection::RecordedPokemon, ledger::DrawCommit},
+    };
+
+    async fn record(store: &Store, sub: &str, timestamp: u64, pokemons: &[(&str, bool)]) {
+        let draw = Draw {
+            timestamp,
+            ..Draw::generate()
+        };
+        let res = store
+            .commit_draw(
+                DrawCommit {
+                    sub: sub.into(),
+                    draw,
+                    banner: Banner::Random,
+                    cost: 0,
+                    idempotency_key: None,
+                    request: String::new(),
+                    recorded: Some(
+                        pokemons
+                            .iter()
+                            .map(|(name, shiny)| RecordedPokemon::test(name, *shiny))
+                            .collect(),
+                    ),
+                    response: String::new(),
+                    dataset_version: String::new(),
+                },
+                || Ok(0),
+                || Ok(0),
+            )
+            .await;
+        assert!(matches!(res, Ok(Ok(_))));
+    }
+
+    fn now() -> u64 {
+        chrono::Utc::now().timestamp() as u64
+    }
+
+    fn values(rows: &[LeaderboardRow]) -> Vec<(&str, u64)> {
+        rows.iter()
+            .map(|row| (row.sub.as_str(), row.value))
+            .collect()
+    }
+
+    #[test]
+    fn rarity_score_follows_capture_rate() {
+        let mut api_pokemon = test_data::pokemon("garchomp");
+        assert_eq!(rarity_score(&api_pokemon, false), 211);
+        assert_eq!(
+            rarity_score(&api_pokemon, true),
+            211 * SHINY_RARITY_MULTIPLIER
+        );
+        api_pokemon.species.as_mut().unwrap().capture_rate = None;
+        assert_eq!(rarity_score(&api_pokemon, false), 1);
+        api_pokemon.species = None;
+        assert_eq!(rarity_score(&api_pokemon, true), SHINY_RARITY_MULTIPLIER);
+    }
+
+    #[test]
+    fn windows_start_today_or_on_monday() {
+        let thursday = NaiveDate::from_ymd_opt(2024, 5, 16).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 13).unwrap();
+        assert_eq!(LeaderboardWindow::Daily.since(thursday), Some(thursday));
+        assert_eq!(LeaderboardWindow::Weekly.since(thursday), Some(monday));
+        assert_eq!(LeaderboardWindow::Weekly.since(monday), Some(monday));
+        assert_eq!(LeaderboardWindow::AllTime.since(thursday), None);
+        assert_eq!(start_of(monday), 1715558400);
+        assert_eq!(day_of(1715558400 - 1), monday.pred_opt().unwrap());
+    }
+
+    #[tokio::test]
+    async fn scores_are_summed_per_window() {
+        let store = store::open_temporary();
+        let leaderboards = LeaderboardCache::default();
+        record(store, "ash", now(), &[("pikachu", true), ("eevee", false)]).await;
+        record(store, "gary", now(), &[("eevee", false)]).await;
+        record(store, "gary", now() - 8 * 24 * 60 * 60, &[("onix", true)]).await;
+
+        let board = Board::Score(Score::Rarity);
+        let (since, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::Daily)
+            .await
+            .unwrap();
+        assert_eq!(since, Some(DailyConfig::get().today()));
+        assert_eq!(values(&rows), [("ash", 211 * 17), ("gary", 211)]);
+        let (_, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::AllTime)
+            .await
+            .unwrap();
+        assert_eq!(values(&rows), [("ash", 211 * 17), ("gary", 211 * 17)]);
+
+        let board = Board::Score(Score::Shinies);
+        let (_, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::Weekly)
+            .await
+            .unwrap();
+        assert_eq!(values(&rows), [("ash", 1)]);
+    }
+
+    #[tokio::test]
+    async fn generation_is_completed_by_the_first_to_draw_all_pokemons() {
+        let store = store::open_temporary();
+        let leaderboards = LeaderboardCache::default();
+        let start = now() - 60;
+        record(store, "ash", start, &[("gible", false)]).await;
+        record(
+            store,
+            "gary",
+            start + 1,
+            &[("gible", false), ("gible", false)],
+        )
+        .await;
+        record(store, "gary", start + 2, &[("gabite", false)]).await;
+        record(store, "ash", start + 3, &[("gabite", false)]).await;
+
+        let board = Board::Generation {
+            generation: 4,
+            size: 2,
+        };
+        let (_, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::AllTime)
+            .await
+            .unwrap();
+        assert_eq!(values(&rows), [("gary", start + 2), ("ash", start + 3)]);
+    }
+
+    #[tokio::test]
+    async fn cached_leaderboards_are_recomputed_after_invalidation() {
+        let store = store::open_temporary();
+        let leaderboards = LeaderboardCache::default();
+        let board = Board::Score(Score::Shinies);
+        record(store, "ash", now(), &[("pikachu", true)]).await;
+        let (_, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::AllTime)
+            .await
+            .unwrap();
+        assert_eq!(values(&rows), [("ash", 1)]);
+
+        record(store, "gary", now(), &[("eevee", true), ("onix", true)]).await;
+        let (_, cached) = leaderboards
+            .get(store, board, LeaderboardWindow::AllTime)
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&rows, &cached));
+
+        leaderboards.invalidate();
+        let (_, rows) = leaderboards
+            .get(store, board, LeaderboardWindow::AllTime)
+            .await
+            .unwrap();
+        assert_eq!(values(&rows), [("gary", 2), ("ash", 1)]);
+    }
+}
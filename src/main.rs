@@ -1,7 +1,9 @@
+use std::time::Duration;
+
 use actix_web::{
     http::StatusCode,
     middleware::{Compress, Logger, NormalizePath, TrailingSlash},
-    web::{self, Data, JsonConfig, PathConfig},
+    web::{self, Data, JsonConfig, PathConfig, QueryConfig},
     App, HttpServer,
 };
 use actix_web_grants::{GrantErrorConfig, GrantsConfig};
@@ -13,17 +15,38 @@ use utoipa::OpenApi;
 use utoipa_scalar::{Scalar, Servable};
 use utoipauto::utoipauto;
 
+mod audit;
 mod cache;
+mod cache_backend;
+mod certificates;
+mod credits;
+mod daily;
+mod dataset_changes;
+mod dataset_refresh;
 mod docs;
+mod draws;
 mod empty_error;
+mod fuzzy_match;
 mod json_error;
 mod jwt_stuff;
+mod leaderboards;
 mod macros;
+mod metrics;
 mod models;
+mod negative_cache;
 mod paths;
+mod peers;
+mod precompressed;
+mod provably_fair;
 mod queries;
+mod quiz;
 mod req_caching;
 mod req_util;
+mod snapshot;
+mod store;
+#[cfg(test)]
+mod test_util;
+mod upstream;
 
 async fn default_handler_debug(req: actix_web::HttpRequest) -> impl actix_web::Responder {
     actix_web::HttpResponse::NotFound().body(format!("{:#?}", req))
@@ -77,6 +100,328 @@ async fn main() -> std::io::Result<()> {
             }),
     };
 
+    let signing_key = match std::env::var("SIGNING_KEY") {
+        Ok(key) => {
+            tracing::info!("Using signing key from environment");
+            Some(key)
+        }
+        Err(_) => std::fs::read_to_string("./signing_key")
+            .inspect(|_| {
+                tracing::info!("Using signing key from filesystem");
+            })
+            .ok(),
+    };
+    match signing_key.map(|key| certificates::CertificateSigner::from_rsa_pem(&key)) {
+        Some(Ok(signer)) => {
+            // certificates signed with the key users authenticate with could be passed off as their tokens
+            match signer.has_public_key(&decoding_key) {
+                Ok(false) => {}
+                Ok(true) => {
+                    tracing::error!("Signing key must be distinct from decoding key");
+                    tracing::info!("Fatal error encountered halting!");
+                    std::thread::park();
+                    panic!();
+                }
+                Err(e) => {
+                    tracing::error!("Parsing of decoding key failed with error: {}", e);
+                    tracing::info!("Fatal error encountered halting!");
+                    std::thread::park();
+                    panic!();
+                }
+            }
+            let _ = certificates::CERTIFICATE_SIGNER.set(signer);
+            tracing::info!("Pokemon certificates are enabled");
+        }
+        Some(Err(e)) => {
+            tracing::error!("Parsing of signing key failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        None => tracing::info!(
+            "Signing key was not found in either environment nor filesystem, pokemon certificates are disabled"
+        ),
+    }
+
+    {
+        let time_zone = match std::env::var("DAILY_TIME_ZONE") {
+            Ok(time_zone) => time_zone.parse::<chrono_tz::Tz>().unwrap_or_else(|e| {
+                tracing::error!("Parsing of daily time zone failed with error: {}", e);
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }),
+            Err(_) => chrono_tz::Tz::UTC,
+        };
+        tracing::info!("Pokemon of the day changes at midnight in {}", time_zone);
+
+        let seed = std::env::var("DAILY_SEED").unwrap_or_else(|_| {
+            tracing::warn!(
+                "Daily seed is not set, upcoming pokemons of the day can be predicted by anyone"
+            );
+            String::new()
+        });
+        let _ = daily::DAILY_CONFIG.set(daily::DailyConfig { time_zone, seed });
+    }
+
+    match std::env::var("QUIZ_SECRET") {
+        Ok(secret) => {
+            let _ = quiz::QUIZ_CONFIG.set(quiz::QuizConfig::new(secret.into_bytes()));
+        }
+        Err(_) => tracing::warn!(
+            "Quiz secret is not set, quiz challenges can be answered only on this replica until restart"
+        ),
+    }
+
+    match std::env::var("PULL_COSTS").map(|costs| credits::PullCosts::parse(&costs)) {
+        Ok(Ok(costs)) => {
+            let _ = credits::PULL_COSTS.set(costs);
+            tracing::info!("Pull costs are enabled");
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of pull costs failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => tracing::info!("Pull costs are not set, all draws are free"),
+    }
+
+    {
+        let store_path = std::env::var("STORE_PATH").unwrap_or("./store.sqlite".into());
+        match store::Store::open(&store_path) {
+            Ok(store) => {
+                let _ = store::STORE.set(store);
+                tracing::info!("Using store at {}", store_path);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Opening of store at {} failed with error: {}",
+                    store_path,
+                    e
+                );
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }
+        }
+    }
+
+    {
+        let store = store::STORE.get().expect("store is opened above");
+        match provably_fair::ProvablyFair::load(store).await {
+            Ok(provably_fair) => {
+                tracing::info!(
+                    "Server seed of fair draws is committed as {}",
+                    provably_fair.current().await.hash()
+                );
+                let _ = provably_fair::PROVABLY_FAIR.set(provably_fair);
+            }
+            Err(e) => {
+                tracing::error!("Loading of server seeds failed with error: {}", e);
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }
+        }
+    }
+
+    {
+        let audit_log_dir = std::env::var("AUDIT_LOG_DIR").unwrap_or("./audit_log".into());
+        let max_file_size = match std::env::var("AUDIT_LOG_MAX_FILE_SIZE") {
+            Ok(size) => size.parse::<u64>().unwrap_or_else(|e| {
+                tracing::error!(
+                    "Parsing of audit log max file size failed with error: {}",
+                    e
+                );
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }),
+            Err(_) => audit::DEFAULT_MAX_FILE_SIZE,
+        };
+        match audit::AuditLog::open(&audit_log_dir, max_file_size) {
+            Ok(audit_log) => {
+                let _ = audit::AUDIT_LOG.set(audit_log);
+                tracing::info!("Using audit log at {}", audit_log_dir);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Opening of audit log at {} failed with error: {}",
+                    audit_log_dir,
+                    e
+                );
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }
+        }
+    }
+
+    match std::env::var("CACHE_TTL").map(|secs| secs.parse::<u64>()) {
+        Ok(Ok(secs)) if secs > 0 => {
+            let _ = cache::DEFAULT_TTL.set(Duration::from_secs(secs));
+            tracing::info!("Cached data expire after {} seconds", secs);
+        }
+        Ok(Ok(_)) | Err(_) => tracing::info!("Cached data never expire"),
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of cache TTL failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+    }
+
+    match std::env::var("CACHE_SOFT_TTL").map(|secs| secs.parse::<u64>()) {
+        Ok(Ok(secs)) if secs > 0 => {
+            let _ = cache::SOFT_TTL.set(Duration::from_secs(secs));
+        }
+        Ok(Ok(_)) => {
+            tracing::error!("Cache soft TTL must be positive");
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of cache soft TTL failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => {}
+    }
+    tracing::info!(
+        "Pokemons fetched by name are refreshed in background after {} seconds",
+        cache::soft_ttl().as_secs()
+    );
+
+    match std::env::var("NEGATIVE_CACHE_TTL").map(|secs| secs.parse::<u64>()) {
+        Ok(Ok(secs)) => {
+            let _ = negative_cache::NEGATIVE_TTL.set(Duration::from_secs(secs));
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of negative cache TTL failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => {}
+    }
+
+    match std::env::var("CACHE_REDIS_ADDRESS") {
+        Ok(address) => {
+            tracing::info!("Sharing cached data through redis at {}", address);
+            let password = std::env::var("CACHE_REDIS_PASSWORD").ok();
+            let backend = cache_backend::RedisBackend::new(address, password);
+            let _ = cache_backend::CACHE_BACKEND.set(Box::new(backend));
+        }
+        Err(_) => tracing::info!("Cached data are not shared with other replicas"),
+    }
+
+    let peer_secret = std::env::var("PEER_SECRET").ok();
+    match std::env::var("PEERS") {
+        Ok(urls) => {
+            let Some(secret) = &peer_secret else {
+                tracing::error!("Peer secret is not set");
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            };
+            match peers::Peers::new(&urls, secret.as_bytes()) {
+                Ok(peers) => {
+                    tracing::info!("Peer replicas are {}", peers.urls().join(", "));
+                    let _ = peers::PEERS.set(peers);
+                }
+                Err(e) => {
+                    tracing::error!("Parsing of peers failed with error: {}", e);
+                    tracing::info!("Fatal error encountered halting!");
+                    std::thread::park();
+                    panic!();
+                }
+            }
+        }
+        Err(_) => tracing::info!("Peer replicas are not set"),
+    }
+    if peer_secret.is_some() {
+        tracing::info!("Requests from peer replicas are accepted");
+    }
+
+    cache::CACHE.register(&*paths::pokemon::get_by_name::POKEMONS);
+    cache::CACHE.register(&*paths::pokemon::random_team::TYPE_EFFICACIES);
+    cache::CACHE.register(&*paths::quiz::SILHOUETTES);
+    cache::CACHE.register(&*paths::quiz::SPECIES_NAMES);
+    cache::CACHE.register(&*negative_cache::NOT_FOUND);
+
+    match std::env::var("CACHE_LIMITS").map(|limits| cache::parse_limits(&limits)) {
+        Ok(Ok(limits)) => {
+            for (name, limit) in limits {
+                let Some(namespace) = cache::CACHE.namespace(&name) else {
+                    tracing::error!("Cache namespace {} doesn't exist", name);
+                    tracing::info!("Fatal error encountered halting!");
+                    std::thread::park();
+                    panic!();
+                };
+                namespace.set_limit(limit);
+                tracing::info!("Cache namespace {} is limited to {} entries", name, limit);
+            }
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of cache limits failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => tracing::info!(
+            "Cache limits are not set, all namespaces are limited to {} entries",
+            cache::DEFAULT_NAMESPACE_LIMIT
+        ),
+    }
+
+    let upstream_timeout = match std::env::var("UPSTREAM_TIMEOUT").map(|secs| secs.parse::<u64>()) {
+        Ok(Ok(secs)) => Duration::from_secs(secs),
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of remote api timeout failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => upstream::DEFAULT_TIMEOUT,
+    };
+    let upstream_deadline = match std::env::var("UPSTREAM_DEADLINE").map(|secs| secs.parse::<u64>())
+    {
+        Ok(Ok(secs)) => Duration::from_secs(secs),
+        Ok(Err(e)) => {
+            tracing::error!("Parsing of remote api deadline failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+        Err(_) => upstream::DEFAULT_DEADLINE,
+    };
+    let upstream_urls =
+        std::env::var("UPSTREAM_ENDPOINTS").unwrap_or(upstream::DEFAULT_ENDPOINT.into());
+    match upstream::Upstream::new(&upstream_urls, upstream_timeout, upstream_deadline) {
+        Ok(upstream) => {
+            tracing::info!(
+                "Remote api endpoints are {} with timeout of {} seconds and deadline of {} seconds",
+                upstream
+                    .endpoints()
+                    .map(|(url, _)| url)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                upstream_timeout.as_secs(),
+                upstream_deadline.as_secs()
+            );
+            let _ = upstream::UPSTREAM.set(upstream);
+        }
+        Err(e) => {
+            tracing::error!("Parsing of remote api endpoints failed with error: {}", e);
+            tracing::info!("Fatal error encountered halting!");
+            std::thread::park();
+            panic!();
+        }
+    }
+
     let req_client = reqwest::Client::builder()
             .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36")
             .build()
@@ -102,7 +447,48 @@ async fn main() -> std::io::Result<()> {
             .map(|val| val == "1")
             .unwrap_or_default();
 
+        let snapshot_path =
+            std::env::var("SNAPSHOT_PATH").unwrap_or("./pokemon_snapshot.bin".into());
+        let snapshot_path = snapshot::SNAPSHOT_PATH.get_or_init(|| snapshot_path.into());
+
+        let mut loaded_snapshot = false;
         if !fetch_unverified_enabled || prefetch_enabled {
+            match paths::pokemon::get_all::load_snapshot(snapshot_path).await {
+                Ok(created_at) => {
+                    tracing::info!(
+                        "Pokemon data loaded from snapshot at {} created at {}, refreshing them in background",
+                        snapshot_path.display(),
+                        created_at
+                    );
+                    dataset_refresh::spawn_refresh(req_client.clone(), created_at);
+                    loaded_snapshot = true;
+                }
+                Err(e) => tracing::warn!(
+                    "Loading of pokemon data snapshot at {} failed with error: {}",
+                    snapshot_path.display(),
+                    e
+                ),
+            }
+        }
+
+        if !loaded_snapshot && (!fetch_unverified_enabled || prefetch_enabled) {
+            if let Some(peers) = peers::PEERS.get() {
+                match peers.load_snapshot(&req_client, 0).await {
+                    Ok((peer, created_at)) => {
+                        tracing::info!(
+                            "Pokemon data loaded from snapshot of peer {} created at {}, refreshing them in background",
+                            peer,
+                            created_at
+                        );
+                        dataset_refresh::spawn_refresh(req_client.clone(), created_at);
+                        loaded_snapshot = true;
+                    }
+                    Err(e) => tracing::warn!("Loading of pokemon data from peers failed: {}", e),
+                }
+            }
+        }
+
+        if !loaded_snapshot && (!fetch_unverified_enabled || prefetch_enabled) {
             tracing::info!("Prefetching data");
             let res = paths::pokemon::get_all::get_all_pokemons(&req_client).await;
             if res.is_err() && !fetch_unverified_enabled {
@@ -114,11 +500,25 @@ async fn main() -> std::io::Result<()> {
                 panic!();
             }
         }
+
+        match std::env::var("DATA_REFRESH_INTERVAL").map(|secs| secs.parse::<u64>()) {
+            Ok(Ok(secs)) if secs > 0 => {
+                tracing::info!("Pokemon data are refreshed every {} seconds", secs);
+                dataset_refresh::spawn_refresher(req_client.clone(), Duration::from_secs(secs));
+            }
+            Ok(Ok(_)) | Err(_) => tracing::info!("Pokemon data are never refreshed"),
+            Ok(Err(e)) => {
+                tracing::error!("Parsing of data refresh interval failed with error: {}", e);
+                tracing::info!("Fatal error encountered halting!");
+                std::thread::park();
+                panic!();
+            }
+        }
     }
 
     let bind_address = std::env::var("ADDRESS").unwrap_or("0.0.0.0:80".into());
 
-    HttpServer::new(move || {
+    let res = HttpServer::new(move || {
         let jwt_decoding_key = match jsonwebtoken::DecodingKey::from_rsa_pem(decoding_key.as_bytes()) {
             Ok(key) => key,
             Err(e) => {
@@ -139,6 +539,10 @@ async fn main() -> std::io::Result<()> {
             if is_debug_on { json_error::config_json_error_handler } else { empty_error::config_empty_error_handler }
         );
 
+        let query_config = QueryConfig::default().error_handler(
+            if is_debug_on { json_error::config_json_error_handler } else { empty_error::config_empty_error_handler }
+        );
+
         let grants_string_error_config = GrantErrorConfig::<String>::default()
             .error_handler(move |condition, grants| {
                 use actix_web::ResponseError;
@@ -159,13 +563,16 @@ async fn main() -> std::io::Result<()> {
             if is_debug_on { JsonError::new("Authorization header is missing".to_string(), code).into() } else { EmptyError::new(code).into() }
         });
 
-        let jwt_grants_middleware = JwtGrantsMiddleware::new(
+        let mut jwt_grants_middleware = JwtGrantsMiddleware::new(
             jwt_decoding_key,
             jwt_validation,
         ).error_handler(move |error| {
             let code = StatusCode::BAD_REQUEST;
             if is_debug_on { JsonError::new(error.to_error_string(), code).into() } else { EmptyError::new(code).into() }
         });
+        if let Some(secret) = &peer_secret {
+            jwt_grants_middleware = jwt_grants_middleware.peer_secret(secret.as_bytes());
+        }
 
         let mut app = App::new()
             .wrap(jwt_grants_middleware)
@@ -174,6 +581,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(Compress::default())
             .app_data(json_config)
             .app_data(path_config)
+            .app_data(query_config)
             .app_data(grants_config)
             .app_data(grants_string_error_config)
             .app_data(Data::new(req_client.clone()));
@@ -190,5 +598,14 @@ async fn main() -> std::io::Result<()> {
     .bind(bind_address)
     .expect("Failed to bind server to address")
     .run()
-    .await
+    .await;
+
+    // seed of draws made before shutdown is revealed, so they can be verified without waiting for a rotation
+    if let Some(provably_fair) = provably_fair::PROVABLY_FAIR.get() {
+        match provably_fair.reveal_current().await {
+            Ok(revealed) => tracing::info!("Server seed {} revealed on shutdown", revealed.hash()),
+            Err(e) => tracing::error!("Revealing of server seed on shutdown failed: {}", e),
+        }
+    }
+    res
 }
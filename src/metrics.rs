@@ -0,0 +1,330 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Upper bounds of latency buckets in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub static CACHE_LOOKUPS: Family<2, Counter> = Family::new(
+    "pokemon_api_cache_lookups_total",
+    "Lookups of cache entries, result is hit, miss, backend_hit, coalesced when the value was fetched while waiting, revalidate when stale value started its background refresh or stale when it was already being refreshed",
+    ["namespace", "result"],
+);
+pub static CACHE_EVICTIONS: Family<1, Counter> = Family::new(
+    "pokemon_api_cache_evictions_total",
+    "Entries evicted because their namespace was full",
+    ["namespace"],
+);
+pub static CACHE_STALE_SERVED: Family<1, Counter> = Family::new(
+    "pokemon_api_cache_stale_served_total",
+    "Expired values served because the remote api failed",
+    ["namespace"],
+);
+pub static CACHE_WRITE_LOCK_WAIT: Family<1, Histogram> = Family::new(
+    "pokemon_api_cache_write_lock_wait_seconds",
+    "Time spent waiting for write lock of a cache entry",
+    ["namespace"],
+);
+pub static UPSTREAM_DURATION: Family<1, Histogram> = Family::new(
+    "pokemon_api_upstream_request_duration_seconds",
+    "Duration of requests to the remote api, outcome is ok or the error class",
+    ["outcome"],
+);
+pub static UPSTREAM_ERRORS: Family<1, Counter> = Family::new(
+    "pokemon_api_upstream_errors_total",
+    "Failed requests to the remote api by error class",
+    ["class"],
+);
+pub static UPSTREAM_FAILOVERS: Family<0, Counter> = Family::new(
+    "pokemon_api_upstream_failovers_total",
+    "Requests to the remote api retried on the next endpoint because the previous one failed",
+    [],
+);
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub struct Histogram {
+    /// Count of observations in each bucket, not cumulative
+    buckets: Vec<AtomicU64>,
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: LATENCY_BUCKETS.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Metric of one name and kind with values for every combination of its labels.
+pub struct Family<const N: usize, M> {
+    name: &'static str,
+    help: &'static str,
+    labels: [&'static str; N],
+    metrics: RwLock<BTreeMap<[&'static str; N], Arc<M>>>,
+}
+
+impl<const N: usize, M: Default> Family<N, M> {
+    pub const fn new(name: &'static str, help: &'static str, labels: [&'static str; N]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            metrics: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns metric with the label values, it's created on the first use.
+    pub fn with(&self, values: [&'static str; N]) -> Arc<M> {
+        let metrics = self.metrics.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(metric) = metrics.get(&values) {
+            return metric.clone();
+        }
+        drop(metrics);
+        let mut metrics = self.metrics.write().unwrap_or_else(|e| e.into_inner());
+        metrics.entry(values).or_default().clone()
+    }
+
+    fn label_set(&self, values: &[&'static str; N], extra: Option<(&str, &str)>) -> String {
+        let pairs = self
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(label, value)| (*label, *value))
+            .chain(extra)
+            .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+            .collect::<Vec<_>>();
+        if pairs.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", pairs.join(","))
+        }
+    }
+
+    fn snapshot(&self) -> Vec<([&'static str; N], Arc<M>)> {
+        let metrics = self.metrics.read().unwrap_or_else(|e| e.into_inner());
+        metrics
+            .iter()
+            .map(|(values, metric)| (*values, metric.clone()))
+            .collect()
+    }
+}
+
+impl<const N: usize> Family<N, Counter> {
+    pub fn inc(&self, values: [&'static str; N]) {
+        self.with(values).inc();
+    }
+
+    pub fn render(&self, out: &mut String) {
+        write_header(out, self.name, self.help, "counter");
+        for (values, counter) in self.snapshot() {
+            let value = counter.0.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "{}{} {value}",
+                self.name,
+                self.label_set(&values, None)
+            );
+        }
+    }
+}
+
+impl<const N: usize> Family<N, Histogram> {
+    pub fn observe(&self, values: [&'static str; N], duration: Duration) {
+        self.with(values).observe(duration);
+    }
+
+    pub fn render(&self, out: &mut String) {
+        write_header(out, self.name, self.help, "histogram");
+        for (values, histogram) in self.snapshot() {
+            let mut cumulative = 0;
+            for (le, bucket) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let labels = self.label_set(&values, Some(("le", &le.to_string())));
+                let _ = writeln!(out, "{}_bucket{labels} {cumulative}", self.name);
+            }
+            let count = histogram.count.load(Ordering::Relaxed);
+            let labels = self.label_set(&values, Some(("le", "+Inf")));
+            let _ = writeln!(out, "{}_bucket{labels} {count}", self.name);
+            let labels = self.label_set(&values, None);
+            let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+            let _ = writeln!(out, "{}_sum{labels} {sum}", self.name);
+            let _ = writeln!(out, "{}_count{labels} {count}", self.name);
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Writes metric computed at the time of scraping, samples are pairs of label set and value.
+pub fn render_samples(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    samples: impl IntoIterator<Item = (Vec<(&'static str, String)>, u64)>,
+) {
+    write_header(out, name, help, kind);
+    for (labels, value) in samples {
+        let labels = labels
+            .iter()
+            .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{}}} {value}", labels.join(","));
+        }
+    }
+}
+
+/// Renders all metrics recorded by the service in Prometheus text format.
+pub fn render(out: &mut String) {
+    CACHE_LOOKUPS.render(out);
+    CACHE_EVICTIONS.render(out);
+    CACHE_STALE_SERVED.render(out);
+    CACHE_WRITE_LOCK_WAIT.render(out);
+    UPSTREAM_DURATION.render(out);
+    UPSTREAM_ERRORS.render(out);
+    UPSTREAM_FAILOVERS.render(out);
+}
+
+/// Returns class of a failed request to the remote api.
+pub fn error_class(error: &reqwest::Error) -> &'static str {
+    match error.status() {
+        Some(status) if status.is_client_error() => "status_4xx",
+        Some(status) if status.is_server_error() => "status_5xx",
+        Some(_) => "status_other",
+        None if error.is_timeout() => "timeout",
+        None if error.is_connect() => "connect",
+        None if error.is_decode() => "decode",
+        None if error.is_body() => "body",
+        None => "other",
+    }
+}
+
+/// Records request to the remote api which started at `started`, `error_class` is `None` when it succeeded.
+pub fn observe_upstream(started: Instant, error_class: Option<&'static str>) {
+    UPSTREAM_DURATION.observe([error_class.unwrap_or("ok")], started.elapsed());
+    if let Some(class) = error_class {
+        UPSTREAM_ERRORS.inc([class]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_are_rendered_by_labels() {
+        let family =
+            Family::<2, Counter>::new("test_lookups_total", "Lookups", ["namespace", "result"]);
+        family.inc(["pokemon", "hit"]);
+        family.inc(["pokemon", "hit"]);
+        family.inc(["pokemon", "miss"]);
+        let mut out = String::new();
+        family.render(&mut out);
+        assert_eq!(
+            out,
+            "# HELP test_lookups_total Lookups\n\
+             # TYPE test_lookups_total counter\n\
+             test_lookups_total{namespace=\"pokemon\",result=\"hit\"} 2\n\
+             test_lookups_total{namespace=\"pokemon\",result=\"miss\"} 1\n"
+        );
+
+        let family = Family::<0, Counter>::new("test_failovers_total", "Failovers", []);
+        family.inc([]);
+        let mut out = String::new();
+        family.render(&mut out);
+        assert!(out.ends_with("\ntest_failovers_total 1\n"), "{out}");
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let family = Family::<1, Histogram>::new("test_duration_seconds", "Duration", ["outcome"]);
+        family.observe(["ok"], Duration::from_millis(20));
+        family.observe(["ok"], Duration::from_millis(300));
+        // slower than the last bucket
+        family.observe(["ok"], Duration::from_secs(20));
+        let mut out = String::new();
+        family.render(&mut out);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[1], "# TYPE test_duration_seconds histogram");
+        for line in [
+            "test_duration_seconds_bucket{outcome=\"ok\",le=\"0.01\"} 0",
+            "test_duration_seconds_bucket{outcome=\"ok\",le=\"0.025\"} 1",
+            "test_duration_seconds_bucket{outcome=\"ok\",le=\"0.5\"} 2",
+            "test_duration_seconds_bucket{outcome=\"ok\",le=\"10\"} 2",
+            "test_duration_seconds_bucket{outcome=\"ok\",le=\"+Inf\"} 3",
+            "test_duration_seconds_sum{outcome=\"ok\"} 20.32",
+            "test_duration_seconds_count{outcome=\"ok\"} 3",
+        ] {
+            assert!(lines.contains(&line), "{line} is missing in\n{out}");
+        }
+        // one line per bucket, +Inf, sum and count
+        assert_eq!(lines.len(), 2 + LATENCY_BUCKETS.len() + 3);
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let mut out = String::new();
+        render_samples(
+            &mut out,
+            "test_endpoint_healthy",
+            "Health",
+            "gauge",
+            [
+                (vec![("endpoint", "http://a\"b\\c\n".to_string())], 1),
+                (vec![], 0),
+            ],
+        );
+        assert_eq!(
+            out,
+            "# HELP test_endpoint_healthy Health\n\
+             # TYPE test_endpoint_healthy gauge\n\
+             test_endpoint_healthy{endpoint=\"http://a\\\"b\\\\c\\n\"} 1\n\
+             test_endpoint_healthy 0\n"
+        );
+    }
+}
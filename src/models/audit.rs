@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Entry of the draw audit log.
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct AuditRecord {
+    /// Sequential id of the entry
+    pub id: u64,
+    /// Unix timestamp of the draw in seconds
+    pub timestamp: u64,
+    pub draw_id: String,
+    /// One of get_random, get_random_fair or generate
+    pub banner: String,
+    pub count: u8,
+    /// Subject of the requester when JWT token contains sub claim
+    pub sub: Option<String>,
+    /// Grants of the requester
+    pub grants: Vec<String>,
+    /// Present only for provably fair draws
+    pub seed: Option<AuditSeed>,
+    /// Fingerprint of the dataset pokemons were drawn from
+    pub dataset_version: String,
+    /// Credits charged for the draw
+    pub cost: u64,
+    pub result: Vec<AuditPokemon>,
+    /// Set on the entry appended when the audited draw couldn't be committed, such draw never happened
+    #[serde(default)]
+    pub reverted: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct AuditSeed {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+pub struct AuditPokemon {
+    pub name: String,
+    pub shiny: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditPage {
+    pub entries: Vec<AuditRecord>,
+    /// Pass as `after_id` to get the next page, not present on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_after_id: Option<u64>,
+}
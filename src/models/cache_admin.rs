@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheOverview {
+    /// Verified pokemon dataset, missing when it wasn't fetched yet
+    pub dataset: Option<DatasetInfo>,
+    pub namespaces: Vec<CacheNamespaceInfo>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DatasetInfo {
+    /// Fingerprint of the dataset
+    pub version: String,
+    pub pokemons: usize,
+    /// Seconds until the dataset expires, missing when it never expires
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheNamespaceInfo {
+    pub name: String,
+    /// Number of entries cached by this replica
+    pub entries: usize,
+    /// Maximal number of entries
+    pub limit: usize,
+    /// Whether entries are shared with other replicas through the cache backend
+    pub shared: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheEntryInfo {
+    pub namespace: String,
+    pub key: String,
+    /// Cached value, missing while it's being fetched or when fetching failed
+    #[schema(value_type = Option<Object>)]
+    pub value: Option<serde_json::Value>,
+    /// Seconds until the value expires, zero when it's expired and missing when it never expires
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CacheInvalidation {
+    /// Number of entries removed from this replica
+    pub removed: usize,
+    /// Peer replicas which failed to invalidate the entries, they keep serving them until they expire
+    #[serde(default)]
+    pub failed_peers: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DatasetRefresh {
+    pub refreshed: bool,
+    /// Why the refresh failed, the previous dataset is kept
+    pub error: Option<String>,
+    pub previous_version: Option<String>,
+    /// Version of the dataset served from now on
+    pub version: Option<String>,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyCertificate {
+    pub certificate: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerifiedCertificate {
+    /// Subject the pokemon was drawn for
+    pub sub: String,
+    /// Unix timestamp of the draw
+    pub iat: u64,
+    pub draw_id: String,
+    /// Position of the pokemon in the draw
+    pub index: usize,
+    /// Drawn pokemon as it was returned by the draw endpoint
+    pub pokemon: serde_json::Value,
+}
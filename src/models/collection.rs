@@ -0,0 +1,33 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct Collection {
+    pub pokemons: Vec<OwnedPokemon>,
+    /// Number of owned pokemons per pokemon name
+    pub counts: Vec<PokemonCount>,
+    /// Number of different pokemons owned
+    pub unique: usize,
+    /// Number of all available pokemons
+    pub pokedex_size: usize,
+    /// Percentage of the pokedex completed
+    pub completion: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OwnedPokemon {
+    pub id: i64,
+    pub draw_id: String,
+    pub name: String,
+    pub shiny: bool,
+    /// Unix timestamp of when the pokemon was obtained
+    pub obtained_at: u64,
+    /// Pokemon as it was returned by the draw endpoint
+    pub pokemon: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PokemonCount {
+    pub name: String,
+    pub count: usize,
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::store::ledger::CreditTransactionRow;
+
+#[derive(Deserialize, ToSchema)]
+pub struct TopUp {
+    /// Subject whose balance is topped up
+    pub sub: String,
+    /// Number of credits to add
+    #[schema(minimum = 1)]
+    pub amount: u64,
+    /// Reason of the top-up shown in transaction history
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Credits {
+    pub balance: i64,
+    /// Transactions of the requester, newest first
+    pub transactions: Vec<CreditTransaction>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreditTransaction {
+    pub id: i64,
+    /// Positive for top-ups, negative for pulls
+    pub amount: i64,
+    /// Balance after the transaction
+    pub balance: i64,
+    /// One of top_up or pull
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub draw_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    /// Subject of the admin who granted the top-up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granted_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub created_at: u64,
+}
+
+impl From<CreditTransactionRow> for CreditTransaction {
+    fn from(value: CreditTransactionRow) -> Self {
+        Self {
+            id: value.id,
+            amount: value.amount,
+            balance: value.balance,
+            kind: value.kind,
+            draw_id: value.draw_id,
+            banner: value.banner,
+            granted_by: value.granted_by,
+            note: value.note,
+            created_at: value.created_at,
+        }
+    }
+}
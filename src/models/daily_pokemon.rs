@@ -0,0 +1,12 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::pokemon::Pokemon;
+
+#[derive(Serialize, ToSchema)]
+pub struct DailyPokemon<'a> {
+    #[schema(value_type = String, format = Date)]
+    pub date: NaiveDate,
+    pub pokemon: Pokemon<'a>,
+}
@@ -0,0 +1,18 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::pokemon::Pokemon;
+
+#[derive(Serialize, ToSchema)]
+pub struct DatasetChanges<'a> {
+    /// Version the changes are relative to
+    pub since: String,
+    /// Current version, use it as `since` in the next request
+    pub version: String,
+    /// Pokemons which were not in the `since` version
+    pub added: Vec<Pokemon<'a>>,
+    /// Pokemons whose data changed
+    pub changed: Vec<Pokemon<'a>>,
+    /// Names of pokemons which are not in the current version anymore
+    pub removed: Vec<String>,
+}
@@ -0,0 +1,14 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::pokemon::Pokemon;
+
+#[derive(Serialize, ToSchema)]
+pub struct FairDraw<'a> {
+    pub server_seed_hash: String,
+    pub client_seed: String,
+    pub nonce: u64,
+    /// Fingerprint of the names the pokemons were drawn from, pass it to `/fair/verify`
+    pub dataset_version: String,
+    pub pokemons: Vec<Pokemon<'a>>,
+}
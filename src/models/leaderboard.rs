@@ -0,0 +1,44 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::leaderboards::LeaderboardWindow;
+
+#[derive(Serialize, ToSchema)]
+pub struct Leaderboard {
+    pub window: LeaderboardWindow,
+    /// First day included in the window, not present for all_time
+    #[schema(value_type = Option<String>, format = Date)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<NaiveDate>,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub sub: String,
+    pub score: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GenerationLeaderboard {
+    pub generation: u8,
+    /// Number of drawable pokemons in the generation
+    pub pokedex_size: usize,
+    pub window: LeaderboardWindow,
+    /// First day included in the window, not present for all_time
+    #[schema(value_type = Option<String>, format = Date)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<NaiveDate>,
+    /// Subjects who drew every pokemon of the generation, the earliest first
+    pub entries: Vec<GenerationCompletion>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GenerationCompletion {
+    pub rank: usize,
+    pub sub: String,
+    /// Unix timestamp of the draw which completed the generation
+    pub completed_at: u64,
+}
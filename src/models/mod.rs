@@ -1,10 +1,27 @@
+pub mod audit;
+pub mod cache_admin;
+pub mod certificate;
+pub mod collection;
+pub mod credits;
+pub mod daily_pokemon;
+pub mod dataset_changes;
+pub mod fair_draw;
+pub mod jwks;
+pub mod leaderboard;
+pub mod nature;
 pub mod pokemon;
+pub mod pokemon_instance;
 pub mod pokemon_pictures;
+pub mod quiz;
+pub mod random_team;
 pub mod remote_api;
+pub mod server_seed;
+pub mod stats;
+pub mod trade;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct DataWrapper<T> {
     pub data: T,
 }
@@ -0,0 +1,142 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Nature {
+    Hardy,
+    Lonely,
+    Brave,
+    Adamant,
+    Naughty,
+    Bold,
+    Docile,
+    Relaxed,
+    Impish,
+    Lax,
+    Timid,
+    Hasty,
+    Serious,
+    Jolly,
+    Naive,
+    Modest,
+    Mild,
+    Quiet,
+    Bashful,
+    Rash,
+    Calm,
+    Gentle,
+    Sassy,
+    Careful,
+    Quirky,
+}
+
+pub struct NatureMultipliers {
+    pub attack: (u32, u32),
+    pub defense: (u32, u32),
+    pub speed: (u32, u32),
+    pub special_attack: (u32, u32),
+    pub special_defense: (u32, u32),
+}
+
+impl Nature {
+    pub const ALL: [Nature; 25] = [
+        Nature::Hardy,
+        Nature::Lonely,
+        Nature::Brave,
+        Nature::Adamant,
+        Nature::Naughty,
+        Nature::Bold,
+        Nature::Docile,
+        Nature::Relaxed,
+        Nature::Impish,
+        Nature::Lax,
+        Nature::Timid,
+        Nature::Hasty,
+        Nature::Serious,
+        Nature::Jolly,
+        Nature::Naive,
+        Nature::Modest,
+        Nature::Mild,
+        Nature::Quiet,
+        Nature::Bashful,
+        Nature::Rash,
+        Nature::Calm,
+        Nature::Gentle,
+        Nature::Sassy,
+        Nature::Careful,
+        Nature::Quirky,
+    ];
+
+    /// Returns stat multipliers as fractions.
+    ///
+    /// Natures are ordered so that `index / 5` is the increased stat
+    /// and `index % 5` is the decreased stat in order attack, defense, speed, special attack, special defense.
+    /// When both are the same the nature is neutral.
+    pub fn multipliers(self) -> NatureMultipliers {
+        let index = self as usize;
+        let (increased, decreased) = (index / 5, index % 5);
+        let multiplier = |stat| match stat {
+            _ if increased == decreased => (1, 1),
+            _ if stat == increased => (11, 10),
+            _ if stat == decreased => (9, 10),
+            _ => (1, 1),
+        };
+
+        NatureMultipliers {
+            attack: multiplier(0),
+            defense: multiplier(1),
+            speed: multiplier(2),
+            special_attack: multiplier(3),
+            special_defense: multiplier(4),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_array(multipliers: NatureMultipliers) -> [(u32, u32); 5] {
+        [
+            multipliers.attack,
+            multipliers.defense,
+            multipliers.speed,
+            multipliers.special_attack,
+            multipliers.special_defense,
+        ]
+    }
+
+    #[test]
+    fn neutral_natures_change_nothing() {
+        for nature in [
+            Nature::Hardy,
+            Nature::Docile,
+            Nature::Serious,
+            Nature::Bashful,
+            Nature::Quirky,
+        ] {
+            assert_eq!(as_array(nature.multipliers()), [(1, 1); 5]);
+        }
+    }
+
+    #[test]
+    fn natures_raise_and_lower_their_stats() {
+        let adamant = Nature::Adamant.multipliers();
+        assert_eq!(adamant.attack, (11, 10));
+        assert_eq!(adamant.special_attack, (9, 10));
+        let timid = Nature::Timid.multipliers();
+        assert_eq!(timid.speed, (11, 10));
+        assert_eq!(timid.attack, (9, 10));
+        let sassy = Nature::Sassy.multipliers();
+        assert_eq!(sassy.special_defense, (11, 10));
+        assert_eq!(sassy.speed, (9, 10));
+
+        for nature in Nature::ALL {
+            let multipliers = as_array(nature.multipliers());
+            let raised = multipliers.iter().filter(|m| **m == (11, 10)).count();
+            let lowered = multipliers.iter().filter(|m| **m == (9, 10)).count();
+            assert!((raised, lowered) == (1, 1) || (raised, lowered) == (0, 0));
+        }
+    }
+}
@@ -1,15 +1,40 @@
 use serde::Serialize;
 use utoipa::ToSchema;
 
+use crate::draws::DrawnPokemon;
+
 use super::{
     pokemon_pictures::PokemonPictures,
     remote_api::{ApiPokemon, ApiPokemonSpritesOfficialArtwork},
 };
 
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, ToSchema, Clone)]
 pub struct Pokemon<'a> {
     pub name: &'a str,
     pub pictures: PokemonPictures<'a>,
+    /// Signed certificate of the draw, only present when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+    #[serde(skip)]
+    pub api_pokemon: &'a ApiPokemon,
+}
+
+impl DrawnPokemon for Pokemon<'_> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn api_pokemon(&self) -> &ApiPokemon {
+        self.api_pokemon
+    }
+
+    fn shiny(&self) -> bool {
+        false
+    }
+
+    fn set_certificate(&mut self, certificate: String) {
+        self.certificate = Some(certificate);
+    }
 }
 
 impl<'a> TryFrom<&'a ApiPokemon> for Pokemon<'a> {
@@ -31,6 +56,8 @@ impl<'a> TryFrom<&'a ApiPokemon> for Pokemon<'a> {
                     front_default,
                     front_shiny,
                 },
+                certificate: None,
+                api_pokemon: value,
             });
         }
         Err(())
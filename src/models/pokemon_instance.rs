@@ -0,0 +1,169 @@
+use std::ops::RangeInclusive;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::draws::DrawnPokemon;
+
+use super::{
+    nature::Nature, pokemon::Pokemon, pokemon_pictures::PokemonPictures, remote_api::ApiPokemon,
+    stats::Stats,
+};
+
+pub const SHINY_ODDS: u32 = 4096;
+
+#[derive(Serialize, ToSchema, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Male,
+    Female,
+    Genderless,
+}
+
+#[derive(Serialize, ToSchema, Clone)]
+pub struct PokemonInstance<'a> {
+    pub name: &'a str,
+    pub pictures: PokemonPictures<'a>,
+    pub level: u8,
+    pub gender: Gender,
+    pub nature: Nature,
+    pub ability: &'a str,
+    pub shiny: bool,
+    pub ivs: Stats,
+    pub stats: Stats,
+    /// Signed certificate of the draw, only present when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+    #[serde(skip)]
+    pub api_pokemon: &'a ApiPokemon,
+}
+
+impl DrawnPokemon for PokemonInstance<'_> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn api_pokemon(&self) -> &ApiPokemon {
+        self.api_pokemon
+    }
+
+    fn shiny(&self) -> bool {
+        self.shiny
+    }
+
+    fn set_certificate(&mut self, certificate: String) {
+        self.certificate = Some(certificate);
+    }
+}
+
+impl<'a> PokemonInstance<'a> {
+    /// Generates concrete pokemon from species data.
+    /// Fails when the species data are incomplete.
+    pub fn generate(
+        api_pokemon: &'a ApiPokemon,
+        levels: RangeInclusive<u8>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, ()> {
+        let Pokemon { name, pictures, .. } = Pokemon::try_from(api_pokemon)?;
+        let base_stats = Stats::try_from(api_pokemon.stats.as_slice())?;
+        let gender_rate = api_pokemon.species.as_ref().ok_or(())?.gender_rate;
+
+        let mut abilities = api_pokemon
+            .abilities
+            .iter()
+            .filter(|ability| !ability.is_hidden)
+            .collect::<Vec<_>>();
+        if abilities.is_empty() {
+            abilities = api_pokemon.abilities.iter().collect();
+        }
+        let ability = &abilities.choose(rng).ok_or(())?.ability.name;
+
+        let level = rng.gen_range(levels);
+        let gender = match gender_rate {
+            rate if rate < 0 => Gender::Genderless,
+            rate if rng.gen_range(0..8) < rate => Gender::Female,
+            _ => Gender::Male,
+        };
+        let nature = *Nature::ALL.choose(rng).unwrap();
+        let mut iv = || rng.gen_range(0..=31);
+        let ivs = Stats {
+            hp: iv(),
+            attack: iv(),
+            defense: iv(),
+            special_attack: iv(),
+            special_defense: iv(),
+            speed: iv(),
+        };
+        let shiny = rng.gen_ratio(1, SHINY_ODDS);
+
+        Ok(Self {
+            name,
+            pictures,
+            level,
+            gender,
+            nature,
+            ability,
+            shiny,
+            ivs,
+            stats: Stats::calculate(&base_stats, &ivs, level, nature),
+            certificate: None,
+            api_pokemon,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::models::remote_api::test_data;
+
+    #[test]
+    fn generated_pokemon_is_within_bounds() {
+        let api_pokemon = test_data::pokemon("garchomp");
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            let pokemon = PokemonInstance::generate(&api_pokemon, 10..=20, &mut rng).unwrap();
+            assert!((10..=20).contains(&pokemon.level));
+            assert_eq!(pokemon.ability, "sand-veil");
+            let ivs = pokemon.ivs;
+            assert!([
+                ivs.hp,
+                ivs.attack,
+                ivs.defense,
+                ivs.special_attack,
+                ivs.special_defense,
+                ivs.speed
+            ]
+            .iter()
+            .all(|iv| *iv <= 31));
+        }
+    }
+
+    #[test]
+    fn gender_follows_gender_rate() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut api_pokemon = test_data::pokemon("garchomp");
+        for (gender_rate, expected) in [(-1, "genderless"), (0, "male"), (8, "female")] {
+            api_pokemon.species.as_mut().unwrap().gender_rate = gender_rate;
+            for _ in 0..20 {
+                let pokemon = PokemonInstance::generate(&api_pokemon, 1..=1, &mut rng).unwrap();
+                assert_eq!(serde_json::to_value(pokemon.gender).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_species_is_not_generated() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut api_pokemon = test_data::pokemon("garchomp");
+        api_pokemon.species = None;
+        assert!(PokemonInstance::generate(&api_pokemon, 1..=1, &mut rng).is_err());
+
+        let mut api_pokemon = test_data::pokemon("garchomp");
+        api_pokemon.stats.pop();
+        assert!(PokemonInstance::generate(&api_pokemon, 1..=1, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct QuizChallenge {
+    /// Signed challenge token, pass it to `/quiz/{id}/answer`
+    pub id: String,
+    /// PNG data URL with silhouette of the pokemon
+    pub silhouette: String,
+    /// Unix timestamp after which the challenge can't be answered
+    pub expires_at: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct QuizGuess {
+    /// Pokemon name in any language
+    pub guess: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct QuizAnswer {
+    pub correct: bool,
+    /// Name of the pokemon, revealed only for correct guesses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Language of the matched name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
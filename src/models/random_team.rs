@@ -0,0 +1,31 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::pokemon::Pokemon;
+
+#[derive(Serialize, ToSchema)]
+pub struct RandomTeam<'a> {
+    pub members: Vec<TeamMember<'a>>,
+    /// Effectiveness of every attacking type against the team
+    pub type_weaknesses: Vec<TypeWeakness<'a>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TeamMember<'a> {
+    pub pokemon: Pokemon<'a>,
+    pub types: Vec<&'a str>,
+    pub base_stat_total: u16,
+    pub generation: Option<u8>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TypeWeakness<'a> {
+    /// Attacking type
+    pub r#type: &'a str,
+    /// Number of members taking more than normal damage
+    pub weak: u8,
+    /// Number of members taking less than normal damage
+    pub resistant: u8,
+    /// Number of members taking no damage
+    pub immune: u8,
+}
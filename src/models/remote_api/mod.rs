@@ -1,7 +1,21 @@
 mod pokemon;
+mod pokemon_ability;
 mod pokemon_list;
+mod pokemon_species;
+mod pokemon_species_names;
 mod pokemon_sprites;
+mod pokemon_stat;
+mod pokemon_type;
+#[cfg(test)]
+pub mod test_data;
+mod type_efficacy;
 
 pub use pokemon::*;
+pub use pokemon_ability::*;
 pub use pokemon_list::*;
+pub use pokemon_species::*;
+pub use pokemon_species_names::*;
 pub use pokemon_sprites::*;
+pub use pokemon_stat::*;
+pub use pokemon_type::*;
+pub use type_efficacy::*;
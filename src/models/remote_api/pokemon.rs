@@ -1,10 +1,37 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use super::ApiPokemonSprites;
+use super::{
+    ApiPokemonAbility, ApiPokemonSpecies, ApiPokemonSprites, ApiPokemonStat, ApiPokemonType,
+};
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ApiPokemon {
     pub name: String,
     #[serde(rename = "pokemon_v2_pokemonsprites")]
     pub sprites: Vec<ApiPokemonSprites>,
+    #[serde(rename = "pokemon_v2_pokemonstats")]
+    pub stats: Vec<ApiPokemonStat>,
+    #[serde(rename = "pokemon_v2_pokemonabilities")]
+    pub abilities: Vec<ApiPokemonAbility>,
+    #[serde(rename = "pokemon_v2_pokemontypes")]
+    pub types: Vec<ApiPokemonType>,
+    #[serde(rename = "pokemon_v2_pokemonspecy")]
+    pub species: Option<ApiPokemonSpecies>,
+}
+
+impl ApiPokemon {
+    /// Returns type names ordered by slot.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut types = self.types.iter().collect::<Vec<_>>();
+        types.sort_by_key(|t| t.slot);
+        types.into_iter().map(|t| t.r#type.name.as_str()).collect()
+    }
+
+    pub fn base_stat_total(&self) -> u16 {
+        self.stats.iter().map(|stat| stat.base_stat).sum()
+    }
+
+    pub fn generation(&self) -> Option<u8> {
+        self.species.as_ref()?.generation_id
+    }
 }
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiPokemonAbility {
+    pub is_hidden: bool,
+    #[serde(rename = "pokemon_v2_ability")]
+    pub ability: ApiAbility,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiAbility {
+    pub name: String,
+}
@@ -1,9 +1,132 @@
-use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use super::ApiPokemon;
+use crate::models::pokemon::Pokemon;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct ApiPokemonList {
     #[serde(rename = "pokemon_v2_pokemon")]
     pub results: Vec<ApiPokemon>,
+    #[serde(skip)]
+    version: OnceLock<String>,
+    #[serde(skip)]
+    by_name: OnceLock<HashMap<String, usize>>,
+    #[serde(skip)]
+    content_hashes: OnceLock<HashMap<String, [u8; 32]>>,
+    #[serde(skip)]
+    content_version: OnceLock<String>,
+}
+
+impl ApiPokemonList {
+    /// Fingerprint of the dataset, SHA-256 of all pokemon names in order.
+    pub fn version(&self) -> &str {
+        self.version.get_or_init(|| {
+            let mut hasher = Sha256::new();
+            for pokemon in &self.results {
+                hasher.update(pokemon.name.as_bytes());
+                hasher.update([0]);
+            }
+            hex::encode(hasher.finalize())
+        })
+    }
+
+    /// SHA-256 of every pokemon's data by its name.
+    pub fn content_hashes(&self) -> &HashMap<String, [u8; 32]> {
+        self.content_hashes.get_or_init(|| {
+            self.results
+                .iter()
+                .map(|pokemon| {
+                    let data = serde_json::to_vec(pokemon).unwrap_or_default();
+                    (pokemon.name.clone(), Sha256::digest(data).into())
+                })
+                .collect()
+        })
+    }
+
+    /// Fingerprint of the dataset content, it changes whenever any pokemon is added, removed or changed.
+    ///
+    /// Unlike [`Self::version`] it's not suitable for verifying draws, it's used to detect changes between refreshes.
+    pub fn content_version(&self) -> &str {
+        self.content_version.get_or_init(|| {
+            let hashes = self.content_hashes();
+            let mut hasher = Sha256::new();
+            for pokemon in &self.results {
+                hasher.update(pokemon.name.as_bytes());
+                hasher.update([0]);
+                hasher.update(hashes[&pokemon.name]);
+            }
+            hex::encode(hasher.finalize())
+        })
+    }
+
+    /// Returns pokemon by its name.
+    pub fn get(&self, name: &str) -> Option<&ApiPokemon> {
+        let by_name = self.by_name.get_or_init(|| {
+            self.results
+                .iter()
+                .enumerate()
+                .map(|(index, pokemon)| (pokemon.name.clone(), index))
+                .collect()
+        });
+        by_name.get(name).map(|&index| &self.results[index])
+    }
+
+    /// Checks the dataset is usable, it has to contain drawable pokemons and their names must be unique.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut names = HashSet::with_capacity(self.results.len());
+        if let Some(pokemon) = self
+            .results
+            .iter()
+            .find(|pokemon| !names.insert(&pokemon.name))
+        {
+            return Err(format!(
+                "Pokemon {} is in the dataset multiple times",
+                pokemon.name
+            ));
+        }
+        if !self
+            .results
+            .iter()
+            .any(|pokemon| Pokemon::try_from(pokemon).is_ok())
+        {
+            return Err("Dataset contains no valid pokemons".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::remote_api::test_data::{pokemon, pokemon_list};
+
+    #[test]
+    fn valid_dataset_is_accepted() {
+        let list = pokemon_list(vec![pokemon("gible"), pokemon("gabite")]);
+        assert!(list.validate().is_ok());
+        assert_eq!(list.get("gabite").unwrap().name, "gabite");
+        assert!(list.get("garchomp").is_none());
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let list = pokemon_list(vec![pokemon("gible"), pokemon("gible")]);
+        assert_eq!(
+            list.validate().unwrap_err(),
+            "Pokemon gible is in the dataset multiple times"
+        );
+    }
+
+    #[test]
+    fn dataset_without_drawable_pokemons_is_rejected() {
+        assert!(pokemon_list(Vec::new()).validate().is_err());
+        let mut gible = pokemon("gible");
+        gible.sprites.clear();
+        assert!(pokemon_list(vec![gible]).validate().is_err());
+    }
 }
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiPokemonSpecies {
+    /// Chance of being female in eighths, -1 for genderless
+    pub gender_rate: i8,
+    pub generation_id: Option<u8>,
+    /// The lower the harder the pokemon is to catch, 3 to 255, missing for some species
+    pub capture_rate: Option<u8>,
+}
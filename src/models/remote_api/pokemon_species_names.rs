@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiPokemonSpeciesNamesList {
+    #[serde(rename = "pokemon_v2_pokemon")]
+    pub results: Vec<ApiPokemonSpeciesNamesPokemon>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiPokemonSpeciesNamesPokemon {
+    #[serde(rename = "pokemon_v2_pokemonspecy")]
+    pub species: Option<ApiPokemonSpeciesNames>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiPokemonSpeciesNames {
+    #[serde(rename = "pokemon_v2_pokemonspeciesnames")]
+    pub names: Vec<ApiPokemonSpeciesName>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiPokemonSpeciesName {
+    pub name: String,
+    #[serde(rename = "pokemon_v2_language")]
+    pub language: ApiLanguage,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiLanguage {
+    pub name: String,
+}
+
+impl ApiPokemonSpeciesNamesList {
+    /// Returns all localized names of the pokemon.
+    pub fn names(&self) -> impl Iterator<Item = &ApiPokemonSpeciesName> {
+        self.results
+            .iter()
+            .filter_map(|pokemon| pokemon.species.as_ref())
+            .flat_map(|species| &species.names)
+    }
+}
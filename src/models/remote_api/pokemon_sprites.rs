@@ -1,11 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ApiPokemonSprites {
     pub sprites: ApiPokemonSpritesOfficialArtwork,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ApiPokemonSpritesOfficialArtwork {
     pub front_default: Option<String>,
     pub front_shiny: Option<String>,
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiPokemonStat {
+    pub base_stat: u16,
+    #[serde(rename = "pokemon_v2_stat")]
+    pub stat: ApiStat,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiStat {
+    pub name: String,
+}
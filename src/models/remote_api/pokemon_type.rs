@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiPokemonType {
+    pub slot: u8,
+    #[serde(rename = "pokemon_v2_type")]
+    pub r#type: ApiType,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiType {
+    pub name: String,
+}
@@ -0,0 +1,48 @@
+use serde_json::{json, Value};
+
+use super::{ApiPokemon, ApiPokemonList};
+
+/// Complete pokemon as the remote api returns it, shared with the integration tests.
+const POKEMON_FIXTURE: &str = include_str!("../../../tests/common/pokemon.json");
+
+/// Pokemon from the fixture with the base stats of Garchomp, its fields can be overridden before it's built.
+pub struct PokemonBuilder {
+    pokemon: Value,
+}
+
+impl PokemonBuilder {
+    /// Pokemon named `name` with pictures at example.com named after it.
+    pub fn new(name: &str) -> Self {
+        let mut pokemon: Value =
+            serde_json::from_str(POKEMON_FIXTURE).expect("pokemon fixture is valid JSON");
+        pokemon["name"] = json!(name);
+        Self { pokemon }
+            .picture(&format!("https://example.com/{name}.png"))
+            .shiny_picture(&format!("https://example.com/shiny/{name}.png"))
+    }
+
+    pub fn picture(mut self, url: &str) -> Self {
+        self.pokemon["pokemon_v2_pokemonsprites"][0]["sprites"]["front_default"] = json!(url);
+        self
+    }
+
+    pub fn shiny_picture(mut self, url: &str) -> Self {
+        self.pokemon["pokemon_v2_pokemonsprites"][0]["sprites"]["front_shiny"] = json!(url);
+        self
+    }
+
+    pub fn build(self) -> ApiPokemon {
+        serde_json::from_value(self.pokemon).expect("test pokemon deserializes")
+    }
+}
+
+/// Complete pokemon as the remote api returns it, with the base stats of Garchomp.
+pub fn pokemon(name: &str) -> ApiPokemon {
+    PokemonBuilder::new(name).build()
+}
+
+/// Dataset of the pokemons as the remote api returns it.
+pub fn pokemon_list(pokemons: Vec<ApiPokemon>) -> ApiPokemonList {
+    serde_json::from_value(json!({ "pokemon_v2_pokemon": pokemons }))
+        .expect("test pokemon list deserializes")
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiTypeEfficacies {
+    #[serde(rename = "pokemon_v2_type")]
+    pub types: Vec<ApiTypeWithId>,
+    #[serde(rename = "pokemon_v2_typeefficacy")]
+    pub efficacies: Vec<ApiTypeEfficacy>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiTypeWithId {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiTypeEfficacy {
+    pub damage_type_id: u32,
+    pub target_type_id: u32,
+    /// Damage in percents
+    pub damage_factor: u32,
+}
+
+impl ApiTypeEfficacies {
+    /// Builds lookup of damage multipliers by type names.
+    pub fn chart(&self) -> TypeChart<'_> {
+        let names = self
+            .types
+            .iter()
+            .map(|t| (t.id, t.name.as_str()))
+            .collect::<HashMap<_, _>>();
+        let factors = self
+            .efficacies
+            .iter()
+            .filter_map(|e| {
+                let attacking = names.get(&e.damage_type_id)?;
+                let defending = names.get(&e.target_type_id)?;
+                Some(((*attacking, *defending), e.damage_factor as f32 / 100.0))
+            })
+            .collect::<HashMap<_, _>>();
+        let attacking_names = factors
+            .keys()
+            .map(|(attacking, _)| *attacking)
+            .collect::<HashSet<_>>();
+        let attacking = self
+            .types
+            .iter()
+            .map(|t| t.name.as_str())
+            .filter(|name| attacking_names.contains(name))
+            .collect();
+        TypeChart { attacking, factors }
+    }
+}
+
+/// Damage multipliers between types, pairs missing in the remote api deal normal damage.
+pub struct TypeChart<'a> {
+    attacking: Vec<&'a str>,
+    factors: HashMap<(&'a str, &'a str), f32>,
+}
+
+impl<'a> TypeChart<'a> {
+    /// Returns names of types that can be used to attack.
+    pub fn attacking_types(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.attacking.iter().copied()
+    }
+
+    /// Returns damage multiplier of attacking type against pokemon with defending types.
+    pub fn multiplier(&self, attacking: &str, defending: &[&str]) -> f32 {
+        defending
+            .iter()
+            .map(|defending| {
+                self.factors
+                    .get(&(attacking, *defending))
+                    .copied()
+                    .unwrap_or(1.0)
+            })
+            .product()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn efficacies() -> ApiTypeEfficacies {
+        let types = ["normal", "ghost", "fire", "water", "grass", "stellar"];
+        let efficacies = [
+            ("normal", "ghost", 0),
+            ("ghost", "ghost", 200),
+            ("ghost", "normal", 0),
+            ("fire", "grass", 200),
+            ("fire", "water", 50),
+            ("water", "fire", 200),
+            ("grass", "water", 200),
+            ("grass", "fire", 50),
+        ];
+        let id = |name: &str| types.iter().position(|t| *t == name).unwrap() as u32 + 1;
+        ApiTypeEfficacies {
+            types: types
+                .iter()
+                .map(|name| ApiTypeWithId {
+                    id: id(name),
+                    name: name.to_string(),
+                })
+                .collect(),
+            efficacies: efficacies
+                .iter()
+                .map(|(damage, target, factor)| ApiTypeEfficacy {
+                    damage_type_id: id(damage),
+                    target_type_id: id(target),
+                    damage_factor: *factor,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn multipliers_of_types_are_multiplied() {
+        let efficacies = efficacies();
+        let chart = efficacies.chart();
+        assert_eq!(chart.multiplier("fire", &["grass"]), 2.0);
+        assert_eq!(chart.multiplier("fire", &["water"]), 0.5);
+        assert_eq!(chart.multiplier("fire", &["grass", "water"]), 1.0);
+        assert_eq!(chart.multiplier("grass", &["water", "ghost"]), 2.0);
+        assert_eq!(chart.multiplier("water", &["fire", "grass"]), 2.0);
+    }
+
+    #[test]
+    fn immunity_cancels_other_multipliers() {
+        let efficacies = efficacies();
+        let chart = efficacies.chart();
+        assert_eq!(chart.multiplier("normal", &["ghost"]), 0.0);
+        assert_eq!(chart.multiplier("ghost", &["normal", "ghost"]), 0.0);
+    }
+
+    #[test]
+    fn missing_pairs_deal_normal_damage() {
+        let efficacies = efficacies();
+        let chart = efficacies.chart();
+        assert_eq!(chart.multiplier("normal", &["fire"]), 1.0);
+        assert_eq!(chart.multiplier("stellar", &["fire", "water"]), 1.0);
+        assert_eq!(chart.multiplier("unknown", &["fire"]), 1.0);
+        assert_eq!(chart.multiplier("fire", &["unknown"]), 1.0);
+        assert_eq!(chart.multiplier("fire", &[]), 1.0);
+    }
+
+    #[test]
+    fn only_types_dealing_damage_attack() {
+        let efficacies = efficacies();
+        let chart = efficacies.chart();
+        assert_eq!(
+            chart.attacking_types().collect::<Vec<_>>(),
+            ["normal", "ghost", "fire", "water", "grass"]
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct ServerSeedCommitment {
+    pub server_seed_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RevealedServerSeed {
+    pub server_seed_hash: String,
+    pub server_seed: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RotatedServerSeed {
+    pub revealed: RevealedServerSeed,
+    pub next_server_seed_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerifiedDraw {
+    pub server_seed_hash: String,
+    pub server_seed: String,
+    /// Names of the drawn pokemons in the order they were drawn
+    pub pokemons: Vec<String>,
+    /// Version of the dataset the draw was replayed on, it matches the draw only when the dataset didn't change since,
+    /// pass `dataset_version` of the draw to get 409 instead of a replay on a changed dataset
+    pub dataset_version: String,
+}
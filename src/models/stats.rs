@@ -0,0 +1,137 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::{nature::Nature, remote_api::ApiPokemonStat};
+
+#[derive(Serialize, ToSchema, Clone, Copy, Default)]
+pub struct Stats {
+    pub hp: u16,
+    pub attack: u16,
+    pub defense: u16,
+    pub special_attack: u16,
+    pub special_defense: u16,
+    pub speed: u16,
+}
+
+impl TryFrom<&[ApiPokemonStat]> for Stats {
+    type Error = ();
+
+    fn try_from(value: &[ApiPokemonStat]) -> Result<Self, Self::Error> {
+        let find = |name: &str| {
+            value
+                .iter()
+                .find(|stat| stat.stat.name == name)
+                .map(|stat| stat.base_stat)
+                .ok_or(())
+        };
+
+        Ok(Self {
+            hp: find("hp")?,
+            attack: find("attack")?,
+            defense: find("defense")?,
+            special_attack: find("special-attack")?,
+            special_defense: find("special-defense")?,
+            speed: find("speed")?,
+        })
+    }
+}
+
+impl Stats {
+    /// Calculates actual stats from base stats using the formulas from generation III onwards.
+    /// Effort values are always zero for freshly generated pokemons.
+    pub fn calculate(base: &Stats, ivs: &Stats, level: u8, nature: Nature) -> Self {
+        let level = level as u32;
+        let inner = |base: u16, iv: u16| (2 * base as u32 + iv as u32) * level / 100;
+        let other = |base: u16, iv: u16, multiplier: (u32, u32)| {
+            ((inner(base, iv) + 5) * multiplier.0 / multiplier.1) as u16
+        };
+        let multipliers = nature.multipliers();
+
+        Self {
+            // Shedinja is the only pokemon with base hp of 1 and its hp is always 1
+            hp: if base.hp == 1 {
+                1
+            } else {
+                (inner(base.hp, ivs.hp) + level + 10) as u16
+            },
+            attack: other(base.attack, ivs.attack, multipliers.attack),
+            defense: other(base.defense, ivs.defense, multipliers.defense),
+            special_attack: other(
+                base.special_attack,
+                ivs.special_attack,
+                multipliers.special_attack,
+            ),
+            special_defense: other(
+                base.special_defense,
+                ivs.special_defense,
+                multipliers.special_defense,
+            ),
+            speed: other(base.speed, ivs.speed, multipliers.speed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::remote_api::test_data;
+
+    const IVS: Stats = Stats {
+        hp: 24,
+        attack: 12,
+        defense: 30,
+        special_attack: 16,
+        special_defense: 23,
+        speed: 5,
+    };
+
+    #[test]
+    fn base_stats_are_read_from_api_stats() {
+        let pokemon = test_data::pokemon("garchomp");
+        let base = Stats::try_from(pokemon.stats.as_slice()).unwrap();
+        assert_eq!(
+            [
+                base.hp,
+                base.attack,
+                base.defense,
+                base.special_attack,
+                base.special_defense,
+                base.speed
+            ],
+            [108, 130, 95, 80, 85, 102]
+        );
+        assert!(Stats::try_from(&pokemon.stats[1..]).is_err());
+    }
+
+    #[test]
+    fn stats_follow_generation_three_formulas() {
+        let pokemon = test_data::pokemon("garchomp");
+        let base = Stats::try_from(pokemon.stats.as_slice()).unwrap();
+
+        // adamant raises attack and lowers special attack
+        let stats = Stats::calculate(&base, &IVS, 78, Nature::Adamant);
+        assert_eq!(
+            [
+                stats.hp,
+                stats.attack,
+                stats.defense,
+                stats.special_attack,
+                stats.special_defense,
+                stats.speed
+            ],
+            [275, 238, 176, 127, 155, 168]
+        );
+
+        let stats = Stats::calculate(&base, &IVS, 78, Nature::Hardy);
+        assert_eq!((stats.attack, stats.special_attack), (217, 142));
+    }
+
+    #[test]
+    fn hp_of_shedinja_is_always_one() {
+        let base = Stats {
+            hp: 1,
+            ..Default::default()
+        };
+        assert_eq!(Stats::calculate(&base, &IVS, 100, Nature::Hardy).hp, 1);
+    }
+}
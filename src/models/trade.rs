@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::store::trades::{TradeEventRow, TradeRow};
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTrade {
+    /// Subject the trade is offered to
+    pub recipient: String,
+    /// Ids of owned pokemons given by the offerer
+    pub offered: Vec<i64>,
+    /// Ids of pokemons owned by the recipient requested in exchange
+    pub requested: Vec<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Trade {
+    pub id: i64,
+    pub offerer: String,
+    pub recipient: String,
+    /// One of pending, accepted, declined or cancelled
+    pub status: String,
+    pub offered: Vec<i64>,
+    pub requested: Vec<i64>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Audit trail of the trade
+    pub events: Vec<TradeEvent>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TradeEvent {
+    pub sub: String,
+    /// One of created, accepted, declined or cancelled
+    pub action: String,
+    pub created_at: u64,
+}
+
+impl From<TradeRow> for Trade {
+    fn from(value: TradeRow) -> Self {
+        Self {
+            id: value.id,
+            offerer: value.offerer,
+            recipient: value.recipient,
+            status: value.status,
+            offered: value.offered,
+            requested: value.requested,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            events: value.events.into_iter().map(TradeEvent::from).collect(),
+        }
+    }
+}
+
+impl From<TradeEventRow> for TradeEvent {
+    fn from(value: TradeEventRow) -> Self {
+        Self {
+            sub: value.sub,
+            action: value.action,
+            created_at: value.created_at,
+        }
+    }
+}
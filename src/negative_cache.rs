@@ -0,0 +1,80 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock, OnceLock,
+    },
+    time::Duration,
+};
+
+use crate::cache::CacheNamespace;
+
+/// How long a name not found in the remote api is remembered unless configured otherwise.
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60);
+
+pub static NEGATIVE_TTL: OnceLock<Duration> = OnceLock::new();
+
+/// Names not found in the remote api by the namespace they were looked up in, shared with other replicas.
+pub static NOT_FOUND: LazyLock<CacheNamespace<(&'static str, String), ()>> =
+    LazyLock::new(|| CacheNamespace::shared("not_found"));
+
+static SAVED_UPSTREAM_CALLS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns whether the name was recently not found in the remote api, so it doesn't have to be fetched again.
+///
+/// Misses are stored in the [`NOT_FOUND`] namespace, which is bounded like every other one.
+/// The lookup never locks entries, so names which aren't missing don't wait for each other.
+pub async fn is_known_missing(namespace: &'static str, name: &str) -> bool {
+    // misses recorded by other replicas are only in the backend, entries are created only for them
+    let missing = NOT_FOUND
+        .get_fresh((namespace, name.to_string()))
+        .await
+        .is_some();
+    if missing {
+        let saved = SAVED_UPSTREAM_CALLS.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::debug!(
+            "{} {} is known to be missing, {} upstream calls saved so far",
+            namespace,
+            name,
+            saved
+        );
+    }
+    missing
+}
+
+pub async fn remember_missing(namespace: &'static str, name: &str) {
+    let ttl = NEGATIVE_TTL.get().copied().unwrap_or(DEFAULT_NEGATIVE_TTL);
+    let entry = NOT_FOUND.entry((namespace, name.to_string()));
+    entry.write().await.set_with_ttl((), Some(ttl));
+}
+
+/// Number of upstream calls answered from the negative cache since start.
+pub fn saved_upstream_calls() -> u64 {
+    SAVED_UPSTREAM_CALLS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn remembered_names_are_missing_until_they_expire() {
+        assert!(!is_known_missing("pokemon", "missingno").await);
+        // lookups of unknown names don't fill the namespace
+        assert!(NOT_FOUND
+            .existing_entry(&("pokemon", "missingno".to_string()))
+            .is_none());
+
+        let saved = saved_upstream_calls();
+        remember_missing("pokemon", "missingno").await;
+        assert!(is_known_missing("pokemon", "missingno").await);
+        assert!(saved_upstream_calls() > saved);
+        assert!(!is_known_missing("species", "missingno").await);
+
+        NOT_FOUND
+            .entry(("pokemon", "missingno".to_string()))
+            .write()
+            .await
+            .set_ttl(Some(Duration::ZERO));
+        assert!(!is_known_missing("pokemon", "missingno").await);
+    }
+}
@@ -0,0 +1,41 @@
+use actix_web::{get, Responder};
+
+use crate::{
+    cache::CACHE,
+    macros::resp_200_Ok_json,
+    models::cache_admin::{CacheNamespaceInfo, CacheOverview, DatasetInfo},
+    paths::pokemon::get_all::POKEMON_LIST,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns the dataset and all cache namespaces with their number of entries", body = CacheOverview),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::admin::route::/admin/cache"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::admin::route::/admin/cache")]
+#[get("/admin/cache")]
+pub async fn cache() -> impl Responder {
+    let dataset = POKEMON_LIST.get().map(|pokemon_list| DatasetInfo {
+        version: pokemon_list.data.version().to_string(),
+        pokemons: pokemon_list.data.results.len(),
+        expires_in: POKEMON_LIST.ttl().map(|ttl| ttl.as_secs()),
+    });
+    let namespaces = CACHE
+        .namespaces()
+        .into_iter()
+        .map(|namespace| CacheNamespaceInfo {
+            name: namespace.name().to_string(),
+            entries: namespace.len(),
+            limit: namespace.limit(),
+            shared: namespace.is_shared(),
+        })
+        .collect();
+
+    resp_200_Ok_json!(CacheOverview {
+        dataset,
+        namespaces
+    })
+}
@@ -0,0 +1,106 @@
+use actix_web::{get, http::StatusCode, web, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::cache_admin::CacheEntryInfo,
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheEntryQuery {
+    namespace: String,
+    /// Key of the entry, keys of the not_found namespace are `namespace/name`
+    /// and namespaces with a single entry use an empty key
+    #[serde(default)]
+    key: String,
+}
+
+#[utoipa::path(
+    params(CacheEntryQuery),
+    responses(
+        (status = 200, description = "Returns the entry cached by this replica", body = CacheEntryInfo),
+        (status = 400, description = "Query parameters are missing or have wrong type"),
+        (status = 404, description = "Namespace doesn't exist or the entry is not cached"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::admin::route::/admin/cache/entry"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::admin::route::/admin/cache/entry")]
+#[get("/admin/cache/entry")]
+pub async fn cache_entry(query: web::Query<CacheEntryQuery>) -> impl Responder {
+    let CacheEntryQuery { namespace, key } = query.into_inner();
+    let res = super::namespace(&namespace);
+    let Some(entry) = yeet_error!(res).inspect(&key) else {
+        return response_from_error(
+            format!("Entry {key} is not cached in namespace {namespace}"),
+            StatusCode::NOT_FOUND,
+        );
+    };
+
+    resp_200_Ok_json!(CacheEntryInfo {
+        namespace,
+        key,
+        value: entry.value,
+        expires_in: entry.ttl.map(|ttl| ttl.as_secs()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::LazyLock, time::Duration};
+
+    use actix_web::{test, App};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cache::{CacheNamespace, CACHE},
+        jwt_stuff::{test_authorization, JwtGrantsMiddleware},
+    };
+
+    static NAMESPACE: LazyLock<CacheNamespace<String, u32>> =
+        LazyLock::new(|| CacheNamespace::new("test_admin_entry"));
+
+    #[actix_web::test]
+    async fn cached_entry_is_returned() {
+        CACHE.register(&*NAMESPACE);
+        let ttl = Some(Duration::from_secs(60));
+        NAMESPACE
+            .entry("pikachu".into())
+            .write()
+            .await
+            .set_with_ttl(25, ttl);
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtGrantsMiddleware::test())
+                .service(cache_entry),
+        )
+        .await;
+        let get = |query: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/admin/cache/entry?{query}"))
+                .insert_header(test_authorization(
+                    None,
+                    &["svc::pokemon_api::admin::route::/admin/cache/entry"],
+                ))
+                .to_request()
+        };
+
+        let req = get("namespace=test_admin_entry&key=pikachu");
+        let res: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res["value"], json!(25));
+        let expires_in = res["expires_in"].as_u64().unwrap();
+        assert!((58..=60).contains(&expires_in), "{expires_in}");
+
+        let res = test::call_service(&app, get("namespace=test_admin_entry&key=raichu")).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = test::call_service(&app, get("namespace=missing&key=pikachu")).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = test::call_service(&app, get("key=pikachu")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
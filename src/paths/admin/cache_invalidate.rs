@@ -0,0 +1,137 @@
+use actix_web::{http::StatusCode, post, web, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::cache_admin::CacheInvalidation,
+    peers::PEERS,
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheInvalidateQuery {
+    namespace: String,
+    /// Key of the entry, whole namespace is invalidated when missing
+    key: Option<String>,
+}
+
+/// Invalidates the entries on this replica only, returns number of removed entries.
+pub async fn invalidate_locally(query: &CacheInvalidateQuery) -> Result<usize, HttpResponse> {
+    let namespace = super::namespace(&query.namespace)?;
+    let res = match &query.key {
+        Some(key) => namespace
+            .invalidate(key)
+            .await
+            .map(|removed| removed as usize),
+        None => namespace.clear().await,
+    };
+    match res {
+        Ok(removed) => {
+            tracing::info!(
+                "Invalidated {} entries of cache namespace {}",
+                removed,
+                namespace.name()
+            );
+            Ok(removed)
+        }
+        Err(e) => Err(response_from_error(
+            format!("Removing of entries from cache backend failed: {e}"),
+            StatusCode::BAD_GATEWAY,
+        )),
+    }
+}
+
+#[utoipa::path(
+    params(CacheInvalidateQuery),
+    responses(
+        (status = 200, description = "Removes the entry or all entries of the namespace, entries of shared namespaces are removed from the cache backend too and the invalidation is sent to peer replicas", body = CacheInvalidation),
+        (status = 400, description = "Query parameters are missing or have wrong type"),
+        (status = 404, description = "Namespace doesn't exist"),
+        (status = 502, description = "Failed to remove entries from the cache backend"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::admin::route::/admin/cache/invalidate"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::admin::route::/admin/cache/invalidate")]
+#[post("/admin/cache/invalidate")]
+pub async fn cache_invalidate(
+    query: web::Query<CacheInvalidateQuery>,
+    req_client: web::Data<reqwest::Client>,
+) -> impl Responder {
+    let res = invalidate_locally(&query).await;
+    let removed = yeet_error!(res);
+    let failed_peers = match PEERS.get() {
+        Some(peers) => {
+            peers
+                .invalidate(&req_client, &query.namespace, query.key.as_deref())
+                .await
+        }
+        None => Vec::new(),
+    };
+    resp_200_Ok_json!(CacheInvalidation {
+        removed,
+        failed_peers
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::LazyLock;
+
+    use actix_web::{test, App};
+
+    use super::*;
+    use crate::{
+        cache::{CacheNamespace, Namespace, CACHE},
+        jwt_stuff::{test_authorization, JwtGrantsMiddleware},
+    };
+
+    static NAMESPACE: LazyLock<CacheNamespace<String, u32>> =
+        LazyLock::new(|| CacheNamespace::new("test_admin_invalidate"));
+
+    #[actix_web::test]
+    async fn entries_are_invalidated() {
+        CACHE.register(&*NAMESPACE);
+        for i in 0..3 {
+            NAMESPACE.entry(i.to_string()).write().await.set(i);
+        }
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtGrantsMiddleware::test())
+                .app_data(web::Data::new(reqwest::Client::new()))
+                .service(cache_invalidate),
+        )
+        .await;
+        let invalidate = |query: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/admin/cache/invalidate?{query}"))
+                .insert_header(test_authorization(
+                    None,
+                    &["svc::pokemon_api::admin::route::/admin/cache/invalidate"],
+                ))
+                .to_request()
+        };
+
+        let req = invalidate("namespace=test_admin_invalidate&key=1");
+        let res: CacheInvalidation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res.removed, 1);
+        assert!(res.failed_peers.is_empty());
+        assert!(NAMESPACE.existing_entry("1").is_none());
+
+        let req = invalidate("namespace=test_admin_invalidate&key=1");
+        let res: CacheInvalidation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res.removed, 0);
+
+        let req = invalidate("namespace=test_admin_invalidate");
+        let res: CacheInvalidation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(res.removed, 2);
+        assert_eq!(NAMESPACE.len(), 0);
+
+        let req = invalidate("namespace=missing");
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,44 @@
+use actix_web::{post, web::Data, Responder};
+
+use crate::{
+    macros::resp_200_Ok_json,
+    models::cache_admin::DatasetRefresh,
+    paths::pokemon::get_all::{refresh_all_pokemons, POKEMON_LIST},
+};
+
+fn dataset_version() -> Option<String> {
+    POKEMON_LIST
+        .get()
+        .map(|pokemon_list| pokemon_list.data.version().to_string())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Fetches the pokemon dataset right away and returns whether it was replaced", body = DatasetRefresh),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::admin::route::/admin/dataset/refresh"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::admin::route::/admin/dataset/refresh")]
+#[post("/admin/dataset/refresh")]
+pub async fn dataset_refresh(req_client: Data<reqwest::Client>) -> impl Responder {
+    let previous_version = dataset_version();
+    let error = match refresh_all_pokemons(&req_client).await {
+        Ok(()) => {
+            tracing::info!("Pokemon data refreshed by administrator");
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Refreshing of pokemon data failed: {}", e);
+            Some(e)
+        }
+    };
+
+    resp_200_Ok_json!(DatasetRefresh {
+        refreshed: error.is_none(),
+        error,
+        previous_version,
+        version: dataset_version(),
+    })
+}
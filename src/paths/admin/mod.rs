@@ -0,0 +1,28 @@
+pub mod cache;
+pub mod cache_entry;
+pub mod cache_invalidate;
+pub mod dataset_refresh;
+
+use actix_web::{http::StatusCode, web::ServiceConfig, HttpResponse};
+
+use crate::{
+    cache::{Namespace, CACHE},
+    req_util::response_from_error,
+};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(cache::cache)
+        .service(cache_entry::cache_entry)
+        .service(cache_invalidate::cache_invalidate)
+        .service(dataset_refresh::dataset_refresh);
+}
+
+#[allow(clippy::result_large_err)]
+fn namespace(name: &str) -> Result<&'static dyn Namespace, HttpResponse> {
+    CACHE.namespace(name).ok_or_else(|| {
+        response_from_error(
+            format!("Cache namespace {name} doesn't exist"),
+            StatusCode::NOT_FOUND,
+        )
+    })
+}
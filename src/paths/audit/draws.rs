@@ -0,0 +1,83 @@
+use actix_web::{get, http::StatusCode, web, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    audit::{AuditFilter, AuditLog},
+    macros::{resp_200_Ok_json, yeet_error},
+    models::audit::AuditPage,
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditPageQuery {
+    /// Return only entries with greater id, use `next_after_id` of the previous page
+    after_id: Option<u64>,
+    /// Maximal number of returned entries, defaults to 100
+    #[param(minimum = 1, maximum = 1000)]
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    params(AuditPageQuery, AuditFilter),
+    responses(
+        (status = 200, description = "Returns page of the draw audit log ordered by id", body = AuditPage),
+        (status = 400, description = "Query parameters have wrong type"),
+        (status = 500, description = "Failed to read audit log"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/audit/draws"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/audit/draws")]
+#[get("/audit/draws")]
+pub async fn draws(
+    page: web::Query<AuditPageQuery>,
+    filter: web::Query<AuditFilter>,
+) -> impl Responder {
+    let audit_log = yeet_error!(AuditLog::get());
+    let from_id = page
+        .after_id
+        .map(|id| id.saturating_add(1))
+        .unwrap_or_default();
+    let limit = page.limit.unwrap_or(100).clamp(1, 1000);
+    let filter = filter.into_inner();
+
+    let res = tokio::task::spawn_blocking(move || {
+        // one more entry is read to know whether there is a next page
+        let entries = audit_log
+            .records(from_id)
+            .filter(|record| !matches!(record, Ok(record) if !filter.matches(record)))
+            .take(limit + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok::<_, std::io::Error>(entries)
+    })
+    .await;
+    let mut entries = match res {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            return response_from_error(
+                format!("Failed to read audit log: {e}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }
+        Err(e) => {
+            return response_from_error(
+                format!("Failed to read audit log: {e}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }
+    };
+
+    let next_after_id = if entries.len() > limit {
+        entries.truncate(limit);
+        entries.last().map(|record| record.id)
+    } else {
+        None
+    };
+    resp_200_Ok_json!(AuditPage {
+        entries,
+        next_after_id,
+    })
+}
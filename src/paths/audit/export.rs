@@ -0,0 +1,99 @@
+use actix_web::{get, web, web::Bytes, HttpResponse, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    audit::{csv_row, AuditFilter, AuditLog, ExportFormat, CSV_HEADER},
+    macros::yeet_error,
+};
+
+/// Number of records sent in one chunk of the response.
+const CHUNK_SIZE: usize = 256;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Format of the export, defaults to ndjson
+    #[param(inline)]
+    format: Option<ExportFormat>,
+}
+
+#[utoipa::path(
+    params(ExportQuery, AuditFilter),
+    responses(
+        (status = 200, description = "Streams matching draws of the audit log as CSV or NDJSON ordered by id<br>CSV contains header row, grants separated by spaces and result as JSON array"),
+        (status = 400, description = "Query parameters have wrong type"),
+        (status = 500, description = "Audit log is not available"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/audit/export"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/audit/export")]
+#[get("/audit/export")]
+pub async fn export(
+    query: web::Query<ExportQuery>,
+    filter: web::Query<AuditFilter>,
+) -> impl Responder {
+    let audit_log = yeet_error!(AuditLog::get());
+    let format = query.format.unwrap_or_default();
+    let filter = filter.into_inner();
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+    tokio::task::spawn_blocking(move || {
+        let mut chunk = match format {
+            ExportFormat::Csv => CSV_HEADER.to_string(),
+            ExportFormat::Ndjson => String::new(),
+        };
+        let mut in_chunk = 0;
+        for record in audit_log.records(0) {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    // the response can only be aborted at this point
+                    let _ = tx.blocking_send(Err(e));
+                    return;
+                }
+            };
+            if !filter.matches(&record) {
+                continue;
+            }
+            match format {
+                ExportFormat::Csv => chunk.push_str(&csv_row(&record)),
+                ExportFormat::Ndjson => {
+                    chunk.push_str(&serde_json::to_string(&record).unwrap_or_default());
+                    chunk.push('\n');
+                }
+            }
+            in_chunk += 1;
+            if in_chunk == CHUNK_SIZE {
+                if tx
+                    .blocking_send(Ok(Bytes::from(std::mem::take(&mut chunk))))
+                    .is_err()
+                {
+                    // client disconnected
+                    return;
+                }
+                in_chunk = 0;
+            }
+        }
+        if !chunk.is_empty() {
+            let _ = tx.blocking_send(Ok(Bytes::from(chunk)));
+        }
+    });
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv", "csv"),
+        ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+    };
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            "content-disposition",
+            format!("attachment; filename=\"draws.{extension}\""),
+        ))
+        .streaming(stream)
+}
@@ -0,0 +1,8 @@
+pub mod draws;
+pub mod export;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(draws::draws).service(export::export);
+}
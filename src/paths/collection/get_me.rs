@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, HashSet};
+
+use actix_web::{get, web::Data, Responder};
+
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{
+        collection::{Collection, OwnedPokemon, PokemonCount},
+        pokemon::Pokemon,
+    },
+    paths::pokemon::get_all,
+    store::Store,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns pokemons owned by the requester with counts and pokedex completion", body = Collection),
+        (status = 400, description = "JWT token doesn't contain sub claim"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Failed to read collection"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/collection/me"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/collection/me")]
+#[get("/collection/me")]
+pub async fn get_me(subject: JwtSubject, req_client: Data<reqwest::Client>) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let res = store.owned_pokemons(subject.0).await;
+    let rows = yeet_error!(res.map_err(|e| e.into_response("Failed to read collection")));
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokedex = yeet_error!(res)
+        .data
+        .results
+        .iter()
+        .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
+        .map(|pokemon| pokemon.name.to_string())
+        .collect::<HashSet<_>>();
+
+    let mut counts = BTreeMap::<String, usize>::new();
+    let pokemons = rows
+        .into_iter()
+        .map(|row| {
+            *counts.entry(row.pokemon_name.clone()).or_default() += 1;
+            OwnedPokemon {
+                id: row.id,
+                draw_id: row.draw_id,
+                name: row.pokemon_name,
+                shiny: row.shiny,
+                obtained_at: row.obtained_at,
+                pokemon: serde_json::from_str(&row.data).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let unique = counts.keys().filter(|name| pokedex.contains(*name)).count();
+    let completion = if pokedex.is_empty() {
+        0.0
+    } else {
+        unique as f64 * 100.0 / pokedex.len() as f64
+    };
+
+    resp_200_Ok_json!(Collection {
+        pokemons,
+        counts: counts
+            .into_iter()
+            .map(|(name, count)| PokemonCount { name, count })
+            .collect(),
+        unique,
+        pokedex_size: pokedex.len(),
+        completion,
+    })
+}
@@ -0,0 +1,7 @@
+pub mod get_me;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(get_me::get_me);
+}
@@ -0,0 +1,51 @@
+use actix_web::{get, web, Responder};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::credits::{CreditTransaction, Credits},
+    store::Store,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreditsQuery {
+    /// Maximal number of returned transactions, defaults to 50
+    #[param(minimum = 1, maximum = 500)]
+    limit: Option<u32>,
+    /// Number of newest transactions to skip
+    offset: Option<u32>,
+}
+
+#[utoipa::path(
+    params(CreditsQuery),
+    responses(
+        (status = 200, description = "Returns credit balance and transaction history of the requester", body = Credits),
+        (status = 400, description = "Query parameters have wrong type<br>or<br>JWT token doesn't contain sub claim"),
+        (status = 500, description = "Failed to read credits"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/credits/me"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/credits/me")]
+#[get("/credits/me")]
+pub async fn get_me(subject: JwtSubject, query: web::Query<CreditsQuery>) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let res = store
+        .credits(subject.0, limit, query.offset.unwrap_or_default())
+        .await;
+    let (balance, transactions) =
+        yeet_error!(res.map_err(|e| e.into_response("Failed to read credits")));
+
+    resp_200_Ok_json!(Credits {
+        balance,
+        transactions: transactions
+            .into_iter()
+            .map(CreditTransaction::from)
+            .collect(),
+    })
+}
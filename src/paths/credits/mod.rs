@@ -0,0 +1,8 @@
+pub mod get_me;
+pub mod top_up;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(get_me::get_me).service(top_up::top_up);
+}
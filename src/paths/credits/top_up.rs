@@ -0,0 +1,44 @@
+use actix_web::{http::StatusCode, post, web, Responder};
+
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::credits::{CreditTransaction, TopUp},
+    req_util::response_from_error,
+    store::{ledger::LedgerError, Store},
+};
+
+#[utoipa::path(
+    request_body = TopUp,
+    responses(
+        (status = 200, description = "Credits were added to the balance of the subject", body = CreditTransaction),
+        (status = 400, description = "Invalid request body<br>or<br>Amount is zero or too large"),
+        (status = 500, description = "Failed to top up credits"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::admin::route::/credits/top_up"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::admin::route::/credits/top_up")]
+#[post("/credits/top_up")]
+pub async fn top_up(admin: Option<JwtSubject>, body: web::Json<TopUp>) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let TopUp { sub, amount, note } = body.into_inner();
+    if sub.is_empty() {
+        return response_from_error("Subject can't be empty", StatusCode::BAD_REQUEST);
+    }
+
+    let res = store
+        .top_up(sub, amount, admin.map(|admin| admin.0), note)
+        .await;
+    match res {
+        Ok(Ok(transaction)) => resp_200_Ok_json!(CreditTransaction::from(transaction)),
+        Ok(Err(
+            LedgerError::InvalidAmount
+            | LedgerError::InsufficientCredits { .. }
+            | LedgerError::IdempotencyKeyReused
+            | LedgerError::Audit(_),
+        )) => response_from_error("Amount is zero or too large", StatusCode::BAD_REQUEST),
+        Err(e) => e.into_response("Failed to top up credits"),
+    }
+}
@@ -0,0 +1,25 @@
+use actix_web::{get, Responder};
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::server_seed::ServerSeedCommitment,
+    provably_fair::ProvablyFair,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns hash of the server seed currently used for fair draws", body = ServerSeedCommitment),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/fair/commitment"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/fair/commitment")]
+#[get("/fair/commitment")]
+pub async fn get_commitment() -> impl Responder {
+    let res = ProvablyFair::get();
+    let provably_fair = yeet_error!(res);
+    resp_200_Ok_json!(ServerSeedCommitment {
+        server_seed_hash: provably_fair.current().await.hash().to_string(),
+    })
+}
@@ -0,0 +1,13 @@
+pub mod get_commitment;
+pub mod reveal;
+pub mod rotate;
+pub mod verify;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(get_commitment::get_commitment)
+        .service(reveal::reveal)
+        .service(rotate::rotate)
+        .service(verify::verify);
+}
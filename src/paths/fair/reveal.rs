@@ -0,0 +1,37 @@
+use actix_web::{get, http::StatusCode, web, Responder};
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::server_seed::RevealedServerSeed,
+    provably_fair::ProvablyFair,
+    req_util::response_from_error,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns revealed server seed for the given hash", body = RevealedServerSeed),
+        (status = 404, description = "Server seed was not found or wasn't rotated yet"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/fair/reveal"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/fair/reveal")]
+#[get("/fair/reveal/{server_seed_hash}")]
+pub async fn reveal(server_seed_hash: web::Path<String>) -> impl Responder {
+    let res = ProvablyFair::get();
+    let provably_fair = yeet_error!(res);
+    match provably_fair
+        .revealed(&server_seed_hash.to_ascii_lowercase())
+        .await
+    {
+        Some(server_seed) => resp_200_Ok_json!(RevealedServerSeed {
+            server_seed_hash: server_seed.hash().to_string(),
+            server_seed: server_seed.seed_hex(),
+        }),
+        None => response_from_error(
+            "Server seed was not found or wasn't rotated yet",
+            StatusCode::NOT_FOUND,
+        ),
+    }
+}
@@ -0,0 +1,35 @@
+use actix_web::{post, Responder};
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::server_seed::{RevealedServerSeed, RotatedServerSeed},
+    provably_fair::ProvablyFair,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Rotates server seed and returns the revealed one with hash of the next one", body = RotatedServerSeed),
+        (status = 500, description = "Failed to store the rotated server seeds"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/fair/rotate"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/fair/rotate")]
+#[post("/fair/rotate")]
+pub async fn rotate() -> impl Responder {
+    let res = ProvablyFair::get();
+    let provably_fair = yeet_error!(res);
+    let res = provably_fair
+        .rotate()
+        .await
+        .map_err(|e| e.into_response("Failed to rotate server seed"));
+    let revealed = yeet_error!(res);
+    resp_200_Ok_json!(RotatedServerSeed {
+        revealed: RevealedServerSeed {
+            server_seed_hash: revealed.hash().to_string(),
+            server_seed: revealed.seed_hex(),
+        },
+        next_server_seed_hash: provably_fair.current().await.hash().to_string(),
+    })
+}
@@ -0,0 +1,91 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{pokemon::Pokemon, server_seed::VerifiedDraw},
+    paths::pokemon::get_all,
+    provably_fair::{verify_draw, ProvablyFair},
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyDrawQuery {
+    server_seed_hash: String,
+    client_seed: String,
+    nonce: u64,
+    count: u8,
+    /// `dataset_version` of the draw, draw isn't replayed when the dataset changed since
+    dataset_version: Option<String>,
+}
+
+#[utoipa::path(
+    params(VerifyDrawQuery),
+    responses(
+        (status = 200, description = "Replays a fair draw with its revealed server seed on the current dataset, same result can be computed offline with `provably_fair::verify_draw`", body = VerifiedDraw),
+        (status = 400, description = "Query parameters are missing or have wrong type"),
+        (status = 404, description = "Server seed was not found or wasn't rotated yet"),
+        (status = 409, description = "Dataset changed since the draw, it can be verified only offline with names of its dataset"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/fair/verify"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/fair/verify")]
+#[get("/fair/verify")]
+pub async fn verify(
+    query: web::Query<VerifyDrawQuery>,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let res = ProvablyFair::get();
+    let provably_fair = yeet_error!(res);
+    let server_seed_hash = query.server_seed_hash.to_ascii_lowercase();
+    let Some(server_seed) = provably_fair.revealed(&server_seed_hash).await else {
+        return response_from_error(
+            "Server seed was not found or wasn't rotated yet",
+            StatusCode::NOT_FOUND,
+        );
+    };
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokemon_list = yeet_error!(res);
+    let dataset_version = pokemon_list.data.version();
+    if matches!(&query.dataset_version, Some(version) if version != dataset_version) {
+        return response_from_error(
+            format!("Dataset changed since the draw, current version is {dataset_version}"),
+            StatusCode::CONFLICT,
+        );
+    }
+    let names = pokemon_list
+        .data
+        .results
+        .iter()
+        .filter(|api_pokemon| Pokemon::try_from(*api_pokemon).is_ok())
+        .map(|api_pokemon| api_pokemon.name.as_str())
+        .collect::<Vec<_>>();
+
+    match verify_draw(
+        &server_seed.seed_hex(),
+        server_seed.hash(),
+        &query.client_seed,
+        query.nonce,
+        query.count as usize,
+        &names,
+    ) {
+        Ok(pokemons) => resp_200_Ok_json!(VerifiedDraw {
+            server_seed_hash: server_seed.hash().to_string(),
+            server_seed: server_seed.seed_hex(),
+            pokemons: pokemons.into_iter().map(str::to_string).collect(),
+            dataset_version: dataset_version.to_string(),
+        }),
+        Err(e) => response_from_error(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
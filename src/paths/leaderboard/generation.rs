@@ -0,0 +1,78 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+
+use crate::{
+    leaderboards::{Board, LeaderboardQuery, LEADERBOARDS},
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{
+        leaderboard::{GenerationCompletion, GenerationLeaderboard},
+        pokemon::Pokemon,
+    },
+    paths::pokemon::get_all,
+    req_util::response_from_error,
+    store::Store,
+};
+
+#[utoipa::path(
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Returns subjects who were first to draw every pokemon of the generation in the window", body = GenerationLeaderboard),
+        (status = 400, description = "Parameter generation has wrong type or is outside of u8 range<br>or<br>Query parameters have wrong type"),
+        (status = 404, description = "Generation has no pokemons"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Failed to compute leaderboard"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/leaderboard/generation"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/leaderboard/generation")]
+#[get("/leaderboard/generation/{generation}")]
+pub async fn generation(
+    generation: web::Path<u8>,
+    query: web::Query<LeaderboardQuery>,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let generation = generation.into_inner();
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokedex_size = yeet_error!(res)
+        .data
+        .results
+        .iter()
+        .filter(|api_pokemon| api_pokemon.generation() == Some(generation))
+        .filter(|api_pokemon| Pokemon::try_from(*api_pokemon).is_ok())
+        .count();
+    if pokedex_size == 0 {
+        return response_from_error("Generation has no pokemons", StatusCode::NOT_FOUND);
+    }
+
+    let store = yeet_error!(Store::get());
+    let window = query.window.unwrap_or_default();
+    let board = Board::Generation {
+        generation,
+        size: pokedex_size,
+    };
+    let res = LEADERBOARDS.get(store, board, window).await;
+    let (since, rows) =
+        yeet_error!(res.map_err(|e| e.into_response("Failed to compute leaderboard")));
+
+    resp_200_Ok_json!(GenerationLeaderboard {
+        generation,
+        pokedex_size,
+        window,
+        since,
+        entries: rows
+            .iter()
+            .take(query.limit())
+            .enumerate()
+            .map(|(i, row)| GenerationCompletion {
+                rank: i + 1,
+                sub: row.sub.clone(),
+                completed_at: row.value,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,41 @@
+pub mod generation;
+pub mod rarity;
+pub mod shinies;
+
+use actix_web::{web::ServiceConfig, HttpResponse};
+
+use crate::{
+    leaderboards::{Board, LeaderboardQuery, LEADERBOARDS},
+    macros::{resp_200_Ok_json, yeet_error},
+    models::leaderboard::{Leaderboard, LeaderboardEntry},
+    store::{leaderboards::Score, Store},
+};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(shinies::shinies)
+        .service(rarity::rarity)
+        .service(generation::generation);
+}
+
+async fn score_leaderboard(score: Score, query: &LeaderboardQuery) -> HttpResponse {
+    let store = yeet_error!(Store::get());
+    let window = query.window.unwrap_or_default();
+    let res = LEADERBOARDS.get(store, Board::Score(score), window).await;
+    let (since, rows) =
+        yeet_error!(res.map_err(|e| e.into_response("Failed to compute leaderboard")));
+
+    resp_200_Ok_json!(Leaderboard {
+        window,
+        since,
+        entries: rows
+            .iter()
+            .take(query.limit())
+            .enumerate()
+            .map(|(i, row)| LeaderboardEntry {
+                rank: i + 1,
+                sub: row.sub.clone(),
+                score: row.value,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,23 @@
+use actix_web::{get, web, Responder};
+
+use super::score_leaderboard;
+use crate::{
+    leaderboards::LeaderboardQuery, models::leaderboard::Leaderboard, store::leaderboards::Score,
+};
+
+#[utoipa::path(
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Returns subjects with the most shiny pokemons drawn in the window", body = Leaderboard),
+        (status = 400, description = "Query parameters have wrong type"),
+        (status = 500, description = "Failed to compute leaderboard"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/leaderboard/shinies"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/leaderboard/shinies")]
+#[get("/leaderboard/shinies")]
+pub async fn shinies(query: web::Query<LeaderboardQuery>) -> impl Responder {
+    score_leaderboard(Score::Shinies, &query).await
+}
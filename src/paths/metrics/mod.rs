@@ -0,0 +1,7 @@
+pub mod prometheus;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(prometheus::metrics);
+}
@@ -0,0 +1,121 @@
+use actix_web::{get, HttpResponse, Responder};
+
+use crate::{
+    cache::CACHE,
+    metrics::{render, render_samples},
+    negative_cache,
+    upstream::upstream,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns cache and remote api metrics in Prometheus text format", body = String, content_type = "text/plain"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/metrics"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/metrics")]
+#[get("/metrics")]
+pub async fn metrics() -> impl Responder {
+    let mut out = String::new();
+    render(&mut out);
+
+    let namespaces = CACHE.namespaces();
+    render_samples(
+        &mut out,
+        "pokemon_api_cache_entries",
+        "Entries cached by this replica",
+        "gauge",
+        namespaces.iter().map(|namespace| {
+            (
+                vec![("namespace", namespace.name().to_string())],
+                namespace.len() as u64,
+            )
+        }),
+    );
+    render_samples(
+        &mut out,
+        "pokemon_api_cache_limit",
+        "Maximal number of entries",
+        "gauge",
+        namespaces.iter().map(|namespace| {
+            (
+                vec![("namespace", namespace.name().to_string())],
+                namespace.limit() as u64,
+            )
+        }),
+    );
+    render_samples(
+        &mut out,
+        "pokemon_api_negative_cache_saved_upstream_calls_total",
+        "Requests to the remote api avoided because the name was recently not found",
+        "counter",
+        [(vec![], negative_cache::saved_upstream_calls())],
+    );
+    render_samples(
+        &mut out,
+        "pokemon_api_upstream_endpoint_healthy",
+        "Whether the remote api endpoint is healthy, unhealthy ones are tried only after the healthy ones fail",
+        "gauge",
+        upstream().endpoints().map(|(url, healthy)| {
+            (vec![("endpoint", url.to_string())], healthy as u64)
+        }),
+    );
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test, App};
+
+    use super::*;
+    use crate::{
+        jwt_stuff::{test_authorization, JwtGrantsMiddleware},
+        metrics::UPSTREAM_FAILOVERS,
+    };
+
+    #[actix_web::test]
+    async fn metrics_are_rendered_as_text() {
+        UPSTREAM_FAILOVERS.inc([]);
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtGrantsMiddleware::test())
+                .service(metrics),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/metrics")
+            .insert_header(test_authorization(
+                None,
+                &["svc::pokemon_api::route::/metrics"],
+            ))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; version=0.0.4"
+        );
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(
+            body.contains("\npokemon_api_upstream_failovers_total "),
+            "{body}"
+        );
+        assert!(
+            body.contains("# TYPE pokemon_api_cache_entries gauge\n"),
+            "{body}"
+        );
+        assert!(
+            body.contains("\npokemon_api_negative_cache_saved_upstream_calls_total "),
+            "{body}"
+        );
+        assert!(
+            body.contains("pokemon_api_upstream_endpoint_healthy{endpoint=\""),
+            "{body}"
+        );
+    }
+}
@@ -1,7 +1,29 @@
 use actix_web::web::ServiceConfig;
 
+pub mod admin;
+pub mod audit;
+pub mod collection;
+pub mod credits;
+pub mod fair;
+pub mod leaderboard;
+pub mod metrics;
+pub mod peer;
 pub mod pokemon;
+pub mod quiz;
+pub mod trade;
+pub mod well_known;
 
 pub fn configure(cfg: &mut ServiceConfig) {
+    admin::configure(cfg);
+    audit::configure(cfg);
+    collection::configure(cfg);
+    credits::configure(cfg);
+    fair::configure(cfg);
+    leaderboard::configure(cfg);
+    metrics::configure(cfg);
+    peer::configure(cfg);
     pokemon::configure(cfg);
+    quiz::configure(cfg);
+    trade::configure(cfg);
+    well_known::configure(cfg);
 }
@@ -0,0 +1,30 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::cache_admin::CacheInvalidation,
+    paths::admin::cache_invalidate::{invalidate_locally, CacheInvalidateQuery},
+};
+
+#[utoipa::path(
+    params(CacheInvalidateQuery),
+    responses(
+        (status = 200, description = "Removes the entry or all entries of the namespace on this replica, it's not sent to other peers", body = CacheInvalidation),
+        (status = 400, description = "Query parameters are missing or have wrong type"),
+        (status = 404, description = "Namespace doesn't exist"),
+        (status = 502, description = "Failed to remove entries from the cache backend"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::peer::route::/peer/cache/invalidate"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::peer::route::/peer/cache/invalidate")]
+#[post("/peer/cache/invalidate")]
+pub async fn peer_cache_invalidate(query: web::Query<CacheInvalidateQuery>) -> impl Responder {
+    let res = invalidate_locally(&query).await;
+    let removed = yeet_error!(res);
+    resp_200_Ok_json!(CacheInvalidation {
+        removed,
+        failed_peers: Vec::new()
+    })
+}
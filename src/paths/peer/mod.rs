@@ -0,0 +1,9 @@
+pub mod cache_invalidate;
+pub mod snapshot;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(cache_invalidate::peer_cache_invalidate)
+        .service(snapshot::peer_snapshot);
+}
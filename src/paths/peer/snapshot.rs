@@ -0,0 +1,36 @@
+use actix_web::{get, http::StatusCode, HttpResponse, Responder};
+
+use crate::{paths::pokemon::get_all::encode_snapshot, req_util::response_from_error};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns snapshot of the pokemon dataset this replica serves, it's in the same format as the snapshot file", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 404, description = "This replica has no dataset loaded yet"),
+        (status = 500, description = "Dataset couldn't be encoded"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::peer::route::/peer/snapshot"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::peer::route::/peer/snapshot")]
+#[get("/peer/snapshot")]
+pub async fn peer_snapshot() -> impl Responder {
+    // encoded from memory, so it's the served dataset even when saving of its snapshot file failed
+    let content = match tokio::task::spawn_blocking(encode_snapshot).await {
+        Ok(Some(Ok(content))) => content,
+        Ok(None) => {
+            return response_from_error("No pokemon data are loaded", StatusCode::NOT_FOUND)
+        }
+        Ok(Some(Err(e))) => {
+            return response_from_error(
+                format!("Encoding of snapshot failed: {e}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }
+        Err(e) => return response_from_error(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .body(content)
+}
@@ -0,0 +1,75 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::{get_all, with_dataset_version};
+use crate::{
+    dataset_changes::{self, ChangeKind},
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{dataset_changes::DatasetChanges, pokemon::Pokemon},
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChangesQuery {
+    /// Version the client has, it's in the `dataset-version` header of every pokemon route
+    since: String,
+}
+
+#[utoipa::path(
+    params(ChangesQuery),
+    responses(
+        (status = 200, description = "Returns pokemons added, changed and removed since the version", body = DatasetChanges),
+        (status = 400, description = "Parameter since is missing"),
+        (status = 410, description = "Version is unknown or too old, fetch all pokemons instead"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/changes"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/changes")]
+#[get("/pokemon/changes")]
+pub async fn changes(
+    query: web::Query<ChangesQuery>,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokemon_list = yeet_error!(res);
+    let version = pokemon_list.data.content_version();
+
+    let Some(pokemons) = dataset_changes::since(&query.since, version) else {
+        return response_from_error(
+            format!("Changes since version {} are not known", query.since),
+            StatusCode::GONE,
+        );
+    };
+
+    let mut changes = DatasetChanges {
+        since: query.since.clone(),
+        version: version.to_string(),
+        added: Vec::new(),
+        changed: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (name, kind) in pokemons {
+        // pokemons which can't be converted are not listed by other routes either
+        let pokemon = pokemon_list
+            .data
+            .get(&name)
+            .and_then(|api_pokemon| Pokemon::try_from(api_pokemon).ok());
+        match (kind, pokemon) {
+            (ChangeKind::Added, Some(pokemon)) => changes.added.push(pokemon),
+            (ChangeKind::Changed, Some(pokemon)) => changes.changed.push(pokemon),
+            (ChangeKind::Removed, _) | (_, None) => changes.removed.push(name),
+        }
+    }
+
+    with_dataset_version(resp_200_Ok_json!(changes), &pokemon_list.data)
+}
@@ -0,0 +1,119 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+use actix_web_grants::authorities::AuthDetails;
+use rand::Rng;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::{get_all, with_dataset_version};
+use crate::{
+    credits::Banner,
+    draws::{finish_draw, DrawContext, DrawQuery},
+    jwt_stuff::JwtSubject,
+    macros::yeet_error,
+    models::pokemon_instance::PokemonInstance,
+    req_util::response_from_error,
+};
+
+/// Species drawn for every requested pokemon before giving up, most species can be generated.
+const MAX_ATTEMPTS_PER_POKEMON: usize = 100;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GenerateQuery {
+    /// Minimal level of generated pokemons, defaults to 1
+    #[param(minimum = 1, maximum = 100)]
+    min_level: Option<u8>,
+    /// Maximal level of generated pokemons, defaults to 100
+    #[param(minimum = 1, maximum = 100)]
+    max_level: Option<u8>,
+}
+
+#[utoipa::path(
+    params(GenerateQuery, DrawQuery),
+    responses(
+        (status = 200, description = "Returns N random pokemon instances with IVs, nature, gender, ability, level and actual stats", body = [PokemonInstance]),
+        (status = 400, description = "Parameter count has wrong type or is outside of u8 range<br>or<br>Level range is invalid<br>or<br>Certificate, recording, charging or idempotency key was requested but JWT token doesn't contain sub claim<br>or<br>Idempotency key is empty or longer than 255 characters"),
+        (status = 402, description = "Balance of the requester is lower than the cost of the draw"),
+        (status = 409, description = "Idempotency key was already used for a different draw"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Certificate was requested but certificates are not configured<br>or<br>Failed to record draw<br>or<br>No pokemons are available or none of the drawn species could be generated"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/generate"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/generate")]
+#[get("/pokemon/generate/{count}")]
+pub async fn generate(
+    count: web::Path<u8>,
+    query: web::Query<GenerateQuery>,
+    draw_query: web::Query<DrawQuery>,
+    subject: Option<JwtSubject>,
+    auth_details: AuthDetails,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let min_level = query.min_level.unwrap_or(1);
+    let max_level = query.max_level.unwrap_or(100);
+    if min_level == 0 || max_level > 100 || min_level > max_level {
+        return response_from_error(
+            "Level range must be within 1 and 100 and min_level can't be greater than max_level",
+            StatusCode::BAD_REQUEST,
+        );
+    }
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+
+    let dataset = yeet_error!(res);
+    let pokemon_list = &dataset.data.results;
+    if pokemon_list.is_empty() {
+        return response_from_error(
+            "No pokemons are available",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    }
+    let mut pokemons = Vec::with_capacity(*count as usize);
+    let mut rng = rand::thread_rng();
+
+    // species without stats or species data can't be generated, the attempts are capped so it never spins forever
+    let mut attempts = 0;
+    while pokemons.len() < *count as usize {
+        if attempts == *count as usize * MAX_ATTEMPTS_PER_POKEMON {
+            return response_from_error(
+                "Failed to generate pokemon instances, drawn species are missing data",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            );
+        }
+        attempts += 1;
+
+        let i = rng.gen_range(0..pokemon_list.len());
+        if let Ok(pokemon) =
+            PokemonInstance::generate(&pokemon_list[i], min_level..=max_level, &mut rng)
+        {
+            pokemons.push(pokemon);
+        }
+    }
+
+    let res = finish_draw(
+        pokemons,
+        &draw_query,
+        DrawContext {
+            banner: Banner::Generate,
+            count: *count,
+            subject: subject.as_ref(),
+            auth_details: &auth_details,
+            seed: None,
+            dataset: &dataset.data,
+            parameters: vec![
+                ("min_level", min_level.to_string()),
+                ("max_level", max_level.to_string()),
+            ],
+        },
+        |pokemons| pokemons,
+    )
+    .await;
+    with_dataset_version(res, &dataset.data)
+}
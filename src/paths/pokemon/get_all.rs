@@ -1,17 +1,48 @@
-use actix_web::{get, http::StatusCode, web::Data, HttpResponse, Responder};
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock},
+    time::Instant,
+};
+
+use actix_web::{get, http::StatusCode, web::Data, HttpRequest, HttpResponse, Responder};
+use arc_swap::ArcSwapOption;
 use serde_json::json;
 
+use super::with_dataset_version;
 use crate::{
-    cache::{RefVal, CACHE},
-    macros::{resp_200_Ok_json, yeet_error},
+    cache::{default_ttl, RefVal, Snapshot, STALE_RETRY_AFTER},
+    dataset_changes,
+    macros::yeet_error,
+    metrics,
     models::{pokemon::Pokemon, remote_api::ApiPokemonList, DataWrapper},
-    req_caching,
-    req_util::response_from_error,
+    precompressed::PrecompressedJson,
+    req_util::{self, response_from_error},
+    snapshot,
+    upstream::{upstream, UpstreamError},
 };
 
-use super::get_by_name::get_cache_key_for_pokemon;
+/// The verified pokemon dataset, it's read without locking and never evicted.
+pub static POKEMON_LIST: LazyLock<Snapshot<DataWrapper<ApiPokemonList>>> =
+    LazyLock::new(Snapshot::default);
+
+/// Response body of `/pokemon/get_all` and content version of the dataset it was built from.
+struct AllPokemonsBody {
+    /// [`ApiPokemonList::content_version`], so the body is rebuilt when any pokemon changes
+    content_version: String,
+    body: PrecompressedJson,
+}
+
+/// Served dataset and when its data were fetched from the remote api, it's sent to peer replicas as a snapshot.
+struct DatasetSource {
+    data: RefVal<DataWrapper<ApiPokemonList>>,
+    created_at: u64,
+}
+
+static DATASET_SOURCE: ArcSwapOption<DatasetSource> = ArcSwapOption::const_empty();
 
-pub const CACHE_KEY: &str = "/pokemon/get_all";
+static ALL_POKEMONS_BODY: ArcSwapOption<AllPokemonsBody> = ArcSwapOption::const_empty();
+/// Held while the body is being built, so it's built only once for every dataset.
+static ALL_POKEMONS_BODY_BUILD: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
 #[utoipa::path(
     responses(
@@ -24,58 +55,331 @@ pub const CACHE_KEY: &str = "/pokemon/get_all";
 )]
 #[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/get_all")]
 #[get("/pokemon/get_all")]
-pub async fn get_all(req_client: Data<reqwest::Client>) -> impl Responder {
+pub async fn get_all(req: HttpRequest, req_client: Data<reqwest::Client>) -> impl Responder {
     let res = get_all_pokemons(&req_client).await;
+    let pokemon_list = yeet_error!(res);
+
+    match all_pokemons_body(pokemon_list.clone()).await {
+        Ok(body) => with_dataset_version(body.body.respond(&req), &pokemon_list.data),
+        Err(e) => response_from_error(
+            format!("Error encountered: {e}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    }
+}
 
-    let pokemon_list = &yeet_error!(res).data.results;
+/// Returns serialized and compressed list of all pokemons in the dataset, it's built on the first use.
+async fn all_pokemons_body(
+    pokemon_list: RefVal<DataWrapper<ApiPokemonList>>,
+) -> Result<Arc<AllPokemonsBody>, String> {
+    let content_version = pokemon_list.data.content_version();
+    let current = ALL_POKEMONS_BODY.load_full();
+    if let Some(body) = current.filter(|body| body.content_version == content_version) {
+        return Ok(body);
+    }
+    let _build_lock = ALL_POKEMONS_BODY_BUILD.lock().await;
+    // body could have been built while waiting for the lock
+    let current = ALL_POKEMONS_BODY.load_full();
+    if let Some(body) = current.filter(|body| body.content_version == content_version) {
+        return Ok(body);
+    }
 
-    let pokemons = pokemon_list
-        .iter()
-        .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
-        .collect::<Vec<_>>();
+    let started = Instant::now();
+    let body = tokio::task::spawn_blocking(move || {
+        let pokemons = pokemon_list
+            .data
+            .results
+            .iter()
+            .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
+            .collect::<Vec<_>>();
+        let json = serde_json::to_vec(&pokemons).map_err(|e| e.to_string())?;
+        Ok::<_, String>(AllPokemonsBody {
+            content_version: pokemon_list.data.content_version().to_string(),
+            body: PrecompressedJson::new(json).map_err(|e| e.to_string())?,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    tracing::info!(
+        "List of all pokemons serialized and compressed in {} ms",
+        started.elapsed().as_millis()
+    );
 
-    resp_200_Ok_json!(pokemons)
+    let body = Arc::new(body);
+    ALL_POKEMONS_BODY.store(Some(body.clone()));
+    Ok(body)
 }
 
+/// Returns the pokemon dataset, fetching it when it's not cached or expired.
+///
+/// Expired dataset is served when the remote api fails or returns invalid data.
 pub async fn get_all_pokemons(
     req_client: &reqwest::Client,
 ) -> Result<RefVal<DataWrapper<ApiPokemonList>>, HttpResponse> {
-    let data = req_caching::post_json_cached::<DataWrapper<ApiPokemonList>, HttpResponse>(
-        req_client,
-        CACHE_KEY,
-        "https://beta.pokeapi.co/graphql/v1beta",
-        &json!(
-            {
-                "query": crate::queries::GET_ALL_POKEMONS,
-                "variables": null,
-                "operationName": "GetAllPokemons"
+    if let Some(data) = POKEMON_LIST.get_fresh() {
+        return Ok(data);
+    }
+    let _refresh_lock = POKEMON_LIST.refresh_lock().await;
+    // dataset could have been refreshed while waiting for the lock
+    if let Some(data) = POKEMON_LIST.get_fresh() {
+        return Ok(data);
+    }
+
+    match fetch_all_pokemons(req_client).await {
+        Ok((data, created_at)) => Ok(set_all_pokemons(data, created_at)),
+        Err(e) => match POKEMON_LIST.set_ttl(Some(STALE_RETRY_AFTER)) {
+            Some(stale) => {
+                tracing::warn!("Refreshing of pokemons failed, serving stale data: {}", e);
+                metrics::CACHE_STALE_SERVED.inc(["pokemon_list"]);
+                Ok(stale)
             }
-        ),
-        |error| {
-            response_from_error(
-                format!("Error encountered: {error}"),
+            None => Err(response_from_error(
+                format!("Error encountered: {e}"),
                 StatusCode::INTERNAL_SERVER_ERROR,
-            )
+            )),
         },
-    )
-    .await?;
+    }
+}
+
+/// Fetches the pokemon dataset and replaces the cached one if it's valid.
+///
+/// Requests are served from the old dataset until the new one is swapped in, which happens at once.
+pub async fn refresh_all_pokemons(req_client: &reqwest::Client) -> Result<(), String> {
+    let _refresh_lock = POKEMON_LIST.refresh_lock().await;
+    let (data, created_at) = fetch_all_pokemons(req_client).await?;
+    set_all_pokemons(data, created_at);
+    Ok(())
+}
+
+/// Fetches and validates the pokemon dataset, valid dataset is saved as a snapshot.
+///
+/// Returns the dataset and when it was fetched.
+async fn fetch_all_pokemons(
+    req_client: &reqwest::Client,
+) -> Result<(DataWrapper<ApiPokemonList>, u64), String> {
+    let body = json!(
+        {
+            "query": crate::queries::GET_ALL_POKEMONS,
+            "variables": null,
+            "operationName": "GetAllPokemons"
+        }
+    );
+    let (payload, data) = upstream()
+        .send(req_client, reqwest::Method::POST, |request| {
+            send_dataset_request(request.json(&body))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    let created_at = snapshot::unix_now();
+
+    if let Some(path) = snapshot::SNAPSHOT_PATH.get() {
+        let res =
+            tokio::task::spawn_blocking(move || snapshot::write(path, &payload, created_at)).await;
+        match res
+            .map_err(|e| e.to_string())
+            .and_then(|res| res.map_err(|e| e.to_string()))
+        {
+            Ok(()) => tracing::info!("Pokemon data snapshot saved to {}", path.display()),
+            Err(e) => tracing::warn!("Saving of pokemon data snapshot failed: {}", e),
+        }
+    }
+    Ok((data, created_at))
+}
+
+/// Sends the dataset request and validates the dataset, returns it with its payload.
+///
+/// Invalid dataset fails like an endpoint which is down, so other endpoints are asked for a valid one.
+async fn send_dataset_request(
+    request: reqwest::RequestBuilder,
+) -> Result<(Vec<u8>, DataWrapper<ApiPokemonList>), UpstreamError> {
+    let payload = req_util::send_graphql_bytes(request).await?;
+    let data = serde_json::from_slice::<DataWrapper<ApiPokemonList>>(&payload)
+        .map_err(|e| UpstreamError::Invalid(e.to_string()))?;
+    data.data.validate().map_err(UpstreamError::Invalid)?;
+    Ok((payload, data))
+}
+
+/// Loads the pokemon dataset from the snapshot, returns when the snapshot was created.
+pub async fn load_snapshot(path: &'static Path) -> Result<u64, String> {
+    let snapshot = tokio::task::spawn_blocking(move || snapshot::read(path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let data = parse_snapshot(&snapshot)?;
 
-    let entry = CACHE.entry::<bool>("get_all is cached".to_string()).await;
-    let mut lock = match entry.get_or_write_lock().await {
-        actix_web::Either::Left(_) => return Ok(data),
-        actix_web::Either::Right(write_lock) => write_lock,
+    set_all_pokemons(data, snapshot.created_at);
+    Ok(snapshot.created_at)
+}
+
+/// Loads the pokemon dataset from encoded snapshot received from a peer replica, returns when the snapshot was created.
+///
+/// Snapshot is loaded only when it was created after `newer_than` and it's saved as the local snapshot.
+pub async fn load_peer_snapshot(content: Vec<u8>, newer_than: u64) -> Result<u64, String> {
+    let _refresh_lock = POKEMON_LIST.refresh_lock().await;
+    let (content, snapshot) = tokio::task::spawn_blocking(move || {
+        snapshot::decode(&content).map(|snapshot| (content, snapshot))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    if snapshot.created_at <= newer_than {
+        return Err(format!(
+            "Snapshot created at {} isn't newer than {}",
+            snapshot.created_at, newer_than
+        ));
+    }
+    let data = parse_snapshot(&snapshot)?;
+    set_all_pokemons(data, snapshot.created_at);
+
+    if let Some(path) = snapshot::SNAPSHOT_PATH.get() {
+        let res =
+            tokio::task::spawn_blocking(move || snapshot::write_encoded(path, &content)).await;
+        match res
+            .map_err(|e| e.to_string())
+            .and_then(|res| res.map_err(|e| e.to_string()))
+        {
+            Ok(()) => tracing::info!("Pokemon data snapshot saved to {}", path.display()),
+            Err(e) => tracing::warn!("Saving of pokemon data snapshot failed: {}", e),
+        }
+    }
+    Ok(snapshot.created_at)
+}
+
+fn parse_snapshot(snapshot: &snapshot::Snapshot) -> Result<DataWrapper<ApiPokemonList>, String> {
+    let data = serde_json::from_slice::<DataWrapper<ApiPokemonList>>(&snapshot.payload)
+        .map_err(|e| e.to_string())?;
+    data.data.validate()?;
+    Ok(data)
+}
+
+/// Encodes the served dataset as a snapshot, `None` when no dataset is loaded yet.
+///
+/// Dataset is serialized on every call, so call it from a blocking task.
+pub fn encode_snapshot() -> Option<serde_json::Result<Vec<u8>>> {
+    let source = DATASET_SOURCE.load_full()?;
+    let payload = serde_json::to_vec(&*source.data);
+    Some(payload.map(|payload| snapshot::encode(&payload, source.created_at)))
+}
+
+/// Replaces the served dataset, `created_at` is when its data were fetched from the remote api.
+fn set_all_pokemons(
+    data: DataWrapper<ApiPokemonList>,
+    created_at: u64,
+) -> RefVal<DataWrapper<ApiPokemonList>> {
+    // builds the name index before the dataset is visible to requests
+    data.data.get("");
+    let previous = POKEMON_LIST.get();
+    let data = POKEMON_LIST.set_with_ttl(data, default_ttl());
+    DATASET_SOURCE.store(Some(Arc::new(DatasetSource {
+        data: data.clone(),
+        created_at,
+    })));
+    if let Some(previous) = previous {
+        dataset_changes::record(&previous.data, &data.data);
+    }
+
+    // body of the previous dataset is dropped and the new one is built before it's requested
+    ALL_POKEMONS_BODY.store(None);
+    let pokemon_list = data.clone();
+    actix_web::rt::spawn(async move {
+        if let Err(e) = all_pokemons_body(pokemon_list).await {
+            tracing::warn!("Building of list of all pokemons failed: {}", e);
+        }
+    });
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{body::MessageBody, test::TestRequest};
+
+    use super::*;
+    use crate::{
+        models::remote_api::test_data::{self, PokemonBuilder},
+        upstream::{mock::mock_endpoint, Upstream},
     };
-    lock.set(true);
-    drop(lock);
-
-    for pokemon in &data.data.results {
-        let entry = CACHE.entry(get_cache_key_for_pokemon(&pokemon.name)).await;
-        let mut lock = match entry.get_or_write_lock().await {
-            actix_web::Either::Left(_) => continue,
-            actix_web::Either::Right(write_lock) => write_lock,
+
+    fn dataset(picture: &str) -> RefVal<DataWrapper<ApiPokemonList>> {
+        let garchomp = PokemonBuilder::new("garchomp").picture(picture).build();
+        let pokemon_list = test_data::pokemon_list(vec![garchomp, test_data::pokemon("gible")]);
+        Snapshot::default().set_with_ttl(DataWrapper { data: pokemon_list }, None)
+    }
+
+    fn pokemons(body: &AllPokemonsBody) -> serde_json::Value {
+        let res = body.body.respond(&TestRequest::default().to_http_request());
+        let Ok(bytes) = res.into_body().try_into_bytes() else {
+            panic!("body is in memory");
         };
-        lock.set(pokemon.clone());
+        serde_json::from_slice(&bytes).unwrap()
     }
 
-    Ok(data)
+    #[actix_web::test]
+    async fn body_is_rebuilt_when_any_pokemon_changes() {
+        let first = all_pokemons_body(dataset("garchomp.png")).await.unwrap();
+        let reused = all_pokemons_body(dataset("garchomp.png")).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &reused));
+
+        // same names, so only the content version differs
+        assert_eq!(
+            dataset("garchomp.png").data.version(),
+            dataset("garchomp-mega.png").data.version()
+        );
+        let changed = all_pokemons_body(dataset("garchomp-mega.png"))
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+        assert_ne!(pokemons(&first), pokemons(&changed));
+        assert_eq!(pokemons(&changed).as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_dataset_is_failed_over() {
+        let valid = serde_json::to_string(&DataWrapper {
+            data: test_data::pokemon_list(vec![test_data::pokemon("gible")]),
+        })
+        .unwrap();
+        let duplicated = serde_json::to_string(&DataWrapper {
+            data: test_data::pokemon_list(vec![
+                test_data::pokemon("gible"),
+                test_data::pokemon("gible"),
+            ]),
+        })
+        .unwrap();
+        let invalid = mock_endpoint(Some((200, &duplicated))).await;
+        let working = mock_endpoint(Some((200, &valid))).await;
+
+        let send = |upstream: Upstream| async move {
+            upstream
+                .send(&reqwest::Client::new(), reqwest::Method::POST, |request| {
+                    send_dataset_request(request.json(&json!({ "query": "" })))
+                })
+                .await
+        };
+        let (_, data) = send(
+            Upstream::new(
+                &format!("{},{}", invalid.url, working.url),
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(data.data.results.len(), 1);
+
+        let e = send(
+            Upstream::new(
+                &invalid.url,
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+            )
+            .unwrap(),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(matches!(e, UpstreamError::Invalid(_)), "{e}");
+    }
 }
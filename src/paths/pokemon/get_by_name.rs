@@ -1,19 +1,35 @@
+use std::sync::LazyLock;
+
 use actix_web::{
     get,
     http::StatusCode,
     web::{self, Data},
-    HttpResponse, Responder,
+    Responder,
 };
 use serde_json::json;
 
+use super::{get_all::POKEMON_LIST, with_dataset_version};
 use crate::{
-    cache::CACHE,
+    cache::{default_ttl, CacheNamespace, STALE_RETRY_AFTER},
     macros::{resp_200_Ok_json, yeet_error},
-    models::{pokemon::Pokemon, remote_api::ApiPokemonList, DataWrapper},
+    metrics,
+    models::{
+        pokemon::Pokemon,
+        remote_api::{ApiPokemon, ApiPokemonList},
+        DataWrapper,
+    },
+    negative_cache,
     req_util::{self, response_from_error},
     FETCH_UNVERIFIED_DATA_FROM_API,
 };
 
+/// Pokemons fetched by name which are not in the verified dataset, shared with other replicas.
+pub static POKEMONS: LazyLock<CacheNamespace<String, ApiPokemon>> =
+    LazyLock::new(|| CacheNamespace::shared("pokemon"));
+
+/// Namespace of pokemon names not found by `/pokemon/get_by_name` in the negative cache.
+pub const NEGATIVE_CACHE_NAMESPACE: &str = "pokemon";
+
 #[utoipa::path(
     responses(
         (status = 200, description = "Returns pokemon by name", body = Pokemon),
@@ -46,19 +62,45 @@ pub async fn get_by_name(
         )
     };
 
-    let entry = CACHE.entry(get_cache_key_for_pokemon(&name)).await;
+    if let Some(pokemon_list) = POKEMON_LIST.get() {
+        if let Some(api_pokemon) = pokemon_list.data.get(&name) {
+            let pokemon = Pokemon::try_from(api_pokemon).map_err(failed_to_convert);
+            let pokemon = yeet_error!(pokemon);
+            return with_dataset_version(resp_200_Ok_json!(pokemon), &pokemon_list.data);
+        }
+    }
+    // unverified pokemons are not part of any dataset, so their responses have no version
     if unsafe { !FETCH_UNVERIFIED_DATA_FROM_API } {
-        return match entry.read().await.get() {
-            Some(api_pokemon) => {
-                let pokemon = Pokemon::try_from(api_pokemon).map_err(failed_to_convert);
-                let pokemon = yeet_error!(pokemon);
-                resp_200_Ok_json!(pokemon)
-            }
-            None => response_from_error("Pokemon was not found", StatusCode::NOT_FOUND),
-        };
+        return response_from_error("Pokemon was not found", StatusCode::NOT_FOUND);
     }
 
-    let mut lock = match entry.get_or_write_lock().await {
+    // pokemons cached locally exist, the negative cache is consulted only before fetching
+    let entry = POKEMONS.existing_entry(&name);
+    let cached = match &entry {
+        Some(entry) => entry.get().await.is_some(),
+        None => false,
+    };
+    if !cached && negative_cache::is_known_missing(NEGATIVE_CACHE_NAMESPACE, &name).await {
+        return response_from_error("Pokemon was not found", StatusCode::NOT_FOUND);
+    }
+
+    let entry = entry.unwrap_or_else(|| POKEMONS.entry(name.clone()));
+
+    let refetch = {
+        let req_client = req_client.get_ref().clone();
+        let name = name.clone();
+        move || async move {
+            match fetch_pokemon(&req_client, &name).await {
+                Ok(Some(api_pokemon)) => Ok(Some(api_pokemon)),
+                Ok(None) => {
+                    negative_cache::remember_missing(NEGATIVE_CACHE_NAMESPACE, &name).await;
+                    Ok(None)
+                }
+                Err(e) => Err(format!("pokemon {name}: {e}")),
+            }
+        }
+    };
+    let mut lock = match entry.get_or_write_lock_revalidating(refetch).await {
         actix_web::Either::Left(api_pokemon) => {
             let pokemon = Pokemon::try_from(&*api_pokemon).map_err(failed_to_convert);
             let pokemon = yeet_error!(pokemon);
@@ -67,40 +109,52 @@ pub async fn get_by_name(
         actix_web::Either::Right(write_lock) => write_lock,
     };
 
-    let res = req_util::post_json::<DataWrapper<ApiPokemonList>, HttpResponse>(
-        &req_client,
-        "https://beta.pokeapi.co/graphql/v1beta",
+    let api_pokemon = match fetch_pokemon(&req_client, &name).await {
+        Ok(Some(api_pokemon)) => api_pokemon,
+        Ok(None) => {
+            negative_cache::remember_missing(NEGATIVE_CACHE_NAMESPACE, &name).await;
+            return response_from_error("Pokemon was not found", StatusCode::NOT_FOUND);
+        }
+        // keeps serving the expired pokemon while the remote api is failing
+        Err(e) => {
+            let Some(stale) = lock.set_ttl(Some(STALE_RETRY_AFTER)) else {
+                return response_from_error(
+                    format!("Error encountered: {e}"),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                );
+            };
+            metrics::CACHE_STALE_SERVED.inc([POKEMONS.name()]);
+            let pokemon = Pokemon::try_from(&*stale).map_err(failed_to_convert);
+            let pokemon = yeet_error!(pokemon);
+            return resp_200_Ok_json!(pokemon);
+        }
+    };
+    let api_pokemon = lock.set_with_ttl(api_pokemon, default_ttl());
+
+    let pokemon = Pokemon::try_from(&*api_pokemon).map_err(failed_to_convert);
+    let pokemon = yeet_error!(pokemon);
+    resp_200_Ok_json!(pokemon)
+}
+
+/// Fetches pokemon from the remote api, returns `None` when it doesn't exist.
+async fn fetch_pokemon(
+    req_client: &reqwest::Client,
+    name: &str,
+) -> Result<Option<ApiPokemon>, String> {
+    let mut api_pokemon = req_util::post_json::<DataWrapper<ApiPokemonList>, String>(
+        req_client,
         &json!(
             {
-                "query": crate::queries::GET_POKEMON.replacen("$name", &name, 1),
+                "query": crate::queries::GET_POKEMON.replacen("$name", name, 1),
                 "variables": null,
                 "operationName": "GetPokemon"
             }
         ),
-        |error| {
-            response_from_error(
-                format!("Error encountered: {error}"),
-                StatusCode::INTERNAL_SERVER_ERROR,
-            )
-        },
+        |error| error.to_string(),
     )
-    .await;
-
-    let mut api_pokemon = yeet_error!(res);
+    .await?;
     if api_pokemon.data.results.len() != 1 {
-        return response_from_error("Pokemon was not found", StatusCode::NOT_FOUND);
-    };
-    let api_pokemon = api_pokemon.data.results.remove(0);
-
-    let pokemon = Pokemon::try_from(&api_pokemon)
-        .map_err(failed_to_convert)
-        .map(|pokemon| resp_200_Ok_json!(pokemon));
-
-    lock.set(api_pokemon);
-    yeet_error!(pokemon)
-}
-
-#[inline]
-pub fn get_cache_key_for_pokemon(pokemon_name: &str) -> String {
-    format!("pokemon//{pokemon_name}")
+        return Ok(None);
+    }
+    Ok(Some(api_pokemon.data.results.remove(0)))
 }
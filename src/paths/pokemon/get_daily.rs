@@ -0,0 +1,90 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+use actix_web_grants::authorities::AuthDetails;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::{get_all, with_dataset_version};
+use crate::{
+    daily::DailyConfig,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{daily_pokemon::DailyPokemon, pokemon::Pokemon},
+    req_util::response_from_error,
+};
+
+pub const HISTORY_GRANT: &str = "svc::pokemon_api::daily::history";
+pub const UPCOMING_GRANT: &str = "svc::pokemon_api::daily::upcoming";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DailyQuery {
+    /// Day in format YYYY-MM-DD, defaults to today<br>
+    /// Past days require `svc::pokemon_api::daily::history` grant
+    /// and upcoming days require `svc::pokemon_api::daily::upcoming` grant
+    #[param(value_type = Option<String>, format = Date)]
+    date: Option<NaiveDate>,
+}
+
+#[utoipa::path(
+    params(DailyQuery),
+    responses(
+        (status = 200, description = "Returns pokemon of the day", body = DailyPokemon),
+        (status = 400, description = "Parameter date isn't valid date"),
+        (status = 403, description = "Missing grant for past or upcoming days"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/daily"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/daily")]
+#[get("/pokemon/daily")]
+pub async fn get_daily(
+    query: web::Query<DailyQuery>,
+    auth_details: AuthDetails,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let config = DailyConfig::get();
+    let today = config.today();
+    let date = query.date.unwrap_or(today);
+
+    let required_grant = match date {
+        date if date < today => Some(HISTORY_GRANT),
+        date if date > today => Some(UPCOMING_GRANT),
+        _ => None,
+    };
+    if let Some(grant) = required_grant {
+        if !auth_details.authorities.contains(grant) {
+            return response_from_error(
+                format!("Grant '{grant}' is required for this date"),
+                StatusCode::FORBIDDEN,
+            );
+        }
+    }
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+
+    let dataset = yeet_error!(res);
+    let pokemon_list = &dataset.data.results;
+    let mut candidates = pokemon_list
+        .iter()
+        .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
+        .collect::<Vec<_>>();
+
+    let Some(i) = config.pick_index(date, candidates.iter().map(|pokemon| pokemon.name)) else {
+        return response_from_error(
+            "No pokemons are available",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    };
+    let res = resp_200_Ok_json!(DailyPokemon {
+        date,
+        pokemon: candidates.swap_remove(i),
+    });
+    with_dataset_version(res, &dataset.data)
+}
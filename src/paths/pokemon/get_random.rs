@@ -3,19 +3,26 @@ use actix_web::{
     web::{self, Data},
     Responder,
 };
+use actix_web_grants::authorities::AuthDetails;
 use rand::Rng;
 
-use super::get_all;
+use super::{get_all, with_dataset_version};
 use crate::{
-    macros::{resp_200_Ok_json, yeet_error},
+    credits::Banner,
+    draws::{finish_draw, DrawContext, DrawQuery},
+    jwt_stuff::JwtSubject,
+    macros::yeet_error,
     models::pokemon::Pokemon,
 };
 
 #[utoipa::path(
+    params(DrawQuery),
     responses(
         (status = 200, description = "Returns N random pokemons", body = [Pokemon]),
-        (status = 400, description = "Parameter count has wrong type or is outside of u8 range"),
-        (status = 500, description = "Failed to fetch/deserialize data from remote api"),
+        (status = 400, description = "Parameter count has wrong type or is outside of u8 range<br>or<br>Certificate, recording, charging or idempotency key was requested but JWT token doesn't contain sub claim<br>or<br>Idempotency key is empty or longer than 255 characters"),
+        (status = 402, description = "Balance of the requester is lower than the cost of the draw"),
+        (status = 409, description = "Idempotency key was already used for a different draw"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Certificate was requested but certificates are not configured<br>or<br>Failed to record draw"),
     ),
     security(
         ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/get_random"]),
@@ -23,10 +30,17 @@ use crate::{
 )]
 #[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/get_random")]
 #[get("/pokemon/get_random/{count}")]
-pub async fn get_random(count: web::Path<u8>, req_client: Data<reqwest::Client>) -> impl Responder {
+pub async fn get_random(
+    count: web::Path<u8>,
+    draw_query: web::Query<DrawQuery>,
+    subject: Option<JwtSubject>,
+    auth_details: AuthDetails,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
     let res = get_all::get_all_pokemons(&req_client).await;
 
-    let pokemon_list = &yeet_error!(res).data.results;
+    let dataset = yeet_error!(res);
+    let pokemon_list = &dataset.data.results;
     let mut pokemons = Vec::with_capacity(*count as usize);
     let mut rng = rand::thread_rng();
 
@@ -41,5 +55,20 @@ pub async fn get_random(count: web::Path<u8>, req_client: Data<reqwest::Client>)
         }
     }
 
-    resp_200_Ok_json!(pokemons)
+    let res = finish_draw(
+        pokemons,
+        &draw_query,
+        DrawContext {
+            banner: Banner::Random,
+            count: *count,
+            subject: subject.as_ref(),
+            auth_details: &auth_details,
+            seed: None,
+            dataset: &dataset.data,
+            parameters: Vec::new(),
+        },
+        |pokemons| pokemons,
+    )
+    .await;
+    with_dataset_version(res, &dataset.data)
 }
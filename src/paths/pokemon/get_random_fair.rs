@@ -0,0 +1,117 @@
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    Responder,
+};
+use actix_web_grants::authorities::AuthDetails;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::{get_all, with_dataset_version};
+use crate::{
+    credits::Banner,
+    draws::{finish_draw, DrawContext, DrawQuery},
+    jwt_stuff::JwtSubject,
+    macros::yeet_error,
+    models::{audit::AuditSeed, fair_draw::FairDraw, pokemon::Pokemon},
+    provably_fair::{sort_draw_candidates, ProvablyFair},
+    req_util::response_from_error,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FairDrawQuery {
+    /// Seed chosen by the client, 1 to 64 characters long
+    client_seed: String,
+    /// Should be incremented by the client for every draw with the same server and client seed
+    nonce: u64,
+}
+
+#[utoipa::path(
+    params(FairDrawQuery, DrawQuery),
+    responses(
+        (status = 200, description = "Returns N random pokemons drawn from server seed, client seed and nonce", body = FairDraw),
+        (status = 400, description = "Parameter count has wrong type or is outside of u8 range<br>or<br>Client seed is empty or longer than 64 characters<br>or<br>Certificate, recording, charging or idempotency key was requested but JWT token doesn't contain sub claim<br>or<br>Idempotency key is empty or longer than 255 characters"),
+        (status = 402, description = "Balance of the requester is lower than the cost of the draw"),
+        (status = 409, description = "Idempotency key was already used for a different draw"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Certificate was requested but certificates are not configured<br>or<br>Failed to record draw"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/get_random_fair"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/get_random_fair")]
+#[get("/pokemon/get_random_fair/{count}")]
+pub async fn get_random_fair(
+    count: web::Path<u8>,
+    query: web::Query<FairDrawQuery>,
+    draw_query: web::Query<DrawQuery>,
+    subject: Option<JwtSubject>,
+    auth_details: AuthDetails,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let FairDrawQuery { client_seed, nonce } = query.into_inner();
+    if client_seed.is_empty() || client_seed.len() > 64 {
+        return response_from_error(
+            "Client seed must be 1 to 64 characters long",
+            StatusCode::BAD_REQUEST,
+        );
+    }
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+
+    let dataset = yeet_error!(res);
+    let pokemon_list = &dataset.data.results;
+    let mut candidates = pokemon_list
+        .iter()
+        .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return response_from_error(
+            "No pokemons are available",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    }
+    sort_draw_candidates(&mut candidates, |pokemon| pokemon.name);
+
+    let res = ProvablyFair::get();
+    let provably_fair = yeet_error!(res);
+    // held until the draw is committed, so its seed can't be revealed meanwhile
+    let server_seed = provably_fair.draw_lock().await;
+    let pokemons = server_seed
+        .draw_indices(&client_seed, nonce, *count as usize, candidates.len())
+        .into_iter()
+        .map(|i| candidates[i].clone())
+        .collect::<Vec<_>>();
+
+    let res = finish_draw(
+        pokemons,
+        &draw_query,
+        DrawContext {
+            banner: Banner::Fair,
+            count: *count,
+            subject: subject.as_ref(),
+            auth_details: &auth_details,
+            seed: Some(AuditSeed {
+                server_seed_hash: server_seed.hash().to_string(),
+                client_seed: client_seed.clone(),
+                nonce,
+            }),
+            dataset: &dataset.data,
+            parameters: vec![
+                ("client_seed", client_seed.clone()),
+                ("nonce", nonce.to_string()),
+            ],
+        },
+        |pokemons| FairDraw {
+            server_seed_hash: server_seed.hash().to_string(),
+            dataset_version: dataset.data.version().to_string(),
+            client_seed,
+            nonce,
+            pokemons,
+        },
+    )
+    .await;
+    with_dataset_version(res, &dataset.data)
+}
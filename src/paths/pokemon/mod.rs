@@ -1,11 +1,68 @@
+pub mod changes;
+pub mod generate;
 pub mod get_all;
 pub mod get_by_name;
+pub mod get_daily;
 pub mod get_random;
+pub mod get_random_fair;
+pub mod random_team;
+pub mod verify;
 
-use actix_web::web::ServiceConfig;
+use actix_web::{
+    http::header::{HeaderName, HeaderValue},
+    web::ServiceConfig,
+    HttpResponse,
+};
+
+use crate::models::remote_api::ApiPokemonList;
+
+pub const DATASET_VERSION_HEADER: &str = "dataset-version";
 
 pub fn configure(cfg: &mut ServiceConfig) {
     cfg.service(get_by_name::get_by_name)
         .service(get_all::get_all)
-        .service(get_random::get_random);
+        .service(get_random::get_random)
+        .service(get_random_fair::get_random_fair)
+        .service(generate::generate)
+        .service(verify::verify)
+        .service(get_daily::get_daily)
+        .service(random_team::random_team)
+        .service(changes::changes);
+}
+
+/// Adds content version of the dataset the response was built from.
+///
+/// Dataset can be replaced while a request is handled, so the version is taken from the dataset the handler used.
+/// Responses which already have the version, e.g. replayed draws, keep it.
+pub fn with_dataset_version(mut res: HttpResponse, dataset: &ApiPokemonList) -> HttpResponse {
+    if res.headers().contains_key(DATASET_VERSION_HEADER) {
+        return res;
+    }
+    if let Ok(version) = HeaderValue::from_str(dataset.content_version()) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(DATASET_VERSION_HEADER), version);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::remote_api::test_data;
+
+    #[test]
+    fn response_has_version_of_its_dataset() {
+        let dataset = test_data::pokemon_list(vec![test_data::pokemon("eevee")]);
+        let res = with_dataset_version(HttpResponse::Ok().finish(), &dataset);
+        assert_eq!(
+            res.headers().get(DATASET_VERSION_HEADER).unwrap(),
+            dataset.content_version()
+        );
+
+        let replayed = HttpResponse::Ok()
+            .insert_header((DATASET_VERSION_HEADER, "older"))
+            .finish();
+        let res = with_dataset_version(replayed, &dataset);
+        assert_eq!(res.headers().get(DATASET_VERSION_HEADER).unwrap(), "older");
+    }
 }
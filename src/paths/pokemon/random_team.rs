@@ -0,0 +1,272 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    HttpResponse, Responder,
+};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use super::{get_all, with_dataset_version};
+use crate::{
+    cache::{CacheNamespace, RefVal},
+    macros::{resp_200_Ok_json, yeet_error},
+    models::{
+        pokemon::Pokemon,
+        random_team::{RandomTeam, TeamMember, TypeWeakness},
+        remote_api::ApiTypeEfficacies,
+        DataWrapper,
+    },
+    req_caching,
+    req_util::response_from_error,
+};
+
+pub const TEAM_SIZE: usize = 6;
+
+pub static TYPE_EFFICACIES: LazyLock<CacheNamespace<(), DataWrapper<ApiTypeEfficacies>>> =
+    LazyLock::new(|| CacheNamespace::new("type_efficacies"));
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RandomTeamQuery {
+    /// Maximal base stat total of every team member
+    max_base_stat_total: Option<u16>,
+    /// Generation all team members have to be from
+    generation: Option<u8>,
+}
+
+#[utoipa::path(
+    params(RandomTeamQuery),
+    responses(
+        (status = 200, description = "Returns team of six different pokemons with as diverse types as possible and team's type weaknesses", body = RandomTeam),
+        (status = 400, description = "Query parameters have wrong type"),
+        (status = 404, description = "Not enough pokemons match the constraints"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/random_team"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/random_team")]
+#[get("/pokemon/random_team")]
+pub async fn random_team(
+    query: web::Query<RandomTeamQuery>,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let dataset = yeet_error!(res);
+    let pokemon_list = &dataset.data.results;
+    let res = get_type_efficacies(&req_client).await;
+    let type_efficacies = &yeet_error!(res).data;
+
+    let mut candidates = pokemon_list
+        .iter()
+        .filter(|api_pokemon| {
+            let too_strong = matches!(
+                query.max_base_stat_total,
+                Some(max) if api_pokemon.base_stat_total() > max
+            );
+            let other_generation = matches!(
+                query.generation,
+                Some(generation) if api_pokemon.generation() != Some(generation)
+            );
+            !too_strong && !other_generation
+        })
+        .filter_map(|api_pokemon| {
+            Pokemon::try_from(api_pokemon)
+                .ok()
+                .map(|pokemon| (api_pokemon, pokemon))
+        })
+        .collect::<Vec<_>>();
+    candidates.shuffle(&mut rand::thread_rng());
+
+    let types = candidates
+        .iter()
+        .map(|(api_pokemon, _)| api_pokemon.type_names())
+        .collect::<Vec<_>>();
+    let team = pick_diverse_team(&types);
+    if team.len() < TEAM_SIZE {
+        return response_from_error(
+            "Not enough pokemons match the constraints",
+            StatusCode::NOT_FOUND,
+        );
+    }
+
+    let members = team
+        .into_iter()
+        .map(|i| {
+            let (api_pokemon, pokemon) = &candidates[i];
+            TeamMember {
+                pokemon: pokemon.clone(),
+                types: types[i].clone(),
+                base_stat_total: api_pokemon.base_stat_total(),
+                generation: api_pokemon.generation(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let type_chart = type_efficacies.chart();
+    let type_weaknesses = type_chart
+        .attacking_types()
+        .map(|attacking| {
+            let mut weakness = TypeWeakness {
+                r#type: attacking,
+                weak: 0,
+                resistant: 0,
+                immune: 0,
+            };
+            for member in &members {
+                let multiplier = type_chart.multiplier(attacking, &member.types);
+                if multiplier == 0.0 {
+                    weakness.immune += 1;
+                } else if multiplier < 1.0 {
+                    weakness.resistant += 1;
+                } else if multiplier > 1.0 {
+                    weakness.weak += 1;
+                }
+            }
+            weakness
+        })
+        .collect();
+
+    let res = resp_200_Ok_json!(RandomTeam {
+        members,
+        type_weaknesses,
+    });
+    with_dataset_version(res, &dataset.data)
+}
+
+/// Picks indices of up to [`TEAM_SIZE`] candidates with as diverse types as possible from their types.
+///
+/// Members which don't share any type are preferred and the limit is relaxed only when the team can't be filled,
+/// primary types are repeated only when there aren't enough candidates with distinct primary types.
+fn pick_diverse_team(types: &[Vec<&str>]) -> Vec<usize> {
+    let mut picked = vec![false; types.len()];
+    let mut type_counts = HashMap::<&str, usize>::new();
+    let mut team = Vec::with_capacity(TEAM_SIZE);
+    for repeat_primary in [false, true] {
+        for max_per_type in 1..=TEAM_SIZE {
+            for (i, types) in types.iter().enumerate() {
+                if team.len() == TEAM_SIZE {
+                    return team;
+                }
+                let primary_taken = types.first().is_some_and(|t| type_counts.contains_key(t));
+                if picked[i]
+                    || (primary_taken && !repeat_primary)
+                    || types
+                        .iter()
+                        .any(|t| type_counts.get(t).copied().unwrap_or_default() >= max_per_type)
+                {
+                    continue;
+                }
+
+                for t in types {
+                    *type_counts.entry(t).or_default() += 1;
+                }
+                picked[i] = true;
+                team.push(i);
+            }
+        }
+    }
+    team
+}
+
+pub async fn get_type_efficacies(
+    req_client: &reqwest::Client,
+) -> Result<RefVal<DataWrapper<ApiTypeEfficacies>>, HttpResponse> {
+    req_caching::post_json_cached(
+        req_client,
+        &TYPE_EFFICACIES,
+        (),
+        &json!(
+            {
+                "query": crate::queries::GET_TYPE_EFFICACIES,
+                "variables": null,
+                "operationName": "GetTypeEfficacies"
+            }
+        ),
+        |error| {
+            response_from_error(
+                format!("Error encountered: {error}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn primary_types<'a>(types: &[Vec<&'a str>], team: &[usize]) -> HashSet<&'a str> {
+        team.iter().map(|i| types[*i][0]).collect()
+    }
+
+    #[test]
+    fn members_without_shared_types_are_preferred() {
+        let types = vec![
+            vec!["fire"],
+            vec!["fire", "flying"],
+            vec!["water"],
+            vec!["grass", "poison"],
+            vec!["poison"],
+            vec!["electric"],
+            vec!["ground"],
+            vec!["rock"],
+            vec!["ice"],
+        ];
+        let team = pick_diverse_team(&types);
+        assert_eq!(team, [0, 2, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn primary_types_are_not_repeated_while_enough_candidates_exist() {
+        // every candidate shares flying, so the limit of types has to be relaxed
+        let types = vec![
+            vec!["fire", "flying"],
+            vec!["fire", "flying"],
+            vec!["water", "flying"],
+            vec!["water", "flying"],
+            vec!["grass", "flying"],
+            vec!["electric", "flying"],
+            vec!["ground", "flying"],
+            vec!["rock", "flying"],
+        ];
+        let team = pick_diverse_team(&types);
+        assert_eq!(team.len(), TEAM_SIZE);
+        assert_eq!(primary_types(&types, &team).len(), TEAM_SIZE);
+    }
+
+    #[test]
+    fn primary_types_are_repeated_to_fill_the_team() {
+        let types = vec![
+            vec!["fire"],
+            vec!["fire"],
+            vec!["water"],
+            vec!["water"],
+            vec!["grass"],
+            vec!["grass"],
+            vec!["grass"],
+        ];
+        let team = pick_diverse_team(&types);
+        assert_eq!(team.len(), TEAM_SIZE);
+        assert_eq!(primary_types(&types, &team).len(), 3);
+        let mut sorted = team.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), TEAM_SIZE);
+    }
+
+    #[test]
+    fn team_is_not_filled_without_enough_candidates() {
+        let types = vec![vec!["fire"], vec!["water"], vec!["fire", "water"]];
+        assert_eq!(pick_diverse_team(&types), [0, 1, 2]);
+    }
+}
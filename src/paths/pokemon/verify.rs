@@ -0,0 +1,48 @@
+use actix_web::{http::StatusCode, post, web, Responder};
+
+use crate::{
+    certificates::{CertificateClaims, CERTIFICATE_SIGNER},
+    macros::resp_200_Ok_json,
+    models::certificate::{VerifiedCertificate, VerifyCertificate},
+    req_util::response_from_error,
+};
+
+#[utoipa::path(
+    request_body = VerifyCertificate,
+    responses(
+        (status = 200, description = "Certificate is valid and was issued by this api, returns its content", body = VerifiedCertificate),
+        (status = 400, description = "Invalid request body<br>or<br>Certificate is invalid or wasn't issued by this api"),
+        (status = 500, description = "Certificates are not configured"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/pokemon/verify"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/pokemon/verify")]
+#[post("/pokemon/verify")]
+pub async fn verify(body: web::Json<VerifyCertificate>) -> impl Responder {
+    let Some(signer) = CERTIFICATE_SIGNER.get() else {
+        return response_from_error(
+            "Certificates are not configured",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    };
+
+    match signer.verify::<serde_json::Value>(&body.certificate) {
+        Ok(CertificateClaims {
+            sub,
+            iat,
+            draw_id,
+            index,
+            pokemon,
+            ..
+        }) => resp_200_Ok_json!(VerifiedCertificate {
+            sub,
+            iat,
+            draw_id,
+            index,
+            pokemon,
+        }),
+        Err(e) => response_from_error(format!("Invalid certificate: {e}"), StatusCode::BAD_REQUEST),
+    }
+}
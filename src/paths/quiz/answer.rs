@@ -0,0 +1,148 @@
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{self, Data},
+    Responder,
+};
+use jsonwebtoken::errors::ErrorKind;
+
+use super::{challenge_pokemon, get_species_names};
+use crate::{
+    fuzzy_match::name_matches,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::quiz::{QuizAnswer, QuizGuess},
+    paths::pokemon::get_all,
+    quiz::QuizConfig,
+    req_util::response_from_error,
+    store::Store,
+};
+
+#[utoipa::path(
+    request_body = QuizGuess,
+    responses(
+        (status = 200, description = "Returns whether the guess is correct, guess can be the pokemon name or any of its localized names with small typos, every challenge can be answered only once", body = QuizAnswer),
+        (status = 400, description = "Invalid request body<br>or<br>Challenge id is invalid"),
+        (status = 409, description = "Pokemon dataset changed since the challenge was created"),
+        (status = 410, description = "Challenge expired or was already answered"),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Failed to mark challenge answered"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/quiz/answer"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/quiz/answer")]
+#[post("/quiz/{id}/answer")]
+pub async fn answer(
+    id: web::Path<String>,
+    body: web::Json<QuizGuess>,
+    req_client: Data<reqwest::Client>,
+) -> impl Responder {
+    let claims = match QuizConfig::get().verify(&id) {
+        Ok(claims) => claims,
+        Err(e) if matches!(e.kind(), ErrorKind::ExpiredSignature) => {
+            return response_from_error("Challenge expired", StatusCode::GONE)
+        }
+        Err(e) => {
+            return response_from_error(
+                format!("Challenge id is invalid: {e}"),
+                StatusCode::BAD_REQUEST,
+            )
+        }
+    };
+
+    // marked before the answer is checked, so concurrent answers aren't both checked
+    // and the challenge can't be retried when checking it fails
+    let store = yeet_error!(Store::get());
+    let res = store
+        .mark_challenge_answered(claims.jti.clone(), claims.exp)
+        .await;
+    if !yeet_error!(res.map_err(|e| e.into_response("Failed to mark challenge answered"))) {
+        return response_from_error("Challenge was already answered", StatusCode::GONE);
+    }
+
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokemon_list = yeet_error!(res);
+    if pokemon_list.data.version() != claims.dataset_version {
+        return response_from_error(
+            "Pokemon dataset changed since the challenge was created",
+            StatusCode::CONFLICT,
+        );
+    }
+    let Some(pokemon) = challenge_pokemon(&pokemon_list.data.results, &claims.jti) else {
+        return response_from_error(
+            "No pokemons are available",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    };
+
+    let guess = &body.guess;
+    let matched_language = if name_matches(guess, pokemon.name) {
+        Some("en".to_string())
+    } else {
+        let res = get_species_names(&req_client, pokemon.name).await;
+        yeet_error!(res).and_then(|species_names| {
+            species_names
+                .data
+                .names()
+                .find(|name| name_matches(guess, &name.name))
+                .map(|name| name.language.name.clone())
+        })
+    };
+
+    resp_200_Ok_json!(QuizAnswer {
+        correct: matched_language.is_some(),
+        name: matched_language.as_ref().map(|_| pokemon.name.to_string()),
+        language: matched_language,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        jwt_stuff::{test_authorization, JwtGrantsMiddleware},
+        store::STORE,
+    };
+
+    async fn post_answer(id: &str) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtGrantsMiddleware::test())
+                .app_data(Data::new(reqwest::Client::new()))
+                .service(answer),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/quiz/{id}/answer"))
+            .insert_header(test_authorization(
+                None,
+                &["svc::pokemon_api::route::/quiz/answer"],
+            ))
+            .set_json(json!({ "guess": "pikachu" }))
+            .to_request();
+        test::call_service(&app, req).await.status()
+    }
+
+    #[actix_web::test]
+    async fn answered_challenge_is_rejected() {
+        let store = STORE.get_or_init(|| Store::open(":memory:").unwrap());
+        let (id, claims) = QuizConfig::get().new_challenge("v1").unwrap();
+        assert!(store
+            .mark_challenge_answered(claims.jti, claims.exp)
+            .await
+            .unwrap());
+        assert_eq!(post_answer(&id).await, StatusCode::GONE);
+    }
+
+    #[actix_web::test]
+    async fn invalid_challenge_is_rejected() {
+        let (id, _) = QuizConfig::new(b"other secret".to_vec())
+            .new_challenge("v1")
+            .unwrap();
+        assert_eq!(post_answer(&id).await, StatusCode::BAD_REQUEST);
+        assert_eq!(post_answer("not-a-token").await, StatusCode::BAD_REQUEST);
+    }
+}
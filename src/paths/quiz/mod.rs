@@ -0,0 +1,162 @@
+pub mod answer;
+pub mod new;
+
+use std::{fmt::Display, sync::LazyLock};
+
+use actix_web::{http::StatusCode, web::ServiceConfig, Either, HttpResponse};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::json;
+
+use crate::{
+    cache::{CacheNamespace, RefVal},
+    models::{
+        pokemon::Pokemon,
+        remote_api::{ApiPokemon, ApiPokemonSpeciesNamesList},
+        DataWrapper,
+    },
+    negative_cache,
+    provably_fair::sort_draw_candidates,
+    quiz::{disguise, silhouette, QuizConfig},
+    req_caching,
+    req_util::response_from_error,
+};
+
+/// Silhouettes of pokemons as PNG by pokemon name, they are disguised for every challenge.
+pub static SILHOUETTES: LazyLock<CacheNamespace<String, Vec<u8>>> =
+    LazyLock::new(|| CacheNamespace::new("quiz_silhouette"));
+
+/// Localized names of pokemon species by pokemon name.
+pub static SPECIES_NAMES: LazyLock<
+    CacheNamespace<String, DataWrapper<ApiPokemonSpeciesNamesList>>,
+> = LazyLock::new(|| CacheNamespace::new("pokemon_species_names"));
+
+/// Namespace of pokemon names without localized species names in the negative cache.
+pub const SPECIES_NEGATIVE_CACHE_NAMESPACE: &str = "species";
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(new::new).service(answer::answer);
+}
+
+/// Returns pokemon of the challenge, `None` when there are no pokemons.
+fn challenge_pokemon<'a>(
+    pokemon_list: &'a [ApiPokemon],
+    challenge_id: &str,
+) -> Option<Pokemon<'a>> {
+    let mut candidates = pokemon_list
+        .iter()
+        .filter_map(|api_pokemon| Pokemon::try_from(api_pokemon).ok())
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return None;
+    }
+    sort_draw_candidates(&mut candidates, |pokemon| pokemon.name);
+    let index = QuizConfig::get().pick_index(challenge_id, candidates.len());
+    Some(candidates.swap_remove(index))
+}
+
+fn silhouette_error(e: impl Display) -> HttpResponse {
+    response_from_error(
+        format!("Failed to create silhouette: {e}"),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+/// Returns silhouette of the pokemon's official artwork as PNG.
+async fn get_silhouette(
+    req_client: &reqwest::Client,
+    pokemon: &Pokemon<'_>,
+) -> Result<RefVal<Vec<u8>>, HttpResponse> {
+    let entry = SILHOUETTES.entry(pokemon.name.to_string());
+    let mut lock = match entry.get_or_write_lock().await {
+        Either::Left(silhouette) => return Ok(silhouette),
+        Either::Right(write_lock) => write_lock,
+    };
+
+    let on_error = |error: reqwest::Error| {
+        response_from_error(
+            format!("Error encountered: {error}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    };
+    let artwork = req_client
+        .get(pokemon.pictures.front_default)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(on_error)?
+        .bytes()
+        .await
+        .map_err(on_error)?;
+    let png = tokio::task::spawn_blocking(move || silhouette(&artwork))
+        .await
+        .map_err(silhouette_error)?
+        .map_err(silhouette_error)?;
+
+    Ok(lock.set(png))
+}
+
+/// Returns silhouette of the pokemon disguised for a single challenge as PNG data URL.
+async fn get_challenge_silhouette(
+    req_client: &reqwest::Client,
+    pokemon: &Pokemon<'_>,
+) -> Result<String, HttpResponse> {
+    let silhouette = get_silhouette(req_client, pokemon).await?;
+    let png = tokio::task::spawn_blocking(move || disguise(&silhouette, &mut rand::thread_rng()))
+        .await
+        .map_err(silhouette_error)?
+        .map_err(silhouette_error)?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+/// Returns localized names of the pokemon's species, `None` when the remote api has none.
+///
+/// Pokemons without names are remembered in the negative cache, so other replicas don't ask for them either.
+async fn get_species_names(
+    req_client: &reqwest::Client,
+    name: &str,
+) -> Result<Option<RefVal<DataWrapper<ApiPokemonSpeciesNamesList>>>, HttpResponse> {
+    if negative_cache::is_known_missing(SPECIES_NEGATIVE_CACHE_NAMESPACE, name).await {
+        return Ok(None);
+    }
+    let species_names = req_caching::post_json_cached(
+        req_client,
+        &SPECIES_NAMES,
+        name.to_string(),
+        &json!(
+            {
+                "query": crate::queries::GET_POKEMON_SPECIES_NAMES.replacen("$name", name, 1),
+                "variables": null,
+                "operationName": "GetPokemonSpeciesNames"
+            }
+        ),
+        |error| {
+            response_from_error(
+                format!("Error encountered: {error}"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        },
+    )
+    .await?;
+    if species_names.data.names().next().is_none() {
+        negative_cache::remember_missing(SPECIES_NEGATIVE_CACHE_NAMESPACE, name).await;
+        return Ok(None);
+    }
+    Ok(Some(species_names))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn species_known_to_be_missing_are_not_fetched() {
+        negative_cache::remember_missing(SPECIES_NEGATIVE_CACHE_NAMESPACE, "substitute").await;
+        // nothing listens on the port, so a request would fail
+        let req_client = reqwest::Client::new();
+        let names = get_species_names(&req_client, "substitute").await;
+        assert!(matches!(names, Ok(None)));
+        assert!(SPECIES_NAMES
+            .existing_entry(&"substitute".to_string())
+            .is_none());
+    }
+}
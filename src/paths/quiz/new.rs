@@ -0,0 +1,50 @@
+use actix_web::{get, http::StatusCode, web::Data, Responder};
+
+use super::{challenge_pokemon, get_challenge_silhouette};
+use crate::{
+    macros::{resp_200_Ok_json, yeet_error},
+    models::quiz::QuizChallenge,
+    paths::pokemon::get_all,
+    quiz::QuizConfig,
+    req_util::response_from_error,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns new challenge with silhouette of a random pokemon", body = QuizChallenge),
+        (status = 500, description = "Failed to fetch/deserialize data from remote api<br>or<br>Failed to create silhouette or sign the challenge"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/quiz/new"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/quiz/new")]
+#[get("/quiz/new")]
+pub async fn new(req_client: Data<reqwest::Client>) -> impl Responder {
+    let res = get_all::get_all_pokemons(&req_client).await;
+    let pokemon_list = yeet_error!(res);
+
+    let config = QuizConfig::get();
+    let res = config.new_challenge(pokemon_list.data.version());
+    let (id, claims) = yeet_error!(res.map_err(|e| {
+        response_from_error(
+            format!("Failed to sign challenge: {e}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }));
+    let Some(pokemon) = challenge_pokemon(&pokemon_list.data.results, &claims.jti) else {
+        return response_from_error(
+            "No pokemons are available",
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    };
+
+    let res = get_challenge_silhouette(&req_client, &pokemon).await;
+    let silhouette = yeet_error!(res);
+
+    resp_200_Ok_json!(QuizChallenge {
+        id,
+        silhouette,
+        expires_at: claims.exp,
+    })
+}
@@ -0,0 +1,33 @@
+use actix_web::{post, web, Responder};
+
+use super::trade_response;
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::yeet_error,
+    models::trade::Trade,
+    store::{trades::TradeAction, Store},
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Trade was cancelled", body = Trade),
+        (status = 400, description = "Parameter id has wrong type or JWT token doesn't contain sub claim"),
+        (status = 403, description = "Only offerer can cancel the trade"),
+        (status = 404, description = "Trade was not found"),
+        (status = 409, description = "Trade is not pending anymore"),
+        (status = 500, description = "Failed to process trade"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/trade/cancel"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/trade/cancel")]
+#[post("/trade/cancel/{id}")]
+pub async fn cancel(id: web::Path<i64>, subject: JwtSubject) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    trade_response(
+        store
+            .resolve_trade(*id, subject.0, TradeAction::Cancelled)
+            .await,
+    )
+}
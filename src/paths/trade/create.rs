@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+
+use super::trade_response;
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::yeet_error,
+    models::trade::{CreateTrade, Trade},
+    store::Store,
+};
+
+#[utoipa::path(
+    request_body = CreateTrade,
+    responses(
+        (status = 200, description = "Trade offer was created", body = Trade),
+        (status = 400, description = "Invalid request body or JWT token doesn't contain sub claim<br>or<br>Trade offer is invalid"),
+        (status = 409, description = "Some pokemon is not owned by the expected trader"),
+        (status = 500, description = "Failed to process trade"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/trade/create"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/trade/create")]
+#[post("/trade/create")]
+pub async fn create(subject: JwtSubject, body: web::Json<CreateTrade>) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let CreateTrade {
+        recipient,
+        offered,
+        requested,
+    } = body.into_inner();
+    trade_response(
+        store
+            .create_trade(subject.0, recipient, offered, requested)
+            .await,
+    )
+}
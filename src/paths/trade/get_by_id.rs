@@ -0,0 +1,31 @@
+use actix_web::{get, http::StatusCode, web, Responder};
+
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::trade::Trade,
+    req_util::response_from_error,
+    store::Store,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns trade with its audit trail", body = Trade),
+        (status = 400, description = "Parameter id has wrong type or JWT token doesn't contain sub claim"),
+        (status = 404, description = "Trade was not found"),
+        (status = 500, description = "Failed to read trade"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/trade/get_by_id"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/trade/get_by_id")]
+#[get("/trade/get_by_id/{id}")]
+pub async fn get_by_id(id: web::Path<i64>, subject: JwtSubject) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let res = store.trade(*id, subject.0).await;
+    match yeet_error!(res.map_err(|e| e.into_response("Failed to read trade"))) {
+        Some(trade) => resp_200_Ok_json!(Trade::from(trade)),
+        None => response_from_error("Trade was not found", StatusCode::NOT_FOUND),
+    }
+}
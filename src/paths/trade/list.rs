@@ -0,0 +1,27 @@
+use actix_web::{get, Responder};
+
+use crate::{
+    jwt_stuff::JwtSubject,
+    macros::{resp_200_Ok_json, yeet_error},
+    models::trade::Trade,
+    store::Store,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns all trades the requester participates in, newest first", body = [Trade]),
+        (status = 400, description = "JWT token doesn't contain sub claim"),
+        (status = 500, description = "Failed to read trades"),
+    ),
+    security(
+        ("jwt_grants" = ["svc::pokemon_api::route::/trade/list"]),
+    )
+)]
+#[actix_web_grants::protect("svc::pokemon_api::route::/trade/list")]
+#[get("/trade/list")]
+pub async fn list(subject: JwtSubject) -> impl Responder {
+    let store = yeet_error!(Store::get());
+    let res = store.trades(subject.0).await;
+    let trades = yeet_error!(res.map_err(|e| e.into_response("Failed to read trades")));
+    resp_200_Ok_json!(trades.into_iter().map(Trade::from).collect::<Vec<_>>())
+}
@@ -0,0 +1,46 @@
+pub mod accept;
+pub mod cancel;
+pub mod create;
+pub mod decline;
+pub mod get_by_id;
+pub mod list;
+
+use actix_web::{http::StatusCode, web::ServiceConfig, HttpResponse};
+
+use crate::{
+    macros::resp_200_Ok_json,
+    models::trade::Trade,
+    req_util::response_from_error,
+    store::{
+        trades::{TradeError, TradeRow},
+        StoreError,
+    },
+};
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(create::create)
+        .service(accept::accept)
+        .service(decline::decline)
+        .service(cancel::cancel)
+        .service(list::list)
+        .service(get_by_id::get_by_id);
+}
+
+fn trade_response(res: Result<Result<TradeRow, TradeError>, StoreError>) -> HttpResponse {
+    match res {
+        Ok(Ok(trade)) => resp_200_Ok_json!(Trade::from(trade)),
+        Ok(Err(TradeError::NotFound)) => {
+            response_from_error("Trade was not found", StatusCode::NOT_FOUND)
+        }
+        Ok(Err(TradeError::NotAllowed(msg))) => response_from_error(msg, StatusCode::FORBIDDEN),
+        Ok(Err(TradeError::NotPending)) => {
+            response_from_error("Trade is not pending anymore", StatusCode::CONFLICT)
+        }
+        Ok(Err(TradeError::NotOwned(id))) => response_from_error(
+            format!("Pokemon with id {id} is not owned by the expected trader"),
+            StatusCode::CONFLICT,
+        ),
+        Ok(Err(TradeError::Invalid(msg))) => response_from_error(msg, StatusCode::BAD_REQUEST),
+        Err(e) => e.into_response("Failed to process trade"),
+    }
+}
@@ -0,0 +1,22 @@
+use actix_web::{get, http::StatusCode, Responder};
+
+use crate::{
+    certificates::CERTIFICATE_SIGNER, macros::resp_200_Ok_json, models::jwks::Jwks,
+    req_util::response_from_error,
+};
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Returns public keys used to sign pokemon certificates", body = Jwks),
+        (status = 404, description = "Certificates are not configured"),
+    )
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks() -> impl Responder {
+    match CERTIFICATE_SIGNER.get() {
+        Some(signer) => resp_200_Ok_json!(Jwks {
+            keys: vec![signer.jwk().clone()],
+        }),
+        None => response_from_error("Certificates are not configured", StatusCode::NOT_FOUND),
+    }
+}
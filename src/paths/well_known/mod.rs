@@ -0,0 +1,7 @@
+pub mod jwks;
+
+use actix_web::web::ServiceConfig;
+
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(jwks::jwks);
+}
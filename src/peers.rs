@@ -0,0 +1,296 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::join_all;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, Validation};
+use serde::Serialize;
+
+use crate::{
+    models::cache_admin::CacheInvalidation, paths::pokemon::get_all::load_peer_snapshot, req_util,
+};
+
+pub static PEERS: OnceLock<Peers> = OnceLock::new();
+
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(30);
+const INVALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const PEER_TOKEN_ISSUER: &str = "pokemon-api-peer";
+/// How long a token minted for a request to a peer is valid, in seconds
+const PEER_TOKEN_TTL: u64 = 60;
+/// Tokens signed with the peer secret can contain only grants with this prefix
+pub const PEER_GRANT_PREFIX: &str = "svc::pokemon_api::peer::";
+const PEER_GRANTS: &[&str] = &[
+    "svc::pokemon_api::peer::route::/peer/snapshot",
+    "svc::pokemon_api::peer::route::/peer/cache/invalidate",
+];
+
+#[derive(Serialize)]
+struct PeerClaims {
+    iss: &'static str,
+    sub: &'static str,
+    grants: &'static [&'static str],
+    iat: u64,
+    nbf: u64,
+    exp: u64,
+}
+
+/// Validation of tokens minted by peers with the shared peer secret.
+pub fn peer_token_validation() -> Validation {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_required_spec_claims(&["iss", "exp", "nbf"]);
+    validation.set_issuer(&[PEER_TOKEN_ISSUER]);
+    validation
+}
+
+/// Other replicas of the service, the pokemon dataset is fetched from them and cache invalidations are sent to them.
+pub struct Peers {
+    /// Base urls of the replicas without trailing slash
+    urls: Vec<String>,
+    /// Key of the secret shared by the replicas, short-lived tokens with grants of the `/peer/*` routes are signed with it
+    encoding_key: EncodingKey,
+}
+
+impl Peers {
+    /// Parses comma separated base urls of the replicas, requests to them are authorized with tokens signed with `secret`.
+    pub fn new(urls: &str, secret: &[u8]) -> Result<Self, String> {
+        let urls = urls
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                reqwest::Url::parse(url)
+                    .map(|_| url.trim_end_matches('/').to_string())
+                    .map_err(|e| format!("Peer url '{url}' is invalid: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            urls,
+            encoding_key: EncodingKey::from_secret(secret),
+        })
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Mints a token with grants of the `/peer/*` routes, it's valid for [`PEER_TOKEN_TTL`] seconds.
+    fn token(&self) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let claims = PeerClaims {
+            iss: PEER_TOKEN_ISSUER,
+            sub: PEER_TOKEN_ISSUER,
+            grants: PEER_GRANTS,
+            iat: now,
+            nbf: now,
+            exp: now + PEER_TOKEN_TTL,
+        };
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+    }
+
+    fn request(
+        &self,
+        req_client: &reqwest::Client,
+        method: reqwest::Method,
+        url: &str,
+        path: &str,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let token = self.token().map_err(|e| e.to_string())?;
+        Ok(req_client
+            .request(method, format!("{url}{path}"))
+            .bearer_auth(token))
+    }
+
+    /// Loads the pokemon dataset from the first peer with snapshot created after `newer_than`.
+    ///
+    /// Returns the peer and when its snapshot was created.
+    pub async fn load_snapshot(
+        &self,
+        req_client: &reqwest::Client,
+        newer_than: u64,
+    ) -> Result<(&str, u64), String> {
+        for url in &self.urls {
+            let res = async {
+                let request = self
+                    .request(req_client, reqwest::Method::GET, url, "/peer/snapshot")?
+                    .timeout(SNAPSHOT_TIMEOUT);
+                let content = req_util::send_bytes(request)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                load_peer_snapshot(content, newer_than).await
+            }
+            .await;
+            match res {
+                Ok(created_at) => return Ok((url, created_at)),
+                Err(e) => tracing::warn!(
+                    "Loading of pokemon data snapshot from peer {} failed: {}",
+                    url,
+                    e
+                ),
+            }
+        }
+        Err("No peer has usable snapshot".into())
+    }
+
+    /// Invalidates the entry, or the whole namespace when `key` is missing, on all peers.
+    ///
+    /// Returns peers on which the invalidation failed.
+    pub async fn invalidate(
+        &self,
+        req_client: &reqwest::Client,
+        namespace: &str,
+        key: Option<&str>,
+    ) -> Vec<String> {
+        let mut query = vec![("namespace", namespace)];
+        if let Some(key) = key {
+            query.push(("key", key));
+        }
+
+        let requests = self.urls.iter().map(|url| {
+            let query = &query;
+            async move {
+                let res = async {
+                    let request = self
+                        .request(
+                            req_client,
+                            reqwest::Method::POST,
+                            url,
+                            "/peer/cache/invalidate",
+                        )?
+                        .query(query)
+                        .timeout(INVALIDATION_TIMEOUT);
+                    req_util::send_json::<CacheInvalidation>(request)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+                .await;
+                match res {
+                    Ok(invalidation) => {
+                        tracing::info!(
+                            "Invalidated {} entries of cache namespace {} on peer {}",
+                            invalidation.removed,
+                            namespace,
+                            url
+                        );
+                        None
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Invalidating of cache namespace {} on peer {} failed: {}",
+                            namespace,
+                            url,
+                            e
+                        );
+                        Some(url.clone())
+                    }
+                }
+            }
+        });
+        join_all(requests).await.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+    use actix_web_grants::authorities::AuthDetails;
+    use serde_json::json;
+
+    use super::*;
+    use crate::jwt_stuff::JwtGrantsMiddleware;
+
+    /// Returns status of the request with the token and grants it was authorized with.
+    async fn grants(token: &str) -> (u16, Vec<String>) {
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtGrantsMiddleware::test().peer_secret(b"secret"))
+                .route(
+                    "/",
+                    web::get().to(|auth_details: AuthDetails| async move {
+                        let mut grants =
+                            auth_details.authorities.iter().cloned().collect::<Vec<_>>();
+                        grants.sort();
+                        HttpResponse::Ok().json(grants)
+                    }),
+                ),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header(("authorization", format!("Bearer {token}")))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let status = res.status().as_u16();
+        if status != 200 {
+            return (status, Vec::new());
+        }
+        (status, test::read_body_json(res).await)
+    }
+
+    fn sign(claims: serde_json::Value, secret: &[u8]) -> String {
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn minted_tokens_grant_peer_routes_for_a_short_time() {
+        let peers = Peers::new("http://127.0.0.1:8081", b"secret").unwrap();
+        let token = peers.token().unwrap();
+        let (status, mut granted) = grants(&token).await;
+        assert_eq!(status, 200);
+        let mut expected = PEER_GRANTS.to_vec();
+        expected.sort();
+        granted.sort();
+        assert_eq!(granted, expected);
+
+        let claims = jsonwebtoken::decode::<serde_json::Value>(
+            &token,
+            &jsonwebtoken::DecodingKey::from_secret(b"secret"),
+            &peer_token_validation(),
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(
+            claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap(),
+            PEER_TOKEN_TTL
+        );
+
+        let other = Peers::new("http://127.0.0.1:8081", b"other").unwrap();
+        assert_eq!(grants(&other.token().unwrap()).await.0, 400);
+    }
+
+    #[actix_web::test]
+    async fn peer_secret_grants_nothing_but_peer_routes() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let token = sign(
+            json!({
+                "iss": PEER_TOKEN_ISSUER,
+                "grants": [PEER_GRANTS[0], "svc::pokemon_api::admin::route::/admin/cache/invalidate"],
+                "nbf": now,
+                "exp": now + 60,
+            }),
+            b"secret",
+        );
+        assert_eq!(
+            grants(&token).await,
+            (200, vec![PEER_GRANTS[0].to_string()])
+        );
+
+        let expired = sign(
+            json!({ "iss": PEER_TOKEN_ISSUER, "grants": PEER_GRANTS, "nbf": 1, "exp": 2 }),
+            b"secret",
+        );
+        assert_eq!(grants(&expired).await.0, 400);
+    }
+}
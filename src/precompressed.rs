@@ -0,0 +1,138 @@
+use std::io::{self, Write};
+
+use actix_web::{
+    http::header::{
+        AcceptEncoding, ContentEncoding, ContentType, Encoding, Header, CONTENT_ENCODING, VARY,
+    },
+    HttpRequest, HttpResponse,
+};
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+const ZSTD_LEVEL: i32 = 19;
+
+/// Encodings in the order they are preferred when the client accepts several of them equally.
+static SUPPORTED_ENCODINGS: &[Encoding] = &[
+    Encoding::brotli(),
+    Encoding::zstd(),
+    Encoding::gzip(),
+    Encoding::identity(),
+];
+
+/// JSON body compressed ahead of time with every supported encoding.
+///
+/// Compressing is done once with the highest levels, so serving it costs only a copy of the reference.
+pub struct PrecompressedJson {
+    identity: Bytes,
+    gzip: Bytes,
+    brotli: Bytes,
+    zstd: Bytes,
+}
+
+impl PrecompressedJson {
+    /// Compresses the body, it's slow so call it from a blocking task.
+    pub fn new(json: Vec<u8>) -> io::Result<Self> {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+        gzip.write_all(&json)?;
+        let gzip = gzip.finish()?;
+
+        let mut brotli = Vec::new();
+        {
+            let mut writer =
+                brotli::CompressorWriter::new(&mut brotli, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+            writer.write_all(&json)?;
+        }
+
+        let zstd = zstd::encode_all(json.as_slice(), ZSTD_LEVEL)?;
+
+        Ok(Self {
+            identity: json.into(),
+            gzip: gzip.into(),
+            brotli: brotli.into(),
+            zstd: zstd.into(),
+        })
+    }
+
+    /// Returns the body in the encoding preferred by the client.
+    ///
+    /// Response has `Content-Encoding` set, so the compress middleware leaves it as it is.
+    pub fn respond(&self, req: &HttpRequest) -> HttpResponse {
+        let encoding = AcceptEncoding::parse(req)
+            .ok()
+            .and_then(|accept| accept.negotiate(SUPPORTED_ENCODINGS.iter()));
+        let (encoding, body) = match encoding {
+            Some(Encoding::Known(ContentEncoding::Brotli)) => {
+                (ContentEncoding::Brotli, &self.brotli)
+            }
+            Some(Encoding::Known(ContentEncoding::Zstd)) => (ContentEncoding::Zstd, &self.zstd),
+            Some(Encoding::Known(ContentEncoding::Gzip)) => (ContentEncoding::Gzip, &self.gzip),
+            _ => (ContentEncoding::Identity, &self.identity),
+        };
+
+        HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .insert_header((CONTENT_ENCODING, encoding.to_header_value()))
+            .insert_header((VARY, "accept-encoding"))
+            .body(body.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use actix_web::{body::MessageBody, test::TestRequest};
+
+    use super::*;
+
+    fn respond(body: &PrecompressedJson, accept_encoding: Option<&str>) -> (String, Bytes) {
+        let mut req = TestRequest::default();
+        if let Some(accept_encoding) = accept_encoding {
+            req = req.insert_header(("accept-encoding", accept_encoding));
+        }
+        let res = body.respond(&req.to_http_request());
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+        let encoding = res.headers().get(CONTENT_ENCODING).unwrap();
+        let encoding = encoding.to_str().unwrap().to_string();
+        let Ok(bytes) = res.into_body().try_into_bytes() else {
+            panic!("body is in memory");
+        };
+        (encoding, bytes)
+    }
+
+    #[test]
+    fn preferred_encoding_is_served() {
+        let json = br#"{"name":"bulbasaur"}"#.repeat(100);
+        let body = PrecompressedJson::new(json.clone()).unwrap();
+
+        let (encoding, bytes) = respond(&body, None);
+        assert_eq!(encoding, "identity");
+        assert_eq!(bytes, json);
+
+        let (encoding, bytes) = respond(&body, Some("gzip, br;q=0.5"));
+        assert_eq!(encoding, "gzip");
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&bytes[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, json);
+
+        // brotli is preferred among equally accepted encodings
+        let (encoding, bytes) = respond(&body, Some("gzip, zstd, br"));
+        assert_eq!(encoding, "br");
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&bytes[..], 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, json);
+
+        let (encoding, bytes) = respond(&body, Some("zstd"));
+        assert_eq!(encoding, "zstd");
+        assert_eq!(zstd::decode_all(&bytes[..]).unwrap(), json);
+
+        let (encoding, _) = respond(&body, Some("deflate"));
+        assert_eq!(encoding, "identity");
+    }
+}
@@ -0,0 +1,425 @@
+use std::{fmt::Display, sync::OnceLock};
+
+use actix_web::{http::StatusCode, HttpResponse};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::{
+    req_util::response_from_error,
+    store::{Store, StoreError},
+};
+
+pub static PROVABLY_FAIR: OnceLock<ProvablyFair> = OnceLock::new();
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+pub struct ServerSeed {
+    seed: [u8; 32],
+    hash: String,
+}
+
+impl ServerSeed {
+    fn generate() -> Self {
+        Self::new(rand::thread_rng().gen::<[u8; 32]>())
+    }
+
+    fn new(seed: [u8; 32]) -> Self {
+        Self {
+            hash: hash_server_seed(&seed),
+            seed,
+        }
+    }
+
+    fn from_hex(seed: &str) -> Result<Self, String> {
+        let seed = hex::decode(seed)
+            .ok()
+            .and_then(|seed| <[u8; 32]>::try_from(seed).ok())
+            .ok_or_else(|| format!("Stored server seed '{seed}' is invalid"))?;
+        Ok(Self::new(seed))
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn seed_hex(&self) -> String {
+        hex::encode(self.seed)
+    }
+
+    pub fn draw_indices(
+        &self,
+        client_seed: &str,
+        nonce: u64,
+        count: usize,
+        len: usize,
+    ) -> Vec<usize> {
+        draw_indices(&self.seed, client_seed, nonce, count, len)
+    }
+}
+
+struct ProvablyFairState {
+    current: ServerSeed,
+    revealed: Vec<ServerSeed>,
+}
+
+/// Server seeds of fair draws, they are persisted in the store so committed seeds survive restarts.
+pub struct ProvablyFair {
+    store: &'static Store,
+    inner: RwLock<ProvablyFairState>,
+}
+
+impl ProvablyFair {
+    /// Loads the active and revealed server seeds, new active seed is generated when there is none.
+    pub async fn load(store: &'static Store) -> Result<Self, String> {
+        let (active, revealed) = store.server_seeds().await.map_err(|e| e.to_string())?;
+        let revealed = revealed
+            .iter()
+            .map(|seed| ServerSeed::from_hex(seed))
+            .collect::<Result<Vec<_>, _>>()?;
+        let current = match active {
+            Some(seed) => ServerSeed::from_hex(&seed)?,
+            None => {
+                let seed = ServerSeed::generate();
+                store
+                    .insert_server_seed(seed.hash.clone(), seed.seed_hex())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                seed
+            }
+        };
+
+        Ok(Self {
+            store,
+            inner: RwLock::new(ProvablyFairState { current, revealed }),
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn get() -> Result<&'static ProvablyFair, HttpResponse> {
+        PROVABLY_FAIR.get().ok_or_else(|| {
+            response_from_error(
+                "Provably fair draws are not available",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        })
+    }
+
+    /// Returns the currently active server seed, its hash is the published commitment.
+    pub async fn current(&self) -> ServerSeed {
+        self.inner.read().await.current.clone()
+    }
+
+    /// Returns the active server seed for a draw, it can't be rotated nor revealed until the guard is dropped.
+    ///
+    /// Hold the guard until the draw is committed.
+    pub async fn draw_lock(&self) -> RwLockReadGuard<'_, ServerSeed> {
+        RwLockReadGuard::map(self.inner.read().await, |state| &state.current)
+    }
+
+    /// Replaces the active server seed with a new one and reveals the old one.
+    /// Returns the revealed seed.
+    ///
+    /// Rotation waits for draws holding [`Self::draw_lock`] and new draws wait until it's stored,
+    /// so no draw is made nor committed with seed which is already revealed.
+    pub async fn rotate(&self) -> Result<ServerSeed, StoreError> {
+        let mut state = self.inner.write().await;
+        let next = ServerSeed::generate();
+        self.store
+            .rotate_server_seed(
+                state.current.hash.clone(),
+                Some((next.hash.clone(), next.seed_hex())),
+            )
+            .await?;
+        let old = std::mem::replace(&mut state.current, next);
+        state.revealed.push(old.clone());
+        Ok(old)
+    }
+
+    /// Reveals the active server seed without replacing it, called on shutdown.
+    ///
+    /// New active seed is generated on the next start.
+    pub async fn reveal_current(&self) -> Result<ServerSeed, StoreError> {
+        let mut state = self.inner.write().await;
+        let current = state.current.clone();
+        self.store
+            .rotate_server_seed(current.hash.clone(), None)
+            .await?;
+        state.revealed.push(current.clone());
+        Ok(current)
+    }
+
+    /// Looks up a revealed server seed by its commitment.
+    /// Seeds that are still active are never returned.
+    pub async fn revealed(&self, server_seed_hash: &str) -> Option<ServerSeed> {
+        self.inner
+            .read()
+            .await
+            .revealed
+            .iter()
+            .find(|seed| seed.hash == server_seed_hash)
+            .cloned()
+    }
+}
+
+pub fn hash_server_seed(server_seed: &[u8]) -> String {
+    hex::encode(Sha256::digest(server_seed))
+}
+
+/// Derives `count` indices into a list of `len` items from the server seed, client seed and nonce.
+///
+/// Every index is taken from digests `HMAC-SHA256(key = server_seed, message = "{client_seed}:{nonce}:{i}:{round}")`
+/// with `round` going from 0, each digest is read as four big endian `u64` numbers.
+/// Numbers lower than `2^64 % len` are rejected so every index is equally likely,
+/// the first accepted number modulo `len` is the index.
+pub fn draw_indices(
+    server_seed: &[u8],
+    client_seed: &str,
+    nonce: u64,
+    count: usize,
+    len: usize,
+) -> Vec<usize> {
+    let len = len as u64;
+    (0..count)
+        .map(|i| {
+            let rejected_below = len.wrapping_neg() % len;
+            (0u64..)
+                .flat_map(|round| {
+                    let mut mac = HmacSha256::new_from_slice(server_seed)
+                        .expect("HMAC can take key of any size");
+                    mac.update(format!("{client_seed}:{nonce}:{i}:{round}").as_bytes());
+                    let digest = mac.finalize().into_bytes();
+                    (0..4).map(move |chunk| {
+                        let mut number = [0; 8];
+                        number.copy_from_slice(&digest[chunk * 8..chunk * 8 + 8]);
+                        u64::from_be_bytes(number)
+                    })
+                })
+                .find(|number| *number >= rejected_below)
+                .map(|number| (number % len) as usize)
+                .expect("rounds never run out")
+        })
+        .collect()
+}
+
+/// Sorts draw candidates by pokemon name into the order draws are indexed in.
+pub fn sort_draw_candidates<T>(candidates: &mut [T], name: impl Fn(&T) -> &str) {
+    candidates.sort_unstable_by(|a, b| name(a).cmp(name(b)));
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    InvalidServerSeed,
+    CommitmentMismatch,
+    NoCandidates,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::InvalidServerSeed => write!(f, "Server seed is not hex encoded"),
+            VerifyError::CommitmentMismatch => {
+                write!(f, "Server seed doesn't match its commitment")
+            }
+            VerifyError::NoCandidates => write!(f, "No pokemons are available"),
+        }
+    }
+}
+
+/// Verifies a past draw offline.
+///
+/// `server_seed` is the hex encoded seed revealed by `/fair/reveal/{server_seed_hash}`,
+/// `names` are the names of all drawable pokemons (`/pokemon/get_all`) in any order.
+/// Returns the drawn pokemon names which can be compared against the draw result.
+pub fn verify_draw<'a>(
+    server_seed: &str,
+    server_seed_hash: &str,
+    client_seed: &str,
+    nonce: u64,
+    count: usize,
+    names: &[&'a str],
+) -> Result<Vec<&'a str>, VerifyError> {
+    let server_seed = hex::decode(server_seed).map_err(|_| VerifyError::InvalidServerSeed)?;
+    if hash_server_seed(&server_seed) != server_seed_hash.to_ascii_lowercase() {
+        return Err(VerifyError::CommitmentMismatch);
+    }
+    if names.is_empty() {
+        return Err(VerifyError::NoCandidates);
+    }
+
+    let mut names = names.to_vec();
+    sort_draw_candidates(&mut names, |name| name);
+    Ok(
+        draw_indices(&server_seed, client_seed, nonce, count, names.len())
+            .into_iter()
+            .map(|i| names[i])
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::open_temporary;
+
+    const SERVER_SEED: [u8; 32] = [7; 32];
+
+    fn digest_numbers(client_seed: &str, nonce: u64, i: usize, round: u64) -> Vec<u64> {
+        let mut mac = HmacSha256::new_from_slice(&SERVER_SEED).unwrap();
+        mac.update(format!("{client_seed}:{nonce}:{i}:{round}").as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .chunks(8)
+            .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn draw_indices_follow_documented_derivation() {
+        // nothing is rejected when the length divides 2^64
+        let indices = draw_indices(&SERVER_SEED, "client", 3, 5, 1024);
+        let expected = (0..5)
+            .map(|i| (digest_numbers("client", 3, i, 0)[0] % 1024) as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, expected);
+    }
+
+    #[test]
+    fn draw_indices_reject_biased_numbers() {
+        // almost half of the numbers are rejected for this length
+        let len = (1u64 << 63) + 1;
+        let rejected_below = (1u64 << 63) - 1;
+        let indices = draw_indices(&SERVER_SEED, "client", 0, 64, len as usize);
+        for (i, index) in indices.into_iter().enumerate() {
+            let expected = (0..)
+                .flat_map(|round| digest_numbers("client", 0, i, round))
+                .find(|number| *number >= rejected_below)
+                .unwrap();
+            assert_eq!(index as u64, expected % len);
+        }
+    }
+
+    #[test]
+    fn draw_indices_are_deterministic_and_in_range() {
+        let indices = draw_indices(&SERVER_SEED, "client", 1, 200, 151);
+        assert_eq!(indices, draw_indices(&SERVER_SEED, "client", 1, 200, 151));
+        assert_ne!(indices, draw_indices(&SERVER_SEED, "client", 2, 200, 151));
+        assert!(indices.iter().all(|index| *index < 151));
+        assert!(draw_indices(&SERVER_SEED, "client", 1, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn verify_draw_replays_draw() {
+        let names = ["pikachu", "bulbasaur", "eevee", "abra", "zubat"];
+        let mut sorted = names;
+        sorted.sort_unstable();
+        let server_seed = ServerSeed::new(SERVER_SEED);
+        let expected = server_seed
+            .draw_indices("client", 9, 6, sorted.len())
+            .into_iter()
+            .map(|i| sorted[i])
+            .collect::<Vec<_>>();
+
+        let drawn = verify_draw(
+            &server_seed.seed_hex(),
+            &server_seed.hash().to_ascii_uppercase(),
+            "client",
+            9,
+            6,
+            &names,
+        )
+        .unwrap();
+        assert_eq!(drawn, expected);
+    }
+
+    #[test]
+    fn verify_draw_rejects_invalid_input() {
+        let server_seed = ServerSeed::new(SERVER_SEED);
+        let other = ServerSeed::new([8; 32]);
+        let names = ["pikachu"];
+        assert_eq!(
+            verify_draw("not hex", server_seed.hash(), "client", 0, 1, &names),
+            Err(VerifyError::InvalidServerSeed)
+        );
+        assert_eq!(
+            verify_draw(
+                &server_seed.seed_hex(),
+                other.hash(),
+                "client",
+                0,
+                1,
+                &names
+            ),
+            Err(VerifyError::CommitmentMismatch)
+        );
+        assert_eq!(
+            verify_draw(
+                &server_seed.seed_hex(),
+                server_seed.hash(),
+                "client",
+                0,
+                1,
+                &[]
+            ),
+            Err(VerifyError::NoCandidates)
+        );
+    }
+
+    #[tokio::test]
+    async fn server_seeds_survive_restart() {
+        let store = open_temporary();
+        let provably_fair = ProvablyFair::load(store).await.unwrap();
+        let committed = provably_fair.current().await;
+        assert!(provably_fair.revealed(committed.hash()).await.is_none());
+
+        let restarted = ProvablyFair::load(store).await.unwrap();
+        assert_eq!(restarted.current().await.hash(), committed.hash());
+
+        let revealed = restarted.rotate().await.unwrap();
+        assert_eq!(revealed.hash(), committed.hash());
+        let next = restarted.current().await;
+        assert_ne!(next.hash(), committed.hash());
+
+        let restarted = ProvablyFair::load(store).await.unwrap();
+        assert_eq!(restarted.current().await.hash(), next.hash());
+        let found = restarted.revealed(committed.hash()).await.unwrap();
+        assert_eq!(found.seed_hex(), committed.seed_hex());
+    }
+
+    #[tokio::test]
+    async fn active_seed_is_revealed_on_shutdown() {
+        let store = open_temporary();
+        let provably_fair = ProvablyFair::load(store).await.unwrap();
+        let committed = provably_fair.reveal_current().await.unwrap();
+
+        let restarted = ProvablyFair::load(store).await.unwrap();
+        assert_ne!(restarted.current().await.hash(), committed.hash());
+        assert!(restarted.revealed(committed.hash()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn rotation_waits_for_draws() {
+        let provably_fair = ProvablyFair::load(open_temporary()).await.unwrap();
+        let draw = provably_fair.draw_lock().await;
+        let committed = draw.hash().to_string();
+
+        let rotation = provably_fair.rotate();
+        tokio::pin!(rotation);
+        let waiting =
+            tokio::time::timeout(std::time::Duration::from_millis(50), &mut rotation).await;
+        assert!(waiting.is_err());
+        // new draws wait for the pending rotation
+        let next_draw = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            provably_fair.draw_lock(),
+        )
+        .await;
+        assert!(next_draw.is_err());
+
+        drop(draw);
+        let revealed = rotation.await.unwrap();
+        assert_eq!(revealed.hash(), committed);
+    }
+}
@@ -1,2 +1,4 @@
 pub const GET_ALL_POKEMONS: &str = include_str!("./get_all_pokemons.graphql");
 pub const GET_POKEMON: &str = include_str!("./get_pokemon.graphql");
+pub const GET_POKEMON_SPECIES_NAMES: &str = include_str!("./get_pokemon_species_names.graphql");
+pub const GET_TYPE_EFFICACIES: &str = include_str!("./get_type_efficacies.graphql");
@@ -0,0 +1,266 @@
+use std::{
+    io::Cursor,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use image::{
+    imageops::{self, FilterType},
+    ImageFormat, Rgba, RgbaImage,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::provably_fair::draw_indices;
+
+pub const CHALLENGE_ISSUER: &str = "pokemon-api-quiz";
+/// How long a challenge can be answered, in seconds
+pub const CHALLENGE_TTL: u64 = 5 * 60;
+
+/// Largest transparent margin added to each side of a disguised silhouette, in pixels
+const MAX_DISGUISE_MARGIN: u32 = 8;
+/// Range of scales of disguised silhouettes, in percent of the artwork size
+const DISGUISE_SCALE_PERCENT: std::ops::RangeInclusive<u32> = 75..=125;
+/// Lightest grey of the noise filling disguised silhouettes
+const MAX_DISGUISE_NOISE: u8 = 64;
+
+pub static QUIZ_CONFIG: OnceLock<QuizConfig> = OnceLock::new();
+
+#[derive(Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub iss: String,
+    /// Random id the pokemon is derived from
+    pub jti: String,
+    pub iat: u64,
+    pub exp: u64,
+    pub dataset_version: String,
+}
+
+/// Signs and verifies quiz challenges.
+///
+/// Challenge token contains only a random id, the pokemon is derived from the id and the secret
+/// so the token doesn't reveal the answer and any replica with the same secret can verify answers.
+pub struct QuizConfig {
+    secret: Vec<u8>,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+impl QuizConfig {
+    pub fn new(secret: Vec<u8>) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["iss", "exp", "jti"]);
+        validation.set_issuer(&[CHALLENGE_ISSUER]);
+        validation.leeway = 0;
+        Self {
+            encoding_key: EncodingKey::from_secret(&secret),
+            decoding_key: DecodingKey::from_secret(&secret),
+            validation,
+            secret,
+        }
+    }
+
+    pub fn get() -> &'static QuizConfig {
+        QUIZ_CONFIG.get_or_init(|| Self::new(rand::thread_rng().gen::<[u8; 32]>().to_vec()))
+    }
+
+    /// Creates new challenge for the dataset, returns the token and its claims.
+    pub fn new_challenge(
+        &self,
+        dataset_version: &str,
+    ) -> Result<(String, ChallengeClaims), jsonwebtoken::errors::Error> {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let claims = ChallengeClaims {
+            iss: CHALLENGE_ISSUER.to_string(),
+            jti: hex::encode(rand::thread_rng().gen::<[u8; 16]>()),
+            iat,
+            exp: iat + CHALLENGE_TTL,
+            dataset_version: dataset_version.to_string(),
+        };
+        let token =
+            jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)?;
+        Ok((token, claims))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<ChallengeClaims, jsonwebtoken::errors::Error> {
+        jsonwebtoken::decode(token, &self.decoding_key, &self.validation).map(|data| data.claims)
+    }
+
+    /// Picks index of the challenge pokemon from `len` pokemons sorted by name.
+    pub fn pick_index(&self, challenge_id: &str, len: usize) -> usize {
+        draw_indices(&self.secret, challenge_id, 0, 1, len)[0]
+    }
+}
+
+/// Turns official artwork into a black silhouette keeping its transparency.
+pub fn silhouette(artwork: &[u8]) -> Result<Vec<u8>, image::ImageError> {
+    let mut image = image::load_from_memory(artwork)?.into_rgba8();
+    for pixel in image.pixels_mut() {
+        *pixel = Rgba([0, 0, 0, pixel[3]]);
+    }
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Randomly scales and mirrors the silhouette, places it at a random offset on a larger canvas and fills it with grey noise,
+/// so silhouettes of the same pokemon differ between challenges and don't match the silhouette of the artwork.
+pub fn disguise(silhouette: &[u8], rng: &mut impl Rng) -> Result<Vec<u8>, image::ImageError> {
+    let image = image::load_from_memory(silhouette)?.into_rgba8();
+    let scale = rng.gen_range(DISGUISE_SCALE_PERCENT);
+    let mut image = imageops::resize(
+        &image,
+        (image.width() * scale / 100).max(1),
+        (image.height() * scale / 100).max(1),
+        FilterType::Triangle,
+    );
+    if rng.gen() {
+        imageops::flip_horizontal_in_place(&mut image);
+    }
+
+    let mut margin = || rng.gen_range(0..=MAX_DISGUISE_MARGIN);
+    let (left, top) = (margin(), margin());
+    let width = image.width() + left + margin();
+    let height = image.height() + top + margin();
+
+    let mut canvas = RgbaImage::new(width, height);
+    imageops::replace(&mut canvas, &image, left as i64, top as i64);
+    for pixel in canvas.pixels_mut() {
+        if pixel[3] > 0 {
+            let grey = rng.gen_range(0..=MAX_DISGUISE_NOISE);
+            *pixel = Rgba([grey, grey, grey, pixel[3]]);
+        }
+    }
+    let mut png = Vec::new();
+    canvas.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn artwork() -> Vec<u8> {
+        let image = RgbaImage::from_fn(4, 3, |x, y| {
+            if x == 0 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([200, 100, 50 * y as u8, 255])
+            }
+        });
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn challenge_is_verified_only_with_the_secret() {
+        let config = QuizConfig::new(b"secret".to_vec());
+        let (token, claims) = config.new_challenge("v1").unwrap();
+        let verified = config.verify(&token).unwrap();
+        assert_eq!(verified.jti, claims.jti);
+        assert_eq!(verified.dataset_version, "v1");
+        assert_eq!(verified.exp, verified.iat + CHALLENGE_TTL);
+
+        let other = QuizConfig::new(b"other".to_vec());
+        assert!(other.verify(&token).is_err());
+        let (other_token, _) = other.new_challenge("v1").unwrap();
+        assert_ne!(other_token, token);
+    }
+
+    #[test]
+    fn expired_challenge_is_rejected() {
+        let config = QuizConfig::new(b"secret".to_vec());
+        let claims = ChallengeClaims {
+            iss: CHALLENGE_ISSUER.into(),
+            jti: "id".into(),
+            iat: 1,
+            exp: 2,
+            dataset_version: "v1".into(),
+        };
+        let token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &config.encoding_key,
+        )
+        .unwrap();
+        let e = config.verify(&token).err().unwrap();
+        assert!(matches!(
+            e.kind(),
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature
+        ));
+    }
+
+    #[test]
+    fn challenge_pokemon_depends_on_id_and_secret() {
+        let config = QuizConfig::new(b"secret".to_vec());
+        assert_eq!(config.pick_index("id", 1000), config.pick_index("id", 1000));
+        let picks = (0..20)
+            .map(|i| config.pick_index(&i.to_string(), 1000))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(picks.len() > 1);
+        assert!(picks.iter().all(|index| *index < 1000));
+    }
+
+    #[test]
+    fn silhouette_is_black_with_original_transparency() {
+        let png = silhouette(&artwork()).unwrap();
+        let image = image::load_from_memory(&png).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(*image.get_pixel(1, 2), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn disguised_silhouettes_are_transformed() {
+        let artwork = RgbaImage::from_fn(40, 30, |x, _| {
+            if x < 10 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([200, 100, 50, 255])
+            }
+        });
+        let mut png = Vec::new();
+        artwork
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        let png = silhouette(&png).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let disguised = (0..20)
+            .map(|_| {
+                let png = disguise(&png, &mut rng).unwrap();
+                image::load_from_memory(&png).unwrap().into_rgba8()
+            })
+            .collect::<Vec<_>>();
+
+        let widths = disguised
+            .iter()
+            .map(|image| image.width())
+            .collect::<std::collections::HashSet<_>>();
+        assert!(widths.len() > 1);
+        for image in &disguised {
+            assert!((30..=50 + 2 * MAX_DISGUISE_MARGIN).contains(&image.width()));
+            assert!((22..=37 + 2 * MAX_DISGUISE_MARGIN).contains(&image.height()));
+            let visible = image
+                .pixels()
+                .filter(|pixel| pixel[3] > 0)
+                .collect::<Vec<_>>();
+            assert!(visible.len() >= 22 * 22);
+            assert!(visible
+                .iter()
+                .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]));
+            assert!(visible.iter().all(|pixel| pixel[0] <= MAX_DISGUISE_NOISE));
+            assert!(visible.iter().any(|pixel| pixel[0] > 0));
+        }
+    }
+}
@@ -1,42 +1,67 @@
+use std::hash::Hash;
+
 use actix_web::Either;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
-    cache::{RefVal, CACHE},
+    cache::{default_ttl, CacheNamespace, RefVal, STALE_RETRY_AFTER},
+    metrics,
     req_util::handle_request,
+    upstream::UpstreamError,
 };
 
-pub async fn handle_cache_request<D: Serialize, T: DeserializeOwned + Send + Sync + 'static, E>(
+pub async fn handle_cache_request<D, K, T, E>(
     req_client: &reqwest::Client,
     method: reqwest::Method,
-    cache_key: String,
-    url: &str,
+    namespace: &CacheNamespace<K, T>,
+    cache_key: K,
     data: Option<&D>,
-    on_error: impl Fn(reqwest::Error) -> E,
-) -> Result<RefVal<T>, E> {
-    let entry = CACHE.entry::<T>(cache_key).await;
+    on_error: impl Fn(UpstreamError) -> E,
+) -> Result<RefVal<T>, E>
+where
+    D: Serialize,
+    K: Hash + Eq + Clone,
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    let entry = namespace.entry(cache_key);
     let mut data_lock = match entry.get_or_write_lock().await {
         Either::Left(data) => return Ok(data),
         Either::Right(write_lock) => write_lock,
     };
 
-    let data = handle_request(req_client, method, url, data, on_error).await?;
-    data_lock.set(data);
-    drop(data_lock);
-    Ok(RefVal(entry.read().await))
+    match handle_request(req_client, method, data, on_error).await {
+        Ok(data) => Ok(data_lock.set_with_ttl(data, default_ttl())),
+        // keeps serving the expired value while the remote api is failing
+        Err(e) => match data_lock.set_ttl(Some(STALE_RETRY_AFTER)) {
+            Some(stale) => {
+                tracing::warn!(
+                    "Refreshing of cached {} failed, serving stale data",
+                    namespace.name()
+                );
+                metrics::CACHE_STALE_SERVED.inc([namespace.name()]);
+                Ok(stale)
+            }
+            None => Err(e),
+        },
+    }
 }
 
 #[allow(dead_code)]
-pub async fn get_json_cached<T: DeserializeOwned + Send + Sync + 'static, E>(
+pub async fn get_json_cached<K, T, E>(
     req_client: &reqwest::Client,
-    url: &str,
-    on_error: impl Fn(reqwest::Error) -> E,
-) -> Result<RefVal<T>, E> {
+    namespace: &CacheNamespace<K, T>,
+    cache_key: K,
+    on_error: impl Fn(UpstreamError) -> E,
+) -> Result<RefVal<T>, E>
+where
+    K: Hash + Eq + Clone,
+    T: DeserializeOwned + Send + Sync + 'static,
+{
     handle_cache_request(
         req_client,
-        reqwest::Method::POST,
-        url.to_string(),
-        url,
+        reqwest::Method::GET,
+        namespace,
+        cache_key,
         Option::<&()>::None,
         on_error,
     )
@@ -44,18 +69,22 @@ pub async fn get_json_cached<T: DeserializeOwned + Send + Sync + 'static, E>(
 }
 
 #[allow(dead_code)]
-pub async fn post_json_cached<T: DeserializeOwned + Send + Sync + 'static, E>(
+pub async fn post_json_cached<K, T, E>(
     req_client: &reqwest::Client,
-    cache_key: impl Into<String>,
-    url: &str,
+    namespace: &CacheNamespace<K, T>,
+    cache_key: K,
     data: &impl Serialize,
-    on_error: impl Fn(reqwest::Error) -> E,
-) -> Result<RefVal<T>, E> {
+    on_error: impl Fn(UpstreamError) -> E,
+) -> Result<RefVal<T>, E>
+where
+    K: Hash + Eq + Clone,
+    T: DeserializeOwned + Send + Sync + 'static,
+{
     handle_cache_request(
         req_client,
         reqwest::Method::POST,
-        cache_key.into(),
-        url,
+        namespace,
+        cache_key,
         Some(data),
         on_error,
     )
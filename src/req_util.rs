@@ -1,56 +1,112 @@
 use std::fmt::Debug;
 
-use crate::{empty_error::EmptyError, json_error::JsonError, IS_DEBUG_ON};
+use crate::{
+    empty_error::EmptyError,
+    json_error::JsonError,
+    upstream::{upstream, UpstreamError},
+    IS_DEBUG_ON,
+};
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+/// Sends the request to the remote api, endpoints are failed over when it fails.
 pub async fn handle_request<D: Serialize, T: DeserializeOwned + Send + Sync + 'static, E>(
     req_client: &reqwest::Client,
     method: reqwest::Method,
-    url: &str,
     data: Option<&D>,
-    on_error: impl Fn(reqwest::Error) -> E,
+    on_error: impl Fn(UpstreamError) -> E,
 ) -> Result<T, E> {
-    let mut request = req_client.request(method, url);
-    if let Some(data) = data {
-        request = request.json(data);
-    }
-    let response = request.send().await;
-    match response.and_then(reqwest::Response::error_for_status) {
-        Ok(res) => match res.json::<T>().await {
-            Ok(data) => Ok(data),
-            Err(e) => Err((on_error)(e)),
-        },
-        Err(e) => Err((on_error)(e)),
-    }
+    upstream()
+        .send(req_client, method, |mut request| {
+            if let Some(data) = data {
+                request = request.json(data);
+            }
+            send_graphql(request)
+        })
+        .await
+        .map_err(on_error)
 }
 
 #[allow(dead_code)]
 pub async fn post_json<T: DeserializeOwned + Send + Sync + 'static, E>(
     req_client: &reqwest::Client,
-    url: &str,
     data: &impl Serialize,
-    on_error: impl Fn(reqwest::Error) -> E,
+    on_error: impl Fn(UpstreamError) -> E,
 ) -> Result<T, E> {
-    handle_request(req_client, reqwest::Method::POST, url, Some(data), on_error).await
+    handle_request(req_client, reqwest::Method::POST, Some(data), on_error).await
 }
 
 #[allow(dead_code)]
 pub async fn get_json<T: DeserializeOwned + Send + Sync + 'static, E>(
     req_client: &reqwest::Client,
-    url: &str,
-    on_error: impl Fn(reqwest::Error) -> E,
+    on_error: impl Fn(UpstreamError) -> E,
 ) -> Result<T, E> {
     handle_request(
         req_client,
         reqwest::Method::GET,
-        url,
         Option::<&()>::None,
         on_error,
     )
     .await
 }
 
+/// Sends the request and deserializes its JSON body, error statuses are turned into errors.
+pub async fn send_json<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, reqwest::Error> {
+    let response = request.send().await;
+    match response.and_then(reqwest::Response::error_for_status) {
+        Ok(res) => res.json::<T>().await,
+        Err(e) => Err(e),
+    }
+}
+
+/// Sends the request and returns its body, error statuses are turned into errors.
+pub async fn send_bytes(request: reqwest::RequestBuilder) -> Result<Vec<u8>, reqwest::Error> {
+    let response = request.send().await;
+    match response.and_then(reqwest::Response::error_for_status) {
+        Ok(res) => res.bytes().await.map(|bytes| bytes.to_vec()),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphQlErrors {
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Sends the GraphQL request and returns its body, responses with GraphQL errors are turned into errors.
+pub async fn send_graphql_bytes(
+    request: reqwest::RequestBuilder,
+) -> Result<Vec<u8>, UpstreamError> {
+    let body = send_bytes(request).await?;
+    let errors = serde_json::from_slice::<GraphQlErrors>(&body)
+        .map_err(|e| UpstreamError::Invalid(e.to_string()))?
+        .errors;
+    if !errors.is_empty() {
+        let messages = errors
+            .into_iter()
+            .map(|error| error.message)
+            .collect::<Vec<_>>();
+        return Err(UpstreamError::GraphQl(messages.join("; ")));
+    }
+    Ok(body)
+}
+
+/// Sends the GraphQL request and deserializes its JSON body, responses with GraphQL errors are turned into errors.
+pub async fn send_graphql<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, UpstreamError> {
+    let body = send_graphql_bytes(request).await?;
+    serde_json::from_slice(&body).map_err(|e| UpstreamError::Invalid(e.to_string()))
+}
+
 pub fn response_from_error(error: impl Serialize + Debug, status_code: StatusCode) -> HttpResponse {
     if unsafe { IS_DEBUG_ON } {
         JsonError::new(error, status_code).error_response()
@@ -0,0 +1,184 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+pub static SNAPSHOT_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+const MAGIC: &[u8; 8] = b"PKAPISNP";
+pub const FORMAT_VERSION: u32 = 1;
+const HEADER_SIZE: usize = MAGIC.len() + 4 + 8 + 8 + 32;
+
+/// Snapshot of the pokemon dataset as it was returned by the remote api.
+pub struct Snapshot {
+    /// Unix timestamp of the snapshot creation
+    pub created_at: u64,
+    pub payload: Vec<u8>,
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Current unix timestamp, snapshots of freshly fetched data are created at it.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Format is the magic, format version, creation timestamp, payload length, SHA-256 of the payload and the payload,
+/// all numbers are little endian.
+fn header(payload: &[u8], created_at: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&created_at.to_le_bytes());
+    header.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    header.extend_from_slice(&Sha256::digest(payload));
+    header
+}
+
+/// Encodes the snapshot the same way it's written.
+pub fn encode(payload: &[u8], created_at: u64) -> Vec<u8> {
+    let mut content = header(payload, created_at);
+    content.extend_from_slice(payload);
+    content
+}
+
+/// Durably writes the snapshot, the previous snapshot is replaced only after the new one is completely written.
+pub fn write(path: &Path, payload: &[u8], created_at: u64) -> io::Result<()> {
+    write_parts(path, &[&header(payload, created_at), payload])
+}
+
+/// Durably writes snapshot which is already encoded, e.g. the one received from a peer replica.
+///
+/// Content is checked before it replaces the previous snapshot.
+pub fn write_encoded(path: &Path, content: &[u8]) -> io::Result<()> {
+    decode(content)?;
+    write_parts(path, &[content])
+}
+
+fn write_parts(path: &Path, parts: &[&[u8]]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut file = File::create(&tmp_path)?;
+    for part in parts {
+        file.write_all(part)?;
+    }
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Reads the snapshot, fails when it's of unknown format version, truncated or corrupted.
+pub fn read(path: &Path) -> io::Result<Snapshot> {
+    decode(&read_encoded(path)?)
+}
+
+/// Reads the snapshot as it's stored without checking it.
+pub fn read_encoded(path: &Path) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    File::open(path)?.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Decodes the snapshot, fails when it's of unknown format version, truncated or corrupted.
+pub fn decode(content: &[u8]) -> io::Result<Snapshot> {
+    if content.len() < HEADER_SIZE {
+        return Err(invalid_data("Snapshot is truncated"));
+    }
+    let (header, payload) = content.split_at(HEADER_SIZE);
+    let (magic, header) = header.split_at(MAGIC.len());
+    let (version, header) = header.split_at(4);
+    let (created_at, header) = header.split_at(8);
+    let (len, checksum) = header.split_at(8);
+
+    if magic != MAGIC {
+        return Err(invalid_data("File is not a snapshot"));
+    }
+    let version = u32::from_le_bytes(version.try_into().unwrap_or_default());
+    if version != FORMAT_VERSION {
+        return Err(invalid_data(format!(
+            "Snapshot format version {version} is not supported"
+        )));
+    }
+    let len = u64::from_le_bytes(len.try_into().unwrap_or_default());
+    if len != payload.len() as u64 {
+        return Err(invalid_data("Snapshot is truncated"));
+    }
+    if Sha256::digest(payload).as_slice() != checksum {
+        return Err(invalid_data("Snapshot checksum doesn't match"));
+    }
+
+    Ok(Snapshot {
+        created_at: u64::from_le_bytes(created_at.try_into().unwrap_or_default()),
+        payload: payload.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn written_snapshot_is_read_back() {
+        let dir = TempDir::new();
+        let path = dir.join("pokemon.snapshot");
+        let created_at = unix_now();
+        write(&path, b"{\"data\":{}}", created_at).unwrap();
+        let snapshot = read(&path).unwrap();
+        assert_eq!(snapshot.payload, b"{\"data\":{}}");
+        assert_eq!(snapshot.created_at, created_at);
+
+        let encoded = read_encoded(&path).unwrap();
+        assert_eq!(encoded.len(), HEADER_SIZE + 11);
+        assert_eq!(&encoded[..MAGIC.len()], MAGIC);
+        assert_eq!(encoded, encode(b"{\"data\":{}}", created_at));
+        assert!(!path.with_extension("snapshot.tmp").exists());
+    }
+
+    #[test]
+    fn damaged_snapshots_are_rejected() {
+        let dir = TempDir::new();
+        let path = dir.join("pokemon.snapshot");
+        write(&path, b"payload", unix_now()).unwrap();
+        let encoded = read_encoded(&path).unwrap();
+
+        let mut corrupted = encoded.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(decode(&corrupted).is_err());
+        assert!(decode(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode(&encoded[..HEADER_SIZE - 1]).is_err());
+
+        let mut other_version = encoded.clone();
+        other_version[MAGIC.len()] += 1;
+        let e = decode(&other_version).err().unwrap();
+        assert!(e.to_string().contains("version 2"), "{e}");
+
+        let mut other_magic = encoded;
+        other_magic[0] = b'X';
+        assert!(decode(&other_magic).is_err());
+    }
+
+    #[test]
+    fn invalid_encoded_snapshot_keeps_the_previous_one() {
+        let dir = TempDir::new();
+        let path = dir.join("pokemon.snapshot");
+        write(&path, b"previous", unix_now()).unwrap();
+        assert!(write_encoded(&path, b"garbage").is_err());
+        assert_eq!(read(&path).unwrap().payload, b"previous");
+
+        let other = dir.join("other.snapshot");
+        write(&other, b"next", unix_now()).unwrap();
+        write_encoded(&path, &read_encoded(&other).unwrap()).unwrap();
+        assert_eq!(read(&path).unwrap().payload, b"next");
+    }
+}
@@ -0,0 +1,220 @@
+use rusqlite::params;
+
+use super::{Store, StoreError};
+use crate::{
+    draws::{Draw, DrawnPokemon},
+    leaderboards::rarity_score,
+};
+
+pub struct OwnedPokemonRow {
+    pub id: i64,
+    pub draw_id: String,
+    pub pokemon_name: String,
+    pub shiny: bool,
+    pub data: String,
+    pub obtained_at: u64,
+}
+
+/// Drawn pokemon serialized for the store.
+pub struct RecordedPokemon {
+    pub name: String,
+    pub shiny: bool,
+    pub data: String,
+    pub generation: Option<u8>,
+    pub rarity_score: u32,
+}
+
+impl RecordedPokemon {
+    pub fn from_drawn<T: DrawnPokemon>(pokemons: &[T]) -> Result<Vec<Self>, StoreError> {
+        pokemons
+            .iter()
+            .map(|pokemon| {
+                serde_json::to_string(pokemon).map(|data| Self {
+                    name: pokemon.name().to_string(),
+                    shiny: pokemon.shiny(),
+                    data,
+                    generation: pokemon.api_pokemon().generation(),
+                    rarity_score: rarity_score(pokemon.api_pokemon(), pokemon.shiny()),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::Serialize)
+    }
+}
+
+impl Store {
+    pub async fn owned_pokemons(&self, sub: String) -> Result<Vec<OwnedPokemonRow>, StoreError> {
+        self.run(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, draw_id, pokemon_name, shiny, data, obtained_at FROM owned_pokemons WHERE sub = ?1 ORDER BY id",
+            )?;
+            let rows = stmt.query_map([&sub], |row| {
+                Ok(OwnedPokemonRow {
+                    id: row.get(0)?,
+                    draw_id: row.get(1)?,
+                    pokemon_name: row.get(2)?,
+                    shiny: row.get(3)?,
+                    data: row.get(4)?,
+                    obtained_at: row.get(5)?,
+                })
+            })?;
+            rows.collect()
+        })
+        .await
+    }
+}
+
+/// Records drawn pokemons as owned by `sub`.
+pub(super) fn record_draw_tx(
+    tx: &rusqlite::Transaction,
+    sub: &str,
+    draw: &Draw,
+    pokemons: &[RecordedPokemon],
+) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO draws (id, sub, drawn_at) VALUES (?1, ?2, ?3)",
+        params![draw.id, sub, draw.timestamp],
+    )?;
+    let mut stmt = tx.prepare_cached(
+        "INSERT INTO owned_pokemons (sub, draw_id, pokemon_name, shiny, data, obtained_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for pokemon in pokemons {
+        stmt.execute(params![
+            sub,
+            draw.id,
+            pokemon.name,
+            pokemon.shiny,
+            pokemon.data,
+            draw.timestamp
+        ])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+impl RecordedPokemon {
+    /// Recorded pokemon generated from the test data.
+    pub fn test(name: &str, shiny: bool) -> Self {
+        let api_pokemon = crate::models::remote_api::test_data::pokemon(name);
+        let mut pokemon = crate::models::pokemon_instance::PokemonInstance::generate(
+            &api_pokemon,
+            1..=100,
+            &mut rand::thread_rng(),
+        )
+        .expect("test pokemon is complete");
+        pokemon.shiny = shiny;
+        Self::from_drawn(&[pokemon])
+            .expect("test pokemon serializes")
+            .remove(0)
+    }
+}
+
+#[cfg(test)]
+impl Store {
+    /// Records the draw outside of the ledger, returns ids of the owned pokemons.
+    pub async fn record_test_draw(
+        &self,
+        sub: &str,
+        draw: &Draw,
+        pokemons: Vec<RecordedPokemon>,
+    ) -> Vec<i64> {
+        let (sub, draw) = (sub.to_string(), draw.clone());
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            record_draw_tx(&tx, &sub, &draw, &pokemons)?;
+            let ids = tx
+                .prepare("SELECT id FROM owned_pokemons WHERE draw_id = ?1 ORDER BY id")?
+                .query_map([&draw.id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            tx.commit()?;
+            Ok(ids)
+        })
+        .await
+        .expect("test draw is recorded")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::pokemon::Pokemon, models::remote_api::test_data, store};
+
+    #[test]
+    fn drawn_pokemons_are_serialized() {
+        let api_pokemon = test_data::pokemon("garchomp");
+        let pokemon = Pokemon::try_from(&api_pokemon).unwrap();
+        let recorded = RecordedPokemon::from_drawn(&[pokemon]).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].name, "garchomp");
+        assert!(!recorded[0].shiny);
+        assert_eq!(recorded[0].generation, Some(4));
+        let data: serde_json::Value = serde_json::from_str(&recorded[0].data).unwrap();
+        assert_eq!(data["name"], "garchomp");
+    }
+
+    #[tokio::test]
+    async fn owned_pokemons_are_listed_per_sub_in_draw_order() {
+        let store = store::open_temporary();
+        let first = Draw::generate();
+        let second = Draw::generate();
+        store
+            .record_test_draw(
+                "ash",
+                &first,
+                vec![
+                    RecordedPokemon::test("pikachu", true),
+                    RecordedPokemon::test("eevee", false),
+                ],
+            )
+            .await;
+        store
+            .record_test_draw("gary", &second, vec![RecordedPokemon::test("eevee", false)])
+            .await;
+        store
+            .record_test_draw(
+                "ash",
+                &Draw::generate(),
+                vec![RecordedPokemon::test("onix", false)],
+            )
+            .await;
+
+        let owned = store.owned_pokemons("ash".into()).await.unwrap();
+        let names = owned
+            .iter()
+            .map(|row| row.pokemon_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["pikachu", "eevee", "onix"]);
+        assert!(owned[0].shiny);
+        assert!(!owned[1].shiny);
+        assert_eq!(owned[0].draw_id, first.id);
+        assert_eq!(owned[0].obtained_at, first.timestamp);
+
+        let owned = store.owned_pokemons("gary".into()).await.unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].draw_id, second.id);
+        assert!(store
+            .owned_pokemons("misty".into())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn draw_is_recorded_only_once() {
+        let store = store::open_temporary();
+        let draw = Draw::generate();
+        store
+            .record_test_draw("ash", &draw, vec![RecordedPokemon::test("pikachu", false)])
+            .await;
+        let (sub, again) = ("ash".to_string(), draw.clone());
+        let res = store
+            .run(move |conn| {
+                let tx = conn.transaction()?;
+                record_draw_tx(&tx, &sub, &again, &[RecordedPokemon::test("eevee", false)])?;
+                tx.commit()
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(store.owned_pokemons("ash".into()).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Transaction};
+
+use super::{collection::RecordedPokemon, Store, StoreError};
+use crate::{draws::Draw, leaderboards::day_of};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Score {
+    Shinies,
+    Rarity,
+}
+
+impl Score {
+    fn column(self) -> &'static str {
+        match self {
+            Score::Shinies => "shinies",
+            Score::Rarity => "rarity_score",
+        }
+    }
+}
+
+pub struct LeaderboardRow {
+    pub sub: String,
+    /// Score or timestamp of the completion depending on the leaderboard
+    pub value: u64,
+}
+
+fn leaderboard_row(row: &rusqlite::Row) -> rusqlite::Result<LeaderboardRow> {
+    Ok(LeaderboardRow {
+        sub: row.get(0)?,
+        value: row.get(1)?,
+    })
+}
+
+/// Adds recorded pokemons to the aggregates leaderboards are computed from.
+pub(super) fn record_leaderboards_tx(
+    tx: &Transaction,
+    sub: &str,
+    draw: &Draw,
+    pokemons: &[RecordedPokemon],
+) -> rusqlite::Result<()> {
+    let shinies = pokemons.iter().filter(|pokemon| pokemon.shiny).count() as u64;
+    let rarity_score = pokemons
+        .iter()
+        .map(|pokemon| pokemon.rarity_score as u64)
+        .sum::<u64>();
+    let day = day_of(draw.timestamp).to_string();
+
+    tx.execute(
+        "INSERT INTO leaderboard_days (day, sub, shinies, rarity_score) VALUES (?1, ?2, ?3, ?4) ON CONFLICT (day, sub) DO UPDATE SET shinies = shinies + excluded.shinies, rarity_score = rarity_score + excluded.rarity_score",
+        params![day, sub, shinies, rarity_score],
+    )?;
+    tx.execute(
+        "INSERT INTO leaderboard_totals (sub, shinies, rarity_score) VALUES (?1, ?2, ?3) ON CONFLICT (sub) DO UPDATE SET shinies = shinies + excluded.shinies, rarity_score = rarity_score + excluded.rarity_score",
+        params![sub, shinies, rarity_score],
+    )?;
+
+    let mut stmt = tx.prepare_cached(
+        "INSERT OR IGNORE INTO generation_progress (sub, generation, pokemon_name, first_drawn_at) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for pokemon in pokemons {
+        if let Some(generation) = pokemon.generation {
+            stmt.execute(params![sub, generation, pokemon.name, draw.timestamp])?;
+        }
+    }
+    Ok(())
+}
+
+impl Store {
+    /// Returns subjects with the highest score since the given day, all-time when `since` is `None`.
+    pub async fn leaderboard(
+        &self,
+        score: Score,
+        since: Option<NaiveDate>,
+        limit: u32,
+    ) -> Result<Vec<LeaderboardRow>, StoreError> {
+        self.run(move |conn| {
+            let column = score.column();
+            match since {
+                Some(since) => {
+                    let mut stmt = conn.prepare_cached(&format!(
+                        "SELECT sub, SUM({column}) AS value FROM leaderboard_days WHERE day >= ?1 GROUP BY sub HAVING value > 0 ORDER BY value DESC, sub LIMIT ?2"
+                    ))?;
+                    let rows = stmt.query_map(params![since.to_string(), limit], leaderboard_row)?;
+                    rows.collect()
+                }
+                None => {
+                    let mut stmt = conn.prepare_cached(&format!(
+                        "SELECT sub, {column} AS value FROM leaderboard_totals WHERE value > 0 ORDER BY value DESC, sub LIMIT ?1"
+                    ))?;
+                    let rows = stmt.query_map([limit], leaderboard_row)?;
+                    rows.collect()
+                }
+            }
+        })
+        .await
+    }
+
+    /// Returns subjects who drew all `size` pokemons of the generation, the earliest first.
+    ///
+    /// Only completions at or after `since` timestamp are returned.
+    pub async fn generation_leaderboard(
+        &self,
+        generation: u8,
+        size: usize,
+        since: u64,
+        limit: u32,
+    ) -> Result<Vec<LeaderboardRow>, StoreError> {
+        self.run(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT sub, MAX(first_drawn_at) AS completed_at FROM generation_progress WHERE generation = ?1 GROUP BY sub HAVING COUNT(*) >= ?2 AND completed_at >= ?3 ORDER BY completed_at, sub LIMIT ?4",
+            )?;
+            let rows = stmt.query_map(params![generation, size, since, limit], leaderboard_row)?;
+            rows.collect()
+        })
+        .await
+    }
+}
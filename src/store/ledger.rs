@@ -0,0 +1,494 @@
+use rusqlite::{params, OptionalExtension, Transaction, TransactionBehavior};
+
+use super::{
+    collection::{record_draw_tx, RecordedPokemon},
+    leaderboards::record_leaderboards_tx,
+    now, Store, StoreError,
+};
+use crate::{credits::Banner, draws::Draw};
+
+pub enum LedgerError {
+    InsufficientCredits {
+        balance: i64,
+        cost: i64,
+    },
+    IdempotencyKeyReused,
+    InvalidAmount,
+    /// Draw couldn't be appended to the audit log, so it wasn't committed
+    Audit(std::io::Error),
+}
+
+pub struct CreditTransactionRow {
+    pub id: i64,
+    pub amount: i64,
+    pub balance: i64,
+    pub kind: String,
+    pub draw_id: Option<String>,
+    pub banner: Option<String>,
+    pub granted_by: Option<String>,
+    pub note: Option<String>,
+    pub created_at: u64,
+}
+
+/// Everything persisted together with a draw.
+pub struct DrawCommit {
+    pub sub: String,
+    pub draw: Draw,
+    pub banner: Banner,
+    pub cost: u64,
+    pub idempotency_key: Option<String>,
+    /// Fingerprint of all parameters of the draw, retries with the same idempotency key have to match it
+    pub request: String,
+    /// Pokemons to record into the collection, `None` when recording wasn't requested
+    pub recorded: Option<Vec<RecordedPokemon>>,
+    /// Serialized response of the draw, replayed for retries with the same idempotency key
+    pub response: String,
+    /// Content version of the dataset the response was built from, replayed with it
+    pub dataset_version: String,
+}
+
+pub enum DrawOutcome {
+    Committed,
+    /// Draw with the same idempotency key was already committed
+    Replayed {
+        response: String,
+        /// Content version of the dataset the response was built from, unknown for old draws
+        dataset_version: Option<String>,
+    },
+}
+
+fn balance(tx: &Transaction, sub: &str) -> rusqlite::Result<i64> {
+    Ok(tx
+        .query_row(
+            "SELECT balance FROM credit_balances WHERE sub = ?1",
+            [sub],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or_default())
+}
+
+/// Changes balance of `sub` by `amount` and appends the transaction.
+#[allow(clippy::too_many_arguments)]
+fn add_transaction(
+    tx: &Transaction,
+    sub: &str,
+    amount: i64,
+    kind: &str,
+    draw_id: Option<&str>,
+    banner: Option<Banner>,
+    granted_by: Option<&str>,
+    note: Option<&str>,
+) -> rusqlite::Result<CreditTransactionRow> {
+    let balance = balance(tx, sub)? + amount;
+    tx.execute(
+        "INSERT INTO credit_balances (sub, balance) VALUES (?1, ?2) ON CONFLICT (sub) DO UPDATE SET balance = excluded.balance",
+        params![sub, balance],
+    )?;
+    let created_at = now();
+    let banner = banner.map(Banner::as_str);
+    tx.execute(
+        "INSERT INTO credit_transactions (sub, amount, balance, kind, draw_id, banner, granted_by, note, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![sub, amount, balance, kind, draw_id, banner, granted_by, note, created_at],
+    )?;
+    Ok(CreditTransactionRow {
+        id: tx.last_insert_rowid(),
+        amount,
+        balance,
+        kind: kind.to_string(),
+        draw_id: draw_id.map(str::to_string),
+        banner: banner.map(str::to_string),
+        granted_by: granted_by.map(str::to_string),
+        note: note.map(str::to_string),
+        created_at,
+    })
+}
+
+impl Store {
+    /// Adds credits to the balance of `sub`.
+    pub async fn top_up(
+        &self,
+        sub: String,
+        amount: u64,
+        granted_by: Option<String>,
+        note: Option<String>,
+    ) -> Result<Result<CreditTransactionRow, LedgerError>, StoreError> {
+        self.run(move |conn| {
+            let Ok(amount) = i64::try_from(amount) else {
+                return Ok(Err(LedgerError::InvalidAmount));
+            };
+            if amount == 0 {
+                return Ok(Err(LedgerError::InvalidAmount));
+            }
+
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            if balance(&tx, &sub)?.checked_add(amount).is_none() {
+                return Ok(Err(LedgerError::InvalidAmount));
+            }
+            let transaction = add_transaction(
+                &tx,
+                &sub,
+                amount,
+                "top_up",
+                None,
+                None,
+                granted_by.as_deref(),
+                note.as_deref(),
+            )?;
+            tx.commit()?;
+            Ok(Ok(transaction))
+        })
+        .await
+    }
+
+    /// Returns balance of `sub` and its transactions, newest first.
+    pub async fn credits(
+        &self,
+        sub: String,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(i64, Vec<CreditTransactionRow>), StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let balance = balance(&tx, &sub)?;
+            let transactions = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT id, amount, balance, kind, draw_id, banner, granted_by, note, created_at FROM credit_transactions WHERE sub = ?1 ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+                )?;
+                let rows = stmt.query_map(params![sub, limit, offset], |row| {
+                    Ok(CreditTransactionRow {
+                        id: row.get(0)?,
+                        amount: row.get(1)?,
+                        balance: row.get(2)?,
+                        kind: row.get(3)?,
+                        draw_id: row.get(4)?,
+                        banner: row.get(5)?,
+                        granted_by: row.get(6)?,
+                        note: row.get(7)?,
+                        created_at: row.get(8)?,
+                    })
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            Ok((balance, transactions))
+        })
+        .await
+    }
+
+    /// Charges the pull, records drawn pokemons and stores the idempotency key in a single transaction.
+    ///
+    /// When the idempotency key was already used for the same request nothing is charged
+    /// and the response of the original draw is returned instead.
+    /// `audit` appends the draw to the audit log right before the transaction is committed,
+    /// the transaction is rolled back when it fails. When the commit itself fails `revert_audit`
+    /// appends entry marking the audited draw as reverted.
+    pub async fn commit_draw<F, R>(
+        &self,
+        commit: DrawCommit,
+        audit: F,
+        revert_audit: R,
+    ) -> Result<Result<DrawOutcome, LedgerError>, StoreError>
+    where
+        F: FnOnce() -> std::io::Result<u64> + Send + 'static,
+        R: FnOnce() -> std::io::Result<u64> + Send + 'static,
+    {
+        self.run(move |conn| {
+            let DrawCommit {
+                sub,
+                draw,
+                banner,
+                cost,
+                idempotency_key,
+                request,
+                recorded,
+                response,
+                dataset_version,
+            } = commit;
+
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            if let Some(key) = &idempotency_key {
+                let previous: Option<(String, String, Option<String>)> = tx
+                    .query_row(
+                        "SELECT request, response, dataset_version FROM idempotency_keys WHERE sub = ?1 AND key = ?2",
+                        params![sub, key],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                    )
+                    .optional()?;
+                if let Some((previous_request, response, dataset_version)) = previous {
+                    if previous_request != request {
+                        return Ok(Err(LedgerError::IdempotencyKeyReused));
+                    }
+                    return Ok(Ok(DrawOutcome::Replayed {
+                        response,
+                        dataset_version,
+                    }));
+                }
+            }
+
+            if cost > 0 {
+                let cost = i64::try_from(cost).unwrap_or(i64::MAX);
+                let balance = balance(&tx, &sub)?;
+                if balance < cost {
+                    return Ok(Err(LedgerError::InsufficientCredits { balance, cost }));
+                }
+                add_transaction(
+                    &tx,
+                    &sub,
+                    -cost,
+                    "pull",
+                    Some(&draw.id),
+                    Some(banner),
+                    None,
+                    None,
+                )?;
+            }
+
+            if let Some(pokemons) = &recorded {
+                record_draw_tx(&tx, &sub, &draw, pokemons)?;
+                record_leaderboards_tx(&tx, &sub, &draw, pokemons)?;
+            }
+
+            if let Some(key) = &idempotency_key {
+                tx.execute(
+                    "INSERT INTO idempotency_keys (sub, key, request, draw_id, response, created_at, dataset_version) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![sub, key, request, draw.id, response, draw.timestamp, dataset_version],
+                )?;
+            }
+
+            if let Err(e) = audit() {
+                return Ok(Err(LedgerError::Audit(e)));
+            }
+            if let Err(e) = tx.commit() {
+                if let Err(audit_error) = revert_audit() {
+                    tracing::error!(
+                        "Failed to audit revert of draw {} which couldn't be committed: {}",
+                        draw.id,
+                        audit_error
+                    );
+                }
+                return Err(e);
+            }
+            Ok(Ok(DrawOutcome::Committed))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::store;
+
+    fn commit(sub: &str, cost: u64, key: Option<&str>, names: &[&str]) -> DrawCommit {
+        DrawCommit {
+            sub: sub.into(),
+            draw: Draw::generate(),
+            banner: Banner::Random,
+            cost,
+            idempotency_key: key.map(Into::into),
+            request: format!("get_random:{}", names.len()),
+            recorded: Some(
+                names
+                    .iter()
+                    .map(|name| RecordedPokemon::test(name, false))
+                    .collect(),
+            ),
+            response: format!("{names:?}"),
+            dataset_version: format!("v{}", names.len()),
+        }
+    }
+
+    #[tokio::test]
+    async fn top_up_rejects_invalid_amounts() {
+        let store = store::open_temporary();
+        let res = store.top_up("ash".into(), 0, None, None).await.unwrap();
+        assert!(matches!(res, Err(LedgerError::InvalidAmount)));
+        let res = store
+            .top_up("ash".into(), u64::MAX, None, None)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(LedgerError::InvalidAmount)));
+
+        let transaction = store
+            .top_up("ash".into(), i64::MAX as u64, Some("oak".into()), None)
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+        assert_eq!(transaction.balance, i64::MAX);
+        assert_eq!(transaction.granted_by.as_deref(), Some("oak"));
+        let res = store.top_up("ash".into(), 1, None, None).await.unwrap();
+        assert!(matches!(res, Err(LedgerError::InvalidAmount)));
+    }
+
+    #[tokio::test]
+    async fn draw_is_not_applied_without_credits() {
+        let store = store::open_temporary();
+        store
+            .top_up("ash".into(), 5, None, None)
+            .await
+            .unwrap()
+            .ok();
+
+        let res = store
+            .commit_draw(commit("ash", 10, None, &["pikachu"]), || Ok(0), || Ok(0))
+            .await;
+        assert!(matches!(
+            res.unwrap(),
+            Err(LedgerError::InsufficientCredits {
+                balance: 5,
+                cost: 10
+            })
+        ));
+        assert!(store.owned_pokemons("ash".into()).await.unwrap().is_empty());
+        let (balance, transactions) = store.credits("ash".into(), 10, 0).await.unwrap();
+        assert_eq!(balance, 5);
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retried_draw_is_charged_once() {
+        let store = store::open_temporary();
+        store
+            .top_up("ash".into(), 25, None, None)
+            .await
+            .unwrap()
+            .ok();
+
+        let first = commit("ash", 10, Some("retry"), &["pikachu"]);
+        let draw_id = first.draw.id.clone();
+        let res = store.commit_draw(first, || Ok(0), || Ok(0)).await.unwrap();
+        assert!(matches!(res, Ok(DrawOutcome::Committed)));
+        let res = store
+            .commit_draw(
+                commit("ash", 10, Some("retry"), &["eevee"]),
+                || Ok(0),
+                || Ok(0),
+            )
+            .await
+            .unwrap();
+        // replayed with the version of the original draw's dataset
+        assert!(matches!(
+            res,
+            Ok(DrawOutcome::Replayed { response, dataset_version })
+                if response == r#"["pikachu"]"# && dataset_version.as_deref() == Some("v1")
+        ));
+        let res = store
+            .commit_draw(
+                commit("ash", 20, Some("retry"), &["eevee", "onix"]),
+                || Ok(0),
+                || Ok(0),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(LedgerError::IdempotencyKeyReused)));
+
+        let (balance, transactions) = store.credits("ash".into(), 10, 0).await.unwrap();
+        assert_eq!(balance, 15);
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].amount, -10);
+        assert_eq!(transactions[0].kind, "pull");
+        assert_eq!(transactions[0].draw_id.as_deref(), Some(draw_id.as_str()));
+        assert_eq!(transactions[0].banner.as_deref(), Some("get_random"));
+        let owned = store.owned_pokemons("ash".into()).await.unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].pokemon_name, "pikachu");
+
+        let res = store
+            .commit_draw(
+                commit("gary", 0, Some("retry"), &["eevee"]),
+                || Ok(0),
+                || Ok(0),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(res, Ok(DrawOutcome::Committed)));
+    }
+
+    #[tokio::test]
+    async fn transactions_are_paged_newest_first() {
+        let store = store::open_temporary();
+        for amount in 1..=3 {
+            store
+                .top_up("ash".into(), amount, None, None)
+                .await
+                .unwrap()
+                .ok();
+        }
+        let (balance, transactions) = store.credits("ash".into(), 2, 1).await.unwrap();
+        assert_eq!(balance, 6);
+        let amounts = transactions.iter().map(|t| t.amount).collect::<Vec<_>>();
+        assert_eq!(amounts, [2, 1]);
+        assert_eq!(transactions[0].balance, 3);
+    }
+
+    #[tokio::test]
+    async fn draw_is_rolled_back_when_it_is_not_audited() {
+        let store = store::open_temporary();
+        store
+            .top_up("ash".into(), 10, None, None)
+            .await
+            .unwrap()
+            .ok();
+
+        let res = store
+            .commit_draw(
+                commit("ash", 10, Some("retry"), &["pikachu"]),
+                || Err(std::io::Error::other("disk is full")),
+                || Ok(0),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(LedgerError::Audit(_))));
+        let (balance, transactions) = store.credits("ash".into(), 10, 0).await.unwrap();
+        assert_eq!(balance, 10);
+        assert_eq!(transactions.len(), 1);
+        assert!(store.owned_pokemons("ash".into()).await.unwrap().is_empty());
+
+        let res = store
+            .commit_draw(
+                commit("ash", 10, Some("retry"), &["pikachu"]),
+                || Ok(0),
+                || Ok(0),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(res, Ok(DrawOutcome::Committed)));
+    }
+
+    #[tokio::test]
+    async fn audit_is_reverted_when_commit_fails() {
+        let store = store::open_temporary();
+        // deferred foreign key is checked only on commit
+        store
+            .run(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE commit_blockers (draw_id TEXT REFERENCES draws (id) DEFERRABLE INITIALLY DEFERRED);
+                    CREATE TRIGGER block_commit AFTER INSERT ON draws BEGIN INSERT INTO commit_blockers VALUES ('missing'); END;",
+                )
+            })
+            .await
+            .unwrap();
+
+        let audited = Arc::new(Mutex::new(Vec::new()));
+        let (audit, revert) = (audited.clone(), audited.clone());
+        let res = store
+            .commit_draw(
+                commit("ash", 0, Some("retry"), &["pikachu"]),
+                move || {
+                    audit.lock().unwrap().push("audited");
+                    Ok(0)
+                },
+                move || {
+                    revert.lock().unwrap().push("reverted");
+                    Ok(1)
+                },
+            )
+            .await;
+        assert!(res.is_err());
+        assert_eq!(*audited.lock().unwrap(), ["audited", "reverted"]);
+        assert!(store.owned_pokemons("ash".into()).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+pub mod collection;
+pub mod leaderboards;
+pub mod ledger;
+pub mod quiz;
+pub mod server_seeds;
+pub mod trades;
+
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{http::StatusCode, HttpResponse};
+use rusqlite::Connection;
+
+use crate::req_util::response_from_error;
+
+pub static STORE: OnceLock<Store> = OnceLock::new();
+
+/// Schema migrations, `PRAGMA user_version` holds the number of applied migrations.
+const MIGRATIONS: &[&str] = &[
+    r#"
+CREATE TABLE draws (
+    id TEXT PRIMARY KEY,
+    sub TEXT NOT NULL,
+    drawn_at INTEGER NOT NULL
+);
+CREATE TABLE owned_pokemons (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sub TEXT NOT NULL,
+    draw_id TEXT NOT NULL REFERENCES draws (id),
+    pokemon_name TEXT NOT NULL,
+    shiny INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    obtained_at INTEGER NOT NULL
+);
+CREATE INDEX owned_pokemons_sub ON owned_pokemons (sub, pokemon_name);
+"#,
+    r#"
+CREATE TABLE trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    offerer TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX trades_offerer ON trades (offerer);
+CREATE INDEX trades_recipient ON trades (recipient);
+CREATE TABLE trade_pokemons (
+    trade_id INTEGER NOT NULL REFERENCES trades (id),
+    owned_pokemon_id INTEGER NOT NULL REFERENCES owned_pokemons (id),
+    offered INTEGER NOT NULL,
+    PRIMARY KEY (trade_id, owned_pokemon_id)
+);
+CREATE TABLE trade_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    trade_id INTEGER NOT NULL REFERENCES trades (id),
+    sub TEXT NOT NULL,
+    action TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE ownership_transfers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owned_pokemon_id INTEGER NOT NULL REFERENCES owned_pokemons (id),
+    from_sub TEXT NOT NULL,
+    to_sub TEXT NOT NULL,
+    trade_id INTEGER REFERENCES trades (id),
+    transferred_at INTEGER NOT NULL
+);
+"#,
+    r#"
+CREATE TABLE credit_balances (
+    sub TEXT PRIMARY KEY,
+    balance INTEGER NOT NULL CHECK (balance >= 0)
+);
+CREATE TABLE credit_transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sub TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    balance INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    draw_id TEXT,
+    banner TEXT,
+    granted_by TEXT,
+    note TEXT,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX credit_transactions_sub ON credit_transactions (sub, id);
+CREATE TABLE idempotency_keys (
+    sub TEXT NOT NULL,
+    key TEXT NOT NULL,
+    request TEXT NOT NULL,
+    draw_id TEXT NOT NULL,
+    response TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (sub, key)
+);
+"#,
+    r#"
+CREATE TABLE leaderboard_days (
+    day TEXT NOT NULL,
+    sub TEXT NOT NULL,
+    shinies INTEGER NOT NULL,
+    rarity_score INTEGER NOT NULL,
+    PRIMARY KEY (day, sub)
+);
+CREATE TABLE leaderboard_totals (
+    sub TEXT PRIMARY KEY,
+    shinies INTEGER NOT NULL,
+    rarity_score INTEGER NOT NULL
+);
+CREATE TABLE generation_progress (
+    sub TEXT NOT NULL,
+    generation INTEGER NOT NULL,
+    pokemon_name TEXT NOT NULL,
+    first_drawn_at INTEGER NOT NULL,
+    PRIMARY KEY (generation, sub, pokemon_name)
+);
+-- draws recorded before leaderboards existed count for whoever drew them, their days are in UTC,
+-- capture rates and generations of their pokemons are unknown so they score as the easiest to catch
+-- (multiplied by SHINY_RARITY_MULTIPLIER for shinies) and don't count towards generation completion
+INSERT INTO leaderboard_days (day, sub, shinies, rarity_score)
+SELECT date(draws.drawn_at, 'unixepoch'), draws.sub, SUM(owned_pokemons.shiny), SUM(CASE WHEN owned_pokemons.shiny THEN 16 ELSE 1 END)
+FROM owned_pokemons JOIN draws ON draws.id = owned_pokemons.draw_id
+GROUP BY 1, 2;
+INSERT INTO leaderboard_totals (sub, shinies, rarity_score)
+SELECT sub, SUM(shinies), SUM(rarity_score) FROM leaderboard_days GROUP BY sub;
+"#,
+    r#"
+CREATE TABLE server_seeds (
+    hash TEXT PRIMARY KEY,
+    seed TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    revealed_at INTEGER
+);
+"#,
+    r#"
+-- content version of the dataset replayed responses were built from, unknown for keys stored before
+ALTER TABLE idempotency_keys ADD COLUMN dataset_version TEXT;
+"#,
+    r#"
+CREATE TABLE quiz_answers (
+    challenge_id TEXT PRIMARY KEY,
+    expires_at INTEGER NOT NULL
+);
+"#,
+];
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Sqlite(rusqlite::Error),
+    Serialize(serde_json::Error),
+    Join(tokio::task::JoinError),
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Sqlite(e) => write!(f, "{e}"),
+            StoreError::Serialize(e) => write!(f, "{e}"),
+            StoreError::Join(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+
+impl StoreError {
+    pub fn into_response(self, context: &str) -> HttpResponse {
+        response_from_error(
+            format!("{context}: {self}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+
+        let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i + 1)?;
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn get() -> Result<&'static Store, HttpResponse> {
+        STORE.get().ok_or_else(|| {
+            response_from_error("Store is not available", StatusCode::INTERNAL_SERVER_ERROR)
+        })
+    }
+
+    /// Runs blocking database work on the blocking thread pool.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(StoreError::Join)?
+        .map_err(StoreError::Sqlite)
+    }
+}
+
+/// Opens a new store in memory, it lives until the end of the tests.
+#[cfg(test)]
+pub fn open_temporary() -> &'static Store {
+    Box::leak(Box::new(
+        Store::open(":memory:").expect("temporary store opens"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{store::leaderboards::Score, test_util::TempDir};
+
+    #[tokio::test]
+    async fn leaderboards_are_backfilled_from_recorded_draws() {
+        let dir = TempDir::new();
+        let path = dir.join("store.sqlite");
+        let path = path.to_str().unwrap();
+        {
+            let conn = Connection::open(path).unwrap();
+            conn.execute_batch(&MIGRATIONS[..3].concat()).unwrap();
+            conn.pragma_update(None, "user_version", 3).unwrap();
+            conn.execute_batch(
+                r#"
+INSERT INTO draws (id, sub, drawn_at) VALUES ('a', 'ash', 86400), ('b', 'ash', 172800), ('c', 'gary', 172800);
+INSERT INTO owned_pokemons (sub, draw_id, pokemon_name, shiny, data, obtained_at) VALUES
+    ('ash', 'a', 'pikachu', 1, '{}', 86400),
+    ('ash', 'a', 'eevee', 0, '{}', 86400),
+    ('gary', 'b', 'onix', 0, '{}', 172800),
+    ('gary', 'c', 'eevee', 0, '{}', 172800);
+"#,
+            )
+            .unwrap();
+        }
+
+        let store = Store::open(path).unwrap();
+        let rows = store.leaderboard(Score::Rarity, None, 10).await.unwrap();
+        let rows = rows
+            .iter()
+            .map(|row| (row.sub.as_str(), row.value))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [("ash", 18), ("gary", 1)]);
+
+        let since = chrono::NaiveDate::from_ymd_opt(1970, 1, 3);
+        let rows = store.leaderboard(Score::Rarity, since, 10).await.unwrap();
+        let rows = rows
+            .iter()
+            .map(|row| (row.sub.as_str(), row.value))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [("ash", 1), ("gary", 1)]);
+        let rows = store.leaderboard(Score::Shinies, None, 10).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].value, 1);
+    }
+}
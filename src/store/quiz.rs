@@ -0,0 +1,80 @@
+use rusqlite::params;
+
+use super::{now, Store, StoreError};
+
+impl Store {
+    /// Marks the quiz challenge answered, returns `false` when it already was.
+    ///
+    /// Challenges which expired are forgotten meanwhile, they can't be answered anyway.
+    pub async fn mark_challenge_answered(
+        &self,
+        challenge_id: String,
+        expires_at: u64,
+    ) -> Result<bool, StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM quiz_answers WHERE expires_at < ?1",
+                params![now()],
+            )?;
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO quiz_answers (challenge_id, expires_at) VALUES (?1, ?2)",
+                params![challenge_id, expires_at],
+            )?;
+            tx.commit()?;
+            Ok(inserted == 1)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store;
+
+    #[tokio::test]
+    async fn challenge_is_answered_only_once() {
+        let store = store::open_temporary();
+        let expires_at = store::now() + 60;
+        assert!(store
+            .mark_challenge_answered("a".into(), expires_at)
+            .await
+            .unwrap());
+        assert!(!store
+            .mark_challenge_answered("a".into(), expires_at)
+            .await
+            .unwrap());
+        assert!(store
+            .mark_challenge_answered("b".into(), expires_at)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_challenges_are_forgotten() {
+        let store = store::open_temporary();
+        assert!(store.mark_challenge_answered("a".into(), 1).await.unwrap());
+        let remembered = store
+            .run(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM quiz_answers", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(remembered, 1);
+        store
+            .mark_challenge_answered("b".into(), store::now() + 60)
+            .await
+            .unwrap();
+        let remembered = store
+            .run(|conn| {
+                conn.query_row("SELECT challenge_id FROM quiz_answers", [], |row| {
+                    row.get::<_, String>(0)
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(remembered, "b");
+    }
+}
@@ -0,0 +1,62 @@
+use rusqlite::{params, OptionalExtension};
+
+use super::{now, Store, StoreError};
+
+impl Store {
+    /// Returns the active server seed, if any, and all revealed ones.
+    pub async fn server_seeds(&self) -> Result<(Option<String>, Vec<String>), StoreError> {
+        self.run(|conn| {
+            let active = conn
+                .query_row(
+                    "SELECT seed FROM server_seeds WHERE revealed_at IS NULL ORDER BY created_at DESC, rowid DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let mut stmt = conn.prepare_cached(
+                "SELECT seed FROM server_seeds WHERE revealed_at IS NOT NULL ORDER BY revealed_at, rowid",
+            )?;
+            let revealed = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok((active, revealed))
+        })
+        .await
+    }
+
+    /// Stores a new active server seed.
+    pub async fn insert_server_seed(&self, hash: String, seed: String) -> Result<(), StoreError> {
+        self.run(move |conn| {
+            conn.execute(
+                "INSERT INTO server_seeds (hash, seed, created_at) VALUES (?1, ?2, ?3)",
+                params![hash, seed, now()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Reveals the server seed and stores the next active one, `None` only reveals it.
+    pub async fn rotate_server_seed(
+        &self,
+        revealed_hash: String,
+        next: Option<(String, String)>,
+    ) -> Result<(), StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let now = now();
+            tx.execute(
+                "UPDATE server_seeds SET revealed_at = ?2 WHERE hash = ?1 AND revealed_at IS NULL",
+                params![revealed_hash, now],
+            )?;
+            if let Some((hash, seed)) = next {
+                tx.execute(
+                    "INSERT INTO server_seeds (hash, seed, created_at) VALUES (?1, ?2, ?3)",
+                    params![hash, seed, now],
+                )?;
+            }
+            tx.commit()
+        })
+        .await
+    }
+}
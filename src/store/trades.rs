@@ -0,0 +1,510 @@
+use std::collections::HashSet;
+
+use rusqlite::{params, OptionalExtension, Transaction, TransactionBehavior};
+
+use super::{now, Store, StoreError};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TradeAction {
+    Created,
+    Accepted,
+    Declined,
+    Cancelled,
+}
+
+impl TradeAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TradeAction::Created => "created",
+            TradeAction::Accepted => "accepted",
+            TradeAction::Declined => "declined",
+            TradeAction::Cancelled => "cancelled",
+        }
+    }
+}
+
+pub enum TradeError {
+    NotFound,
+    NotAllowed(&'static str),
+    NotPending,
+    NotOwned(i64),
+    Invalid(&'static str),
+}
+
+pub struct TradeRow {
+    pub id: i64,
+    pub offerer: String,
+    pub recipient: String,
+    pub status: String,
+    pub offered: Vec<i64>,
+    pub requested: Vec<i64>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub events: Vec<TradeEventRow>,
+}
+
+pub struct TradeEventRow {
+    pub sub: String,
+    pub action: String,
+    pub created_at: u64,
+}
+
+fn check_owned(
+    tx: &Transaction,
+    sub: &str,
+    ids: &[i64],
+) -> rusqlite::Result<Result<(), TradeError>> {
+    let mut stmt = tx.prepare_cached("SELECT sub FROM owned_pokemons WHERE id = ?1")?;
+    for id in ids {
+        let owner: Option<String> = stmt.query_row([id], |row| row.get(0)).optional()?;
+        if owner.as_deref() != Some(sub) {
+            return Ok(Err(TradeError::NotOwned(*id)));
+        }
+    }
+    Ok(Ok(()))
+}
+
+fn add_event(
+    tx: &Transaction,
+    trade_id: i64,
+    sub: &str,
+    action: TradeAction,
+    at: u64,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO trade_events (trade_id, sub, action, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![trade_id, sub, action.as_str(), at],
+    )?;
+    Ok(())
+}
+
+fn load_trade(tx: &Transaction, id: i64) -> rusqlite::Result<Option<TradeRow>> {
+    let Some(mut trade) = tx
+        .query_row(
+            "SELECT id, offerer, recipient, status, created_at, updated_at FROM trades WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(TradeRow {
+                    id: row.get(0)?,
+                    offerer: row.get(1)?,
+                    recipient: row.get(2)?,
+                    status: row.get(3)?,
+                    offered: Vec::new(),
+                    requested: Vec::new(),
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    events: Vec::new(),
+                })
+            },
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let mut stmt = tx.prepare_cached(
+        "SELECT owned_pokemon_id, offered FROM trade_pokemons WHERE trade_id = ?1 ORDER BY owned_pokemon_id",
+    )?;
+    let pokemons = stmt.query_map([id], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?))
+    })?;
+    for pokemon in pokemons {
+        let (pokemon_id, offered) = pokemon?;
+        if offered {
+            trade.offered.push(pokemon_id);
+        } else {
+            trade.requested.push(pokemon_id);
+        }
+    }
+
+    let mut stmt = tx.prepare_cached(
+        "SELECT sub, action, created_at FROM trade_events WHERE trade_id = ?1 ORDER BY id",
+    )?;
+    trade.events = stmt
+        .query_map([id], |row| {
+            Ok(TradeEventRow {
+                sub: row.get(0)?,
+                action: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(Some(trade))
+}
+
+fn transfer(
+    tx: &Transaction,
+    trade_id: i64,
+    ids: &[i64],
+    from: &str,
+    to: &str,
+    at: u64,
+) -> rusqlite::Result<()> {
+    let mut update = tx.prepare_cached("UPDATE owned_pokemons SET sub = ?1 WHERE id = ?2")?;
+    let mut audit = tx.prepare_cached(
+        "INSERT INTO ownership_transfers (owned_pokemon_id, from_sub, to_sub, trade_id, transferred_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for id in ids {
+        update.execute(params![to, id])?;
+        audit.execute(params![id, from, to, trade_id, at])?;
+    }
+    Ok(())
+}
+
+impl Store {
+    /// Creates trade offer where `offerer` gives `offered` pokemons to `recipient` for `requested` pokemons.
+    pub async fn create_trade(
+        &self,
+        offerer: String,
+        recipient: String,
+        offered: Vec<i64>,
+        requested: Vec<i64>,
+    ) -> Result<Result<TradeRow, TradeError>, StoreError> {
+        self.run(move |conn| {
+            if offerer == recipient {
+                return Ok(Err(TradeError::Invalid("Can't trade with yourself")));
+            }
+            if offered.is_empty() {
+                return Ok(Err(TradeError::Invalid("At least one pokemon has to be offered")));
+            }
+            let mut unique = HashSet::new();
+            if !offered.iter().chain(&requested).all(|id| unique.insert(*id)) {
+                return Ok(Err(TradeError::Invalid("Every pokemon can be in the trade only once")));
+            }
+
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            if let Err(e) = check_owned(&tx, &offerer, &offered)? {
+                return Ok(Err(e));
+            }
+            if let Err(e) = check_owned(&tx, &recipient, &requested)? {
+                return Ok(Err(e));
+            }
+
+            let at = now();
+            tx.execute(
+                "INSERT INTO trades (offerer, recipient, status, created_at, updated_at) VALUES (?1, ?2, 'pending', ?3, ?3)",
+                params![offerer, recipient, at],
+            )?;
+            let trade_id = tx.last_insert_rowid();
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT INTO trade_pokemons (trade_id, owned_pokemon_id, offered) VALUES (?1, ?2, ?3)",
+                )?;
+                for id in &offered {
+                    stmt.execute(params![trade_id, id, true])?;
+                }
+                for id in &requested {
+                    stmt.execute(params![trade_id, id, false])?;
+                }
+            }
+            add_event(&tx, trade_id, &offerer, TradeAction::Created, at)?;
+
+            let trade = load_trade(&tx, trade_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+            tx.commit()?;
+            Ok(Ok(trade))
+        })
+        .await
+    }
+
+    /// Accepts, declines or cancels pending trade.
+    ///
+    /// Only recipient can accept or decline and only offerer can cancel.
+    /// Accepting validates ownership of all pokemons again and transfers them atomically.
+    pub async fn resolve_trade(
+        &self,
+        trade_id: i64,
+        sub: String,
+        action: TradeAction,
+    ) -> Result<Result<TradeRow, TradeError>, StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let Some(trade) = load_trade(&tx, trade_id)? else {
+                return Ok(Err(TradeError::NotFound));
+            };
+
+            let allowed = match action {
+                TradeAction::Accepted | TradeAction::Declined => trade.recipient == sub,
+                TradeAction::Cancelled => trade.offerer == sub,
+                TradeAction::Created => false,
+            };
+            if !allowed {
+                if trade.recipient != sub && trade.offerer != sub {
+                    return Ok(Err(TradeError::NotFound));
+                }
+                return Ok(Err(TradeError::NotAllowed(match action {
+                    TradeAction::Cancelled => "Only offerer can cancel the trade",
+                    _ => "Only recipient can accept or decline the trade",
+                })));
+            }
+            if trade.status != "pending" {
+                return Ok(Err(TradeError::NotPending));
+            }
+
+            let at = now();
+            if action == TradeAction::Accepted {
+                if let Err(e) = check_owned(&tx, &trade.offerer, &trade.offered)? {
+                    return Ok(Err(e));
+                }
+                if let Err(e) = check_owned(&tx, &trade.recipient, &trade.requested)? {
+                    return Ok(Err(e));
+                }
+                transfer(
+                    &tx,
+                    trade_id,
+                    &trade.offered,
+                    &trade.offerer,
+                    &trade.recipient,
+                    at,
+                )?;
+                transfer(
+                    &tx,
+                    trade_id,
+                    &trade.requested,
+                    &trade.recipient,
+                    &trade.offerer,
+                    at,
+                )?;
+            }
+
+            tx.execute(
+                "UPDATE trades SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![action.as_str(), at, trade_id],
+            )?;
+            add_event(&tx, trade_id, &sub, action, at)?;
+
+            let trade = load_trade(&tx, trade_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+            tx.commit()?;
+            Ok(Ok(trade))
+        })
+        .await
+    }
+
+    /// Returns trade if `sub` participates in it.
+    pub async fn trade(&self, trade_id: i64, sub: String) -> Result<Option<TradeRow>, StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let trade = load_trade(&tx, trade_id)?
+                .filter(|trade| trade.offerer == sub || trade.recipient == sub);
+            Ok(trade)
+        })
+        .await
+    }
+
+    /// Returns all trades `sub` participates in, newest first.
+    pub async fn trades(&self, sub: String) -> Result<Vec<TradeRow>, StoreError> {
+        self.run(move |conn| {
+            let tx = conn.transaction()?;
+            let ids = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT id FROM trades WHERE offerer = ?1 OR recipient = ?1 ORDER BY id DESC",
+                )?;
+                let ids = stmt.query_map([&sub], |row| row.get::<_, i64>(0))?;
+                ids.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            let mut trades = Vec::with_capacity(ids.len());
+            for id in ids {
+                trades.extend(load_trade(&tx, id)?);
+            }
+            Ok(trades)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{draws::Draw, store, store::collection::RecordedPokemon};
+
+    async fn owned(store: &Store, sub: &str, name: &str) -> i64 {
+        store
+            .record_test_draw(
+                sub,
+                &Draw::generate(),
+                vec![RecordedPokemon::test(name, false)],
+            )
+            .await[0]
+    }
+
+    async fn owner(store: &Store, id: i64) -> String {
+        store
+            .run(move |conn| {
+                conn.query_row(
+                    "SELECT sub FROM owned_pokemons WHERE id = ?1",
+                    [id],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepted_trade_swaps_owners() {
+        let store = store::open_temporary();
+        let pikachu = owned(store, "ash", "pikachu").await;
+        let eevee = owned(store, "gary", "eevee").await;
+
+        let trade = store
+            .create_trade("ash".into(), "gary".into(), vec![pikachu], vec![eevee])
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+        assert_eq!(trade.status, "pending");
+        assert_eq!(trade.offered, [pikachu]);
+        assert_eq!(trade.requested, [eevee]);
+
+        let trade = store
+            .resolve_trade(trade.id, "gary".into(), TradeAction::Accepted)
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+        assert_eq!(trade.status, "accepted");
+        let actions = trade
+            .events
+            .iter()
+            .map(|event| (event.sub.as_str(), event.action.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(actions, [("ash", "created"), ("gary", "accepted")]);
+        assert_eq!(owner(store, pikachu).await, "gary");
+        assert_eq!(owner(store, eevee).await, "ash");
+
+        let transfers: i64 = store
+            .run(move |conn| {
+                conn.query_row(
+                    "SELECT COUNT(*) FROM ownership_transfers WHERE trade_id = ?1",
+                    [trade.id],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(transfers, 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_offers_are_rejected() {
+        let store = store::open_temporary();
+        let pikachu = owned(store, "ash", "pikachu").await;
+        let eevee = owned(store, "gary", "eevee").await;
+
+        let res = store
+            .create_trade("ash".into(), "ash".into(), vec![pikachu], vec![])
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::Invalid(_))));
+        let res = store
+            .create_trade("ash".into(), "gary".into(), vec![], vec![eevee])
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::Invalid(_))));
+        let res = store
+            .create_trade("ash".into(), "gary".into(), vec![pikachu, pikachu], vec![])
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::Invalid(_))));
+        let res = store
+            .create_trade("ash".into(), "gary".into(), vec![eevee], vec![])
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotOwned(id)) if id == eevee));
+        let res = store
+            .create_trade(
+                "ash".into(),
+                "gary".into(),
+                vec![pikachu],
+                vec![pikachu + 100],
+            )
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotOwned(_))));
+    }
+
+    #[tokio::test]
+    async fn only_participants_resolve_pending_trades() {
+        let store = store::open_temporary();
+        let pikachu = owned(store, "ash", "pikachu").await;
+        let trade = store
+            .create_trade("ash".into(), "gary".into(), vec![pikachu], vec![])
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+
+        let res = store
+            .resolve_trade(trade.id, "misty".into(), TradeAction::Accepted)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotFound)));
+        let res = store
+            .resolve_trade(trade.id, "ash".into(), TradeAction::Accepted)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotAllowed(_))));
+        let res = store
+            .resolve_trade(trade.id, "gary".into(), TradeAction::Cancelled)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotAllowed(_))));
+        assert!(store
+            .trade(trade.id, "misty".into())
+            .await
+            .unwrap()
+            .is_none());
+
+        let res = store
+            .resolve_trade(trade.id, "ash".into(), TradeAction::Cancelled)
+            .await
+            .unwrap();
+        assert!(matches!(res, Ok(TradeRow { ref status, .. }) if status == "cancelled"));
+        let res = store
+            .resolve_trade(trade.id, "gary".into(), TradeAction::Accepted)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotPending)));
+        assert_eq!(owner(store, pikachu).await, "ash");
+        assert_eq!(store.trades("gary".into()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn accepting_revalidates_ownership() {
+        let store = store::open_temporary();
+        let pikachu = owned(store, "ash", "pikachu").await;
+        let eevee = owned(store, "gary", "eevee").await;
+        let first = store
+            .create_trade("ash".into(), "gary".into(), vec![pikachu], vec![])
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+        let second = store
+            .create_trade("ash".into(), "misty".into(), vec![pikachu], vec![])
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+        store
+            .resolve_trade(first.id, "gary".into(), TradeAction::Accepted)
+            .await
+            .unwrap()
+            .ok()
+            .unwrap();
+
+        let res = store
+            .resolve_trade(second.id, "misty".into(), TradeAction::Accepted)
+            .await
+            .unwrap();
+        assert!(matches!(res, Err(TradeError::NotOwned(id)) if id == pikachu));
+        assert_eq!(owner(store, pikachu).await, "gary");
+        assert_eq!(owner(store, eevee).await, "gary");
+        let second = store
+            .trade(second.id, "misty".into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.status, "pending");
+    }
+}
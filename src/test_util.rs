@@ -0,0 +1,37 @@
+//! Helpers shared by unit tests.
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// New directory in the system temporary directory, it's removed with its content on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("pokemon_api_test_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&path).expect("temporary directory is created");
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::metrics;
+
+pub static UPSTREAM: OnceLock<Upstream> = OnceLock::new();
+
+pub const DEFAULT_ENDPOINT: &str = "https://beta.pokeapi.co/graphql/v1beta";
+
+/// Timeout of a single request to the remote api.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time in which a request including its failovers has to finish.
+pub const DEFAULT_DEADLINE: Duration = Duration::from_secs(60);
+
+/// Consecutive failures after which endpoint is marked unhealthy.
+pub const FAILURE_THRESHOLD: u32 = 3;
+
+/// How long unhealthy endpoint is tried only after the healthy ones.
+pub const UNHEALTHY_FOR: Duration = Duration::from_secs(30);
+
+/// Returns the configured remote api, it's the public PokeAPI unless configured otherwise.
+pub fn upstream() -> &'static Upstream {
+    UPSTREAM.get_or_init(|| Upstream {
+        endpoints: vec![Endpoint::new(DEFAULT_ENDPOINT.into())],
+        timeout: DEFAULT_TIMEOUT,
+        deadline: DEFAULT_DEADLINE,
+    })
+}
+
+/// GraphQL endpoint of PokeAPI or of its mirror.
+struct Endpoint {
+    url: String,
+    consecutive_failures: AtomicU32,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            consecutive_failures: AtomicU32::new(0),
+            unhealthy_until: Mutex::new(None),
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        let unhealthy_until = self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        !matches!(*unhealthy_until, Some(until) if until > Instant::now())
+    }
+
+    fn succeeded(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn failed(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= FAILURE_THRESHOLD {
+            tracing::warn!(
+                "Remote api {} failed {} times in a row, it's used only when other endpoints fail for the next {} seconds",
+                self.url,
+                failures,
+                UNHEALTHY_FOR.as_secs()
+            );
+            *self
+                .unhealthy_until
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now() + UNHEALTHY_FOR);
+        }
+    }
+}
+
+/// Failed request to the remote api.
+#[derive(Debug)]
+pub enum UpstreamError {
+    /// Request failed or its response couldn't be read, error statuses included
+    Request(reqwest::Error),
+    /// Remote api answered with GraphQL errors
+    GraphQl(String),
+    /// Response isn't the requested data or the data are invalid
+    Invalid(String),
+}
+
+impl Display for UpstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamError::Request(e) => write!(f, "{e}"),
+            UpstreamError::GraphQl(e) => write!(f, "Remote api returned errors: {e}"),
+            UpstreamError::Invalid(e) => write!(f, "Remote api returned invalid data: {e}"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for UpstreamError {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Request(value)
+    }
+}
+
+impl UpstreamError {
+    /// Returns whether the endpoint is failing rather than the request being wrong, only then other endpoints are tried.
+    fn is_endpoint_failure(&self) -> bool {
+        match self {
+            UpstreamError::Request(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.is_request()
+                    || e.is_body()
+                    || e.is_decode()
+                    || e.status().is_some_and(|status| status.is_server_error())
+            }
+            UpstreamError::GraphQl(_) | UpstreamError::Invalid(_) => true,
+        }
+    }
+
+    /// Returns class of the error for metrics.
+    pub fn class(&self) -> &'static str {
+        match self {
+            UpstreamError::Request(e) => metrics::error_class(e),
+            UpstreamError::GraphQl(_) => "graphql",
+            UpstreamError::Invalid(_) => "invalid_data",
+        }
+    }
+}
+
+/// Remote api with its mirrors, requests go to the first healthy endpoint and fail over to the next one when it fails.
+pub struct Upstream {
+    /// Endpoints in the order of preference
+    endpoints: Vec<Endpoint>,
+    timeout: Duration,
+    deadline: Duration,
+}
+
+impl Upstream {
+    /// Parses comma separated urls of the endpoints, the first one is preferred.
+    ///
+    /// Every request to an endpoint times out after `timeout`, no endpoint is tried after `deadline`.
+    pub fn new(urls: &str, timeout: Duration, deadline: Duration) -> Result<Self, String> {
+        let endpoints = urls
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(|url| {
+                reqwest::Url::parse(url)
+                    .map(|_| Endpoint::new(url.to_string()))
+                    .map_err(|e| format!("Remote api url '{url}' is invalid: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if endpoints.is_empty() {
+            return Err("No remote api url is set".into());
+        }
+        Ok(Self {
+            endpoints,
+            timeout,
+            deadline,
+        })
+    }
+
+    /// Urls of the endpoints and whether they are healthy.
+    pub fn endpoints(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.url.as_str(), endpoint.is_healthy()))
+    }
+
+    /// Sends the request built by `send` to the endpoints until one of them succeeds.
+    ///
+    /// Healthy endpoints are tried first and the next one is tried only when the endpoint couldn't be reached,
+    /// dropped the connection, timed out, returned server error, GraphQL errors or invalid data.
+    /// Other errors are returned right away, as would be from any endpoint.
+    /// Last error is returned when all endpoints failed or the deadline passed.
+    pub async fn send<T, F, Fut>(
+        &self,
+        req_client: &reqwest::Client,
+        method: reqwest::Method,
+        send: F,
+    ) -> Result<T, UpstreamError>
+    where
+        F: Fn(reqwest::RequestBuilder) -> Fut,
+        Fut: Future<Output = Result<T, UpstreamError>>,
+    {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .partition(|endpoint| endpoint.is_healthy());
+
+        let deadline = Instant::now() + self.deadline;
+        let mut last_error = None;
+        for endpoint in healthy.into_iter().chain(unhealthy) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Some(e) = &last_error {
+                if remaining.is_zero() {
+                    tracing::warn!(
+                        "Remote api request failed and its deadline passed, not failing over to {}: {}",
+                        endpoint.url,
+                        e
+                    );
+                    break;
+                }
+                tracing::warn!(
+                    "Remote api request failed, failing over to {}: {}",
+                    endpoint.url,
+                    e
+                );
+                metrics::UPSTREAM_FAILOVERS.inc([]);
+            }
+
+            let request = req_client
+                .request(method.clone(), &endpoint.url)
+                .timeout(self.timeout.min(remaining));
+            let started = Instant::now();
+            let res = send(request).await;
+            metrics::observe_upstream(started, res.as_ref().err().map(UpstreamError::class));
+            match res {
+                Ok(data) => {
+                    endpoint.succeeded();
+                    return Ok(data);
+                }
+                Err(e) if e.is_endpoint_failure() => {
+                    endpoint.failed();
+                    last_error = Some(e);
+                }
+                // endpoint is up, the request itself was refused
+                Err(e) => {
+                    endpoint.succeeded();
+                    return Err(e);
+                }
+            }
+        }
+        Err(last_error.expect("remote api has at least one endpoint"))
+    }
+}
+
+/// Mock endpoints of the remote api.
+#[cfg(test)]
+pub mod mock {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Endpoint answering every request with the same response.
+    pub struct MockEndpoint {
+        pub url: String,
+        /// Number of received requests
+        pub requests: Arc<AtomicUsize>,
+    }
+
+    /// Starts a mock endpoint, requests get no reply when `response` is `None`.
+    pub async fn mock_endpoint(response: Option<(u16, &str)>) -> MockEndpoint {
+        mock_raw_endpoint(response.map(|(status, body)| {
+            format!(
+                "HTTP/1.1 {status} Status\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }))
+        .await
+    }
+
+    /// Starts a mock endpoint writing the raw `response` and closing the connection,
+    /// requests get no reply when `response` is `None`.
+    pub async fn mock_raw_endpoint(response: Option<String>) -> MockEndpoint {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let received = received.clone();
+                let response = response.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    // requests are sent with a small body right after the headers
+                    while !request.ends_with(b"}") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => request.extend_from_slice(&buf[..read]),
+                        }
+                    }
+                    received.fetch_add(1, Ordering::SeqCst);
+                    let Some(response) = response else {
+                        std::future::pending::<()>().await;
+                        return;
+                    };
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        MockEndpoint { url, requests }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use tokio::net::TcpListener;
+
+    use super::{mock::*, *};
+    use crate::req_util::send_graphql;
+
+    fn mock_upstream(endpoints: &[&str], timeout: Duration, deadline: Duration) -> Upstream {
+        Upstream::new(&endpoints.join(","), timeout, deadline).unwrap()
+    }
+
+    async fn send(upstream: &Upstream) -> Result<serde_json::Value, UpstreamError> {
+        let client = reqwest::Client::new();
+        upstream
+            .send(&client, reqwest::Method::POST, |request| {
+                send_graphql(request.json(&serde_json::json!({ "query": "" })))
+            })
+            .await
+    }
+
+    fn health(upstream: &Upstream) -> Vec<bool> {
+        upstream.endpoints().map(|(_, healthy)| healthy).collect()
+    }
+
+    #[tokio::test]
+    async fn failing_endpoints_are_failed_over_and_tried_last() {
+        let failing = mock_endpoint(Some((503, "{}"))).await;
+        let working = mock_endpoint(Some((200, r#"{"data":1}"#))).await;
+        // nothing listens on the port of the released listener
+        let unreachable = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}/graphql", listener.local_addr().unwrap())
+        };
+        let upstream = mock_upstream(
+            &[&unreachable, &failing.url, &working.url],
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+        );
+
+        for i in 1..=FAILURE_THRESHOLD {
+            let data = send(&upstream).await.unwrap();
+            assert_eq!(data["data"], 1);
+            let unhealthy = i == FAILURE_THRESHOLD;
+            assert_eq!(health(&upstream), [!unhealthy, !unhealthy, true]);
+        }
+        assert_eq!(failing.requests.load(Ordering::SeqCst), 3);
+
+        // unhealthy endpoints aren't tried while a healthy one works
+        send(&upstream).await.unwrap();
+        assert_eq!(failing.requests.load(Ordering::SeqCst), 3);
+        assert_eq!(working.requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn request_errors_are_not_failed_over() {
+        let working = mock_endpoint(Some((200, r#"{"data":1}"#))).await;
+        let refusing = mock_endpoint(Some((400, "{}"))).await;
+        let upstream = mock_upstream(
+            &[&refusing.url, &working.url],
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+        );
+        for _ in 0..FAILURE_THRESHOLD {
+            let e = send(&upstream).await.unwrap_err();
+            assert!(!e.is_endpoint_failure(), "{e}");
+        }
+        assert_eq!(health(&upstream), [true, true]);
+        assert_eq!(working.requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn broken_responses_are_failed_over() {
+        let working = mock_endpoint(Some((200, r#"{"data":1}"#))).await;
+        let broken = [
+            mock_endpoint(Some((200, "{not json}"))).await,
+            mock_endpoint(Some((200, r#"{"errors":[{"message":"boom"}]}"#))).await,
+            // connection is closed without any response
+            mock_raw_endpoint(Some(String::new())).await,
+            // connection is closed in the middle of the body
+            mock_raw_endpoint(Some(
+                "HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\n{\"data\"".into(),
+            ))
+            .await,
+        ];
+        for (i, endpoint) in broken.iter().enumerate() {
+            let upstream = mock_upstream(
+                &[&endpoint.url, &working.url],
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+            );
+            assert_eq!(send(&upstream).await.unwrap()["data"], 1);
+            assert_eq!(working.requests.load(Ordering::SeqCst), i + 1);
+
+            let upstream = mock_upstream(
+                &[&endpoint.url],
+                Duration::from_secs(5),
+                Duration::from_secs(10),
+            );
+            let e = send(&upstream).await.unwrap_err();
+            assert!(e.is_endpoint_failure(), "{e}");
+        }
+
+        let upstream = mock_upstream(
+            &[&broken[1].url],
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+        );
+        let e = send(&upstream).await.unwrap_err();
+        assert!(
+            matches!(&e, UpstreamError::GraphQl(message) if message == "boom"),
+            "{e}"
+        );
+    }
+
+    #[tokio::test]
+    async fn no_endpoint_is_tried_after_deadline() {
+        let stalled = mock_endpoint(None).await;
+        let working = mock_endpoint(Some((200, r#"{"data":1}"#))).await;
+
+        let upstream = mock_upstream(
+            &[&stalled.url, &working.url],
+            Duration::from_millis(200),
+            Duration::from_millis(100),
+        );
+        let started = Instant::now();
+        let e = send(&upstream).await.unwrap_err();
+        assert!(
+            matches!(&e, UpstreamError::Request(e) if e.is_timeout()),
+            "{e}"
+        );
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(working.requests.load(Ordering::SeqCst), 0);
+
+        // timed out endpoint is failed over while there is time left
+        let upstream = mock_upstream(
+            &[&stalled.url, &working.url],
+            Duration::from_millis(100),
+            Duration::from_secs(10),
+        );
+        assert_eq!(send(&upstream).await.unwrap()["data"], 1);
+        assert_eq!(working.requests.load(Ordering::SeqCst), 1);
+    }
+}
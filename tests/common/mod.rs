@@ -0,0 +1,42 @@
+//! Helpers shared by the integration tests.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+/// Pokemon named `name` as the remote api returns it, built from the fixture shared with the unit tests.
+pub fn pokemon(name: &str) -> Value {
+    let mut pokemon: Value =
+        serde_json::from_str(include_str!("pokemon.json")).expect("pokemon fixture is valid JSON");
+    pokemon["name"] = json!(name);
+    pokemon["pokemon_v2_pokemonsprites"][0]["sprites"] = json!({
+        "front_default": format!("https://example.com/{name}.png"),
+        "front_shiny": format!("https://example.com/shiny/{name}.png")
+    });
+    pokemon
+}
+
+/// New directory in the system temporary directory, it's removed with its content on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "pokemon-api-{name}-{}-{}",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        std::fs::create_dir_all(&path).expect("temporary directory is created");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,253 @@
+//! Runs two replicas of the api, the second one is started without access to the remote api
+//! and gets its data and cache invalidations only from the first one.
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use common::{pokemon, TempDir};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+const PEER_SECRET: &str = "peers-test-secret";
+
+const GRANTS: &[&str] = &[
+    "svc::pokemon_api::route::/pokemon/get_all",
+    "svc::pokemon_api::route::/pokemon/get_by_name",
+    "svc::pokemon_api::admin::route::/admin/cache/entry",
+    "svc::pokemon_api::admin::route::/admin/cache/invalidate",
+];
+
+fn test_data(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("test_data")
+        .join(file)
+}
+
+fn token() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time is after unix epoch")
+        .as_secs();
+    let key = std::fs::read(test_data("certificate_key.pem")).expect("signing key is readable");
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+        &json!({ "grants": GRANTS, "sub": "peers-test", "nbf": now - 60, "exp": now + 3600 }),
+        &jsonwebtoken::EncodingKey::from_rsa_pem(&key).expect("signing key is valid"),
+    )
+    .expect("token is signed")
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("free port is found")
+        .port()
+}
+
+/// Remote api returning the dataset for the whole list query and nothing for any other.
+fn spawn_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("upstream binds");
+    let address = listener.local_addr().expect("upstream has address");
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || answer_upstream(stream));
+        }
+    });
+    format!("http://{address}")
+}
+
+fn answer_upstream(stream: TcpStream) {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap_or_default() == 0 {
+            return;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    let pokemons = if String::from_utf8_lossy(&body).contains("GetAllPokemons") {
+        vec![pokemon("gible"), pokemon("gabite"), pokemon("garchomp")]
+    } else {
+        Vec::new()
+    };
+    let body = json!({ "data": { "pokemon_v2_pokemon": pokemons } }).to_string();
+    let _ = write!(
+        reader.get_mut(),
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+}
+
+/// Running replica keeping all its files in its own directory, it's killed on drop.
+struct Replica {
+    url: String,
+    child: Child,
+}
+
+impl Replica {
+    fn spawn(root: &Path, name: &str, envs: &[(&str, String)]) -> Self {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).expect("replica directory is created");
+        let file = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let address = format!("127.0.0.1:{}", free_port());
+        let decoding_key = std::fs::read_to_string(test_data("certificate_key.pub.pem"))
+            .expect("decoding key is readable");
+        let child = Command::new(env!("CARGO_BIN_EXE_pokemon-api"))
+            .current_dir(&dir)
+            .env_clear()
+            .env("ADDRESS", &address)
+            .env("DECODING_KEY", decoding_key)
+            .env("STORE_PATH", file("store.sqlite"))
+            .env("SNAPSHOT_PATH", file("pokemon_snapshot.bin"))
+            .env("AUDIT_LOG_DIR", file("audit_log"))
+            .envs(envs.iter().map(|(key, value)| (key, value)))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("replica starts");
+        Self {
+            url: format!("http://{address}"),
+            child,
+        }
+    }
+
+    async fn wait_ready(&mut self, client: &reqwest::Client, token: &str) {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait().expect("replica status is known") {
+                panic!("replica {} exited with {status}", self.url);
+            }
+            let res = client
+                .get(format!("{}/pokemon/get_all", self.url))
+                .bearer_auth(token)
+                .send()
+                .await;
+            if matches!(res, Ok(res) if res.status().is_success()) {
+                return;
+            }
+            assert!(
+                start.elapsed() < STARTUP_TIMEOUT,
+                "replica {} isn't ready",
+                self.url
+            );
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
+
+impl Drop for Replica {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn snapshot_and_invalidations_are_shared_with_peers() {
+    // dropped last, after the replicas using it are killed
+    let dir = TempDir::new("peers");
+    let client = reqwest::Client::new();
+    let token = token();
+    let upstream = spawn_upstream();
+    let unreachable = format!("http://127.0.0.1:{}", free_port());
+
+    let mut first = Replica::spawn(
+        dir.path(),
+        "first",
+        &[
+            ("UPSTREAM_ENDPOINTS", upstream),
+            ("PREFETCH_DATA", "1".into()),
+            ("FETCH_UNVERIFIED_DATA_FROM_API", "1".into()),
+            ("PEER_SECRET", PEER_SECRET.into()),
+        ],
+    );
+    first.wait_ready(&client, &token).await;
+
+    // without verified data of its own the second replica halts unless the first one shares them
+    let mut second = Replica::spawn(
+        dir.path(),
+        "second",
+        &[
+            ("UPSTREAM_ENDPOINTS", unreachable),
+            ("UPSTREAM_TIMEOUT", "1".into()),
+            ("PEERS", first.url.clone()),
+            ("PEER_SECRET", PEER_SECRET.into()),
+        ],
+    );
+    second.wait_ready(&client, &token).await;
+
+    let get_all = |replica: &Replica| {
+        client
+            .get(format!("{}/pokemon/get_all", replica.url))
+            .bearer_auth(&token)
+            .send()
+    };
+    let first_all = get_all(&first).await.expect("first replica responds");
+    let second_all = get_all(&second).await.expect("second replica responds");
+    assert_eq!(
+        first_all.headers().get("dataset-version"),
+        second_all.headers().get("dataset-version")
+    );
+    assert!(first_all.headers().contains_key("dataset-version"));
+    assert_eq!(
+        first_all.json::<Value>().await.unwrap(),
+        second_all.json::<Value>().await.unwrap()
+    );
+
+    let res = client
+        .get(format!("{}/pokemon/get_by_name/missingno", first.url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let missing_entry = || {
+        client
+            .get(format!("{}/admin/cache/entry", first.url))
+            .query(&[("namespace", "not_found"), ("key", "pokemon/missingno")])
+            .bearer_auth(&token)
+            .send()
+    };
+    assert_eq!(missing_entry().await.unwrap().status(), StatusCode::OK);
+
+    let invalidation = client
+        .post(format!("{}/admin/cache/invalidate", second.url))
+        .query(&[("namespace", "not_found")])
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalidation.status(), StatusCode::OK);
+    let invalidation = invalidation.json::<Value>().await.unwrap();
+    assert_eq!(invalidation["failed_peers"], json!([]));
+    assert_eq!(
+        missing_entry().await.unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+}